@@ -0,0 +1,220 @@
+use crate::agent::GameAgent;
+use crate::environment::{
+    AfterstateEnvironment, Player, RewardT, State, StateId, StateKey, TwoPlayerEnvironment,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// Tabular TD(0) learning of afterstate values, as in the tic-tac-toe example
+// of Sutton & Barto (section 1.5). The value of an afterstate is stored from
+// the perspective of the player whose move produced it, so a single table
+// serves both seats during self-play. Afterstates are identified by `K`, which
+// selects the `AfterstateEnvironment` implementation used.
+#[derive(Debug, Clone)]
+pub struct AfterstateAgent<K: StateKey = usize> {
+    values: HashMap<StateId<K>, f64>,
+    step_size: f64,
+    epsilon: f64,
+    initial_value: f64,
+    // Per player: the last afterstate it produced and the reward it collected
+    // since then.
+    last_afterstates: [Option<StateId<K>>; 2],
+    pending_rewards: [f64; 2],
+    rng: StdRng,
+}
+
+impl<K: StateKey> AfterstateAgent<K> {
+    pub fn new(step_size: f64, epsilon: f64, seed: u64) -> Self {
+        AfterstateAgent {
+            values: HashMap::new(),
+            step_size,
+            epsilon,
+            initial_value: 0.0,
+            last_afterstates: [None, None],
+            pending_rewards: [0.0, 0.0],
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Value assumed for afterstates that were never updated.
+    pub fn with_initial_value(mut self, initial_value: f64) -> Self {
+        self.initial_value = initial_value;
+        self
+    }
+
+    pub fn value(&self, afterstate_id: &StateId<K>) -> f64 {
+        *self
+            .values
+            .get(afterstate_id)
+            .unwrap_or(&self.initial_value)
+    }
+
+    pub fn values(&self) -> &HashMap<StateId<K>, f64> {
+        &self.values
+    }
+
+    pub fn set_epsilon(&mut self, epsilon: f64) {
+        self.epsilon = epsilon;
+    }
+
+    fn update(&mut self, afterstate_id: StateId<K>, target: f64) {
+        let value = self.value(&afterstate_id);
+        self.values
+            .insert(afterstate_id, value + self.step_size * (target - value));
+    }
+
+    // Returns the index of the action with the most valuable afterstate,
+    // breaking ties uniformly at random.
+    fn greedy_index(&mut self, afterstate_ids: &[StateId<K>]) -> usize {
+        let values: Vec<f64> = afterstate_ids.iter().map(|id| self.value(id)).collect();
+        let best_value = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        let best_indices: Vec<usize> = (0..values.len())
+            .filter(|&i| values[i] == best_value)
+            .collect();
+        best_indices[self.rng.gen_range(0..best_indices.len())]
+    }
+}
+
+impl<K, E> GameAgent<E> for AfterstateAgent<K>
+where
+    K: StateKey,
+    E: AfterstateEnvironment<K> + TwoPlayerEnvironment,
+{
+    fn act(&mut self, env: &E, explore: bool) -> E::Action {
+        let mut actions = env.actions();
+        if actions.is_empty() {
+            panic!("tried acting in a state without actions");
+        }
+        let afterstate_ids: Vec<StateId<K>> =
+            actions.iter().map(|a| env.afterstate_id(a)).collect();
+
+        let exploratory = explore && self.rng.gen_bool(self.epsilon);
+        let index = if exploratory {
+            self.rng.gen_range(0..actions.len())
+        } else {
+            self.greedy_index(&afterstate_ids)
+        };
+        let afterstate_id = afterstate_ids[index].clone();
+
+        if explore {
+            let player = env.current_player().index();
+            // Like in the book, exploratory moves don't lead to updates.
+            match self.last_afterstates[player].take() {
+                Some(last) if !exploratory => {
+                    let target = self.pending_rewards[player] + self.value(&afterstate_id);
+                    self.update(last, target);
+                }
+                _ => {}
+            }
+            self.last_afterstates[player] = Some(afterstate_id);
+            self.pending_rewards[player] = 0.0;
+        }
+        actions.swap_remove(index)
+    }
+
+    fn observe(&mut self, player: Player, env: &E, reward: RewardT) {
+        self.pending_rewards[player.index()] += reward.0;
+        if !env.state().is_terminal() {
+            return;
+        }
+        if let Some(last) = self.last_afterstates[player.index()].clone() {
+            self.update(last, self.pending_rewards[player.index()]);
+        }
+    }
+
+    fn end_episode(&mut self, player: Player) {
+        self.last_afterstates[player.index()] = None;
+        self.pending_rewards[player.index()] = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RandomAgent;
+    use crate::environment::Environment;
+    use crate::selfplay::{play_match, SelfPlay, SelfPlayConfig};
+    use crate::tictactoe::action::TicTacToeAction;
+    use crate::tictactoe::board::{BoardKey, BoardShape};
+    use crate::tictactoe::cell::CellValue;
+    use crate::tictactoe::environment::TicTacToeEnvironment;
+    use crate::tictactoe::state::TicTacToeState;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn greedy_action_uses_afterstate_values() {
+        let env = TicTacToeEnvironment::new();
+        let mut agent: AfterstateAgent = AfterstateAgent::new(0.1, 0.0, 0);
+        let center = TicTacToeState::empty(BoardShape::tic_tac_toe())
+            .apply_action(&TicTacToeAction::new(CellValue::Cross, 4));
+        agent.values.insert(center.id(), 1.0);
+
+        let action = agent.act(&env, false);
+        assert_eq!(action, TicTacToeAction::new(CellValue::Cross, 4));
+        // Greedy moves outside of training don't touch the table.
+        assert_eq!(agent.values().len(), 1);
+    }
+
+    #[test]
+    fn terminal_rewards_are_backed_up() {
+        let mut agent: AfterstateAgent = AfterstateAgent::new(0.5, 0.0, 0);
+        let mut env = TicTacToeEnvironment::new();
+        let moves = [0, 3, 1, 4, 2];
+        let mut afterstates = vec![];
+        for index in moves {
+            let player = env.current_player();
+            let value = match player {
+                Player::First => CellValue::Cross,
+                Player::Second => CellValue::Circle,
+            };
+            let action = TicTacToeAction::new(value, index);
+            afterstates.push(env.afterstate_id(&action));
+            // Force the scripted move by making its afterstate the only
+            // attractive one.
+            agent.values.insert(*afterstates.last().unwrap(), 0.01);
+            let chosen = GameAgent::<TicTacToeEnvironment>::act(&mut agent, &env, true);
+            assert_eq!(chosen, action);
+            let reward = env.apply_action(&chosen);
+            for p in [Player::First, Player::Second] {
+                agent.observe(p, &env, p.perspective(reward));
+            }
+        }
+
+        // Crosses won with their last move, the circles' last afterstate gets
+        // pulled towards the loss.
+        assert!((agent.value(&afterstates[4]) - 0.505).abs() < 1e-9);
+        assert!((agent.value(&afterstates[3]) + 0.495).abs() < 1e-9);
+    }
+
+    #[test]
+    fn self_play_learns_to_beat_random_player() {
+        let config = SelfPlayConfig {
+            episodes: 5000,
+            eval_interval: 0,
+            ..Default::default()
+        };
+        let mut self_play: SelfPlay<TicTacToeEnvironment, AfterstateAgent> =
+            SelfPlay::new(AfterstateAgent::new(0.2, 0.1, 0), config);
+        self_play.train();
+        let mut agent = self_play.into_learner();
+
+        let mut random = RandomAgent::new(0);
+        let result = play_match(TicTacToeEnvironment::new, &mut agent, &mut random, 200);
+        assert!(result.score() > 0.85, "result={:?}", result);
+    }
+
+    #[test]
+    fn learns_board_keys_on_larger_boards() {
+        let config = SelfPlayConfig {
+            episodes: 10,
+            eval_interval: 0,
+            ..Default::default()
+        };
+        let mut self_play: SelfPlay<TicTacToeEnvironment, AfterstateAgent<BoardKey>> =
+            SelfPlay::new(AfterstateAgent::new(0.2, 0.1, 0), config)
+                .with_environment(|| TicTacToeEnvironment::with_shape(BoardShape::new(5, 5, 4)));
+        self_play.train();
+        assert!(!self_play.learner().values().is_empty());
+    }
+}
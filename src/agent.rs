@@ -0,0 +1,56 @@
+use crate::environment::{Player, RewardT, TwoPlayerEnvironment};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// An agent occupying one or both seats of a two-player game. The player to
+// move is available through `TwoPlayerEnvironment::current_player`.
+pub trait GameAgent<E: TwoPlayerEnvironment> {
+    // Picks an action for the player to move. Exploration should only happen
+    // when `explore` is set, evaluation games call this with `explore=false`.
+    fn act(&mut self, env: &E, explore: bool) -> E::Action;
+
+    // Called after every move of a game the agent learns from, once per seat
+    // the agent occupies. The reward is given from `player`'s perspective.
+    fn observe(&mut self, _player: Player, _env: &E, _reward: RewardT) {}
+
+    // Called once per occupied seat after the game reached a terminal state.
+    fn end_episode(&mut self, _player: Player) {}
+}
+
+#[derive(Debug, Clone)]
+pub struct RandomAgent {
+    rng: StdRng,
+}
+
+impl RandomAgent {
+    pub fn new(seed: u64) -> Self {
+        RandomAgent {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl<E: TwoPlayerEnvironment> GameAgent<E> for RandomAgent {
+    fn act(&mut self, env: &E, _explore: bool) -> E::Action {
+        let mut actions = env.actions();
+        if actions.is_empty() {
+            panic!("tried acting in a state without actions");
+        }
+        let index = self.rng.gen_range(0..actions.len());
+        actions.swap_remove(index)
+    }
+}
+
+// Plays the first action offered by the environment, handy as a fully
+// deterministic opponent.
+#[derive(Debug, Clone, Default)]
+pub struct FirstActionAgent;
+
+impl<E: TwoPlayerEnvironment> GameAgent<E> for FirstActionAgent {
+    fn act(&mut self, env: &E, _explore: bool) -> E::Action {
+        env.actions()
+            .into_iter()
+            .next()
+            .expect("tried acting in a state without actions")
+    }
+}
@@ -0,0 +1,369 @@
+// Continuing tasks under the average-reward criterion. Instead of by their
+// discounted return, policies are compared by their gain, the reward per step
+// in the long run, and states by their differential values: how much more
+// reward than the gain they collect until that evens out. Differential values
+// are only defined up to a constant, so they're relative to a reference
+// state. This assumes every policy reaches a single recurrent class of states
+// (a unichain MDP). States without transitions stay put without reward.
+use crate::dp::{action_values, DPConfig, Policy, TransitionTable, ValueTable};
+use crate::environment::{ActionId, RewardT, StateId, StateKey, StateTransition};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// Every backup first stays put with this probability. That scales the gain
+// down but leaves differential values and greedy policies alone, and keeps
+// the sweeps from oscillating on periodic chains.
+const LAZINESS: f64 = 0.5;
+
+// Relative value iteration: value iteration without discounting, where the
+// value of `reference` is subtracted from all values after every sweep to keep
+// them bounded. Returns the optimal gain and the differential values with
+// `reference` at zero, `greedy_policy` with a discount of 1 turns them into an
+// optimal policy. Uses `config.theta` and `config.max_sweeps`, there's no
+// discount.
+pub fn relative_value_iteration<K: StateKey>(
+    table: &TransitionTable<K>,
+    config: &DPConfig,
+    reference: &StateId<K>,
+) -> (f64, ValueTable<K>) {
+    relative_sweeps(table, config, reference, |_, transitions, values| {
+        action_values(transitions, values, 1.0)
+            .into_iter()
+            .map(|(_, value)| value)
+            .fold(f64::NEG_INFINITY, f64::max)
+    })
+}
+
+// The gain and differential values of a deterministic policy. States the
+// policy doesn't cover have to be without transitions.
+pub fn average_reward_policy_evaluation<K: StateKey>(
+    table: &TransitionTable<K>,
+    policy: &Policy<K>,
+    config: &DPConfig,
+    reference: &StateId<K>,
+) -> (f64, ValueTable<K>) {
+    relative_sweeps(table, config, reference, |state_id, transitions, values| {
+        let action_id = policy
+            .get(state_id)
+            .unwrap_or_else(|| panic!("no action for non-terminal state {:?}", state_id));
+        action_values(transitions, values, 1.0)
+            .into_iter()
+            .find(|(id, _)| id == action_id)
+            .unwrap_or_else(|| panic!("{:?} is not available in {:?}", action_id, state_id))
+            .1
+    })
+}
+
+// Runs synchronous sweeps until the change of the values, which converges to
+// the gain in every state, is the same everywhere up to `config.theta`.
+// `backup` computes the undiscounted expected return of a state.
+fn relative_sweeps<K, F>(
+    table: &TransitionTable<K>,
+    config: &DPConfig,
+    reference: &StateId<K>,
+    backup: F,
+) -> (f64, ValueTable<K>)
+where
+    K: StateKey,
+    F: Fn(&StateId<K>, &[StateTransition<K>], &ValueTable<K>) -> f64,
+{
+    if !table.contains_key(reference) {
+        panic!("reference state {:?} is not in the table", reference);
+    }
+    let mut values: ValueTable<K> = table.keys().map(|id| (id.clone(), 0.0)).collect();
+    let mut gain = 0.0;
+    for _ in 0..config.max_sweeps {
+        let updated: ValueTable<K> = table
+            .iter()
+            .map(|(state_id, transitions)| {
+                let value = values[state_id];
+                let backed_up = if transitions.is_empty() {
+                    value
+                } else {
+                    backup(state_id, transitions, &values)
+                };
+                (
+                    state_id.clone(),
+                    LAZINESS * value + (1.0 - LAZINESS) * backed_up,
+                )
+            })
+            .collect();
+        let (mut min_change, mut max_change) = (f64::INFINITY, f64::NEG_INFINITY);
+        for (state_id, value) in updated.iter() {
+            let change = value - values[state_id];
+            min_change = min_change.min(change);
+            max_change = max_change.max(change);
+        }
+        // The gain of the lazy chain is scaled down by `1 - LAZINESS`.
+        gain = (min_change + max_change) / 2.0 / (1.0 - LAZINESS);
+        let offset = updated[reference];
+        values = updated
+            .into_iter()
+            .map(|(state_id, value)| (state_id, value - offset))
+            .collect();
+        if max_change - min_change <= config.theta {
+            break;
+        }
+    }
+    (gain, values)
+}
+
+// Tabular differential SARSA (Sutton & Barto, section 10.3), semi-gradient
+// SARSA for continuing tasks with one feature per state and action. The
+// average reward, learned along the way, is subtracted from every reward.
+#[derive(Debug)]
+pub struct DifferentialSarsa<K: StateKey = usize> {
+    step_size: f64,
+    average_step_size: f64,
+    epsilon: f64,
+    average_reward: f64,
+    q_values: HashMap<(StateId<K>, ActionId), f64>,
+    rng: StdRng,
+}
+
+impl<K: StateKey> DifferentialSarsa<K> {
+    pub fn new(step_size: f64, average_step_size: f64, epsilon: f64, seed: u64) -> Self {
+        DifferentialSarsa {
+            step_size,
+            average_step_size,
+            epsilon,
+            average_reward: 0.0,
+            q_values: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn average_reward(&self) -> f64 {
+        self.average_reward
+    }
+
+    pub fn q_value(&self, state_id: &StateId<K>, action_id: ActionId) -> f64 {
+        q_value(&self.q_values, state_id, action_id)
+    }
+
+    pub fn greedy_action(&self, state_id: &StateId<K>, action_ids: &[ActionId]) -> ActionId {
+        greedy_action(&self.q_values, state_id, action_ids)
+    }
+
+    pub fn select_action(&mut self, state_id: &StateId<K>, action_ids: &[ActionId]) -> ActionId {
+        epsilon_greedy(
+            &self.q_values,
+            state_id,
+            action_ids,
+            self.epsilon,
+            &mut self.rng,
+        )
+    }
+
+    // Learns from a step and the action already selected for the next one.
+    pub fn learn(
+        &mut self,
+        state_id: &StateId<K>,
+        action_id: ActionId,
+        reward: RewardT,
+        next_state_id: &StateId<K>,
+        next_action_id: ActionId,
+    ) {
+        let error = reward.0 - self.average_reward + self.q_value(next_state_id, next_action_id)
+            - self.q_value(state_id, action_id);
+        self.average_reward += self.average_step_size * error;
+        *self
+            .q_values
+            .entry((state_id.clone(), action_id))
+            .or_insert(0.0) += self.step_size * error;
+    }
+}
+
+// R-learning (Schwartz, 1993): the off-policy counterpart of differential
+// SARSA, which learns the values of the greedy policy like Q-learning does.
+// The average reward is only updated after greedy actions.
+#[derive(Debug)]
+pub struct RLearning<K: StateKey = usize> {
+    step_size: f64,
+    average_step_size: f64,
+    epsilon: f64,
+    average_reward: f64,
+    q_values: HashMap<(StateId<K>, ActionId), f64>,
+    rng: StdRng,
+}
+
+impl<K: StateKey> RLearning<K> {
+    pub fn new(step_size: f64, average_step_size: f64, epsilon: f64, seed: u64) -> Self {
+        RLearning {
+            step_size,
+            average_step_size,
+            epsilon,
+            average_reward: 0.0,
+            q_values: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn average_reward(&self) -> f64 {
+        self.average_reward
+    }
+
+    pub fn q_value(&self, state_id: &StateId<K>, action_id: ActionId) -> f64 {
+        q_value(&self.q_values, state_id, action_id)
+    }
+
+    pub fn greedy_action(&self, state_id: &StateId<K>, action_ids: &[ActionId]) -> ActionId {
+        greedy_action(&self.q_values, state_id, action_ids)
+    }
+
+    pub fn select_action(&mut self, state_id: &StateId<K>, action_ids: &[ActionId]) -> ActionId {
+        epsilon_greedy(
+            &self.q_values,
+            state_id,
+            action_ids,
+            self.epsilon,
+            &mut self.rng,
+        )
+    }
+
+    // Learns from taking `action_id` out of `action_ids` in `state_id`, the
+    // actions of the next state are `next_action_ids`.
+    pub fn learn(
+        &mut self,
+        state_id: &StateId<K>,
+        action_ids: &[ActionId],
+        action_id: ActionId,
+        reward: RewardT,
+        next_state_id: &StateId<K>,
+        next_action_ids: &[ActionId],
+    ) {
+        let max_value = |state_id: &StateId<K>, action_ids: &[ActionId]| {
+            action_ids
+                .iter()
+                .map(|action_id| self.q_value(state_id, *action_id))
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+        let next_value = max_value(next_state_id, next_action_ids);
+        let value = max_value(state_id, action_ids);
+        let was_greedy = self.q_value(state_id, action_id) >= value;
+        let q_value = self
+            .q_values
+            .entry((state_id.clone(), action_id))
+            .or_insert(0.0);
+        *q_value += self.step_size * (reward.0 - self.average_reward + next_value - *q_value);
+        if was_greedy {
+            self.average_reward +=
+                self.average_step_size * (reward.0 - self.average_reward + next_value - value);
+        }
+    }
+}
+
+fn q_value<K: StateKey>(
+    q_values: &HashMap<(StateId<K>, ActionId), f64>,
+    state_id: &StateId<K>,
+    action_id: ActionId,
+) -> f64 {
+    *q_values.get(&(state_id.clone(), action_id)).unwrap_or(&0.0)
+}
+
+// Ties go to the action appearing first.
+fn greedy_action<K: StateKey>(
+    q_values: &HashMap<(StateId<K>, ActionId), f64>,
+    state_id: &StateId<K>,
+    action_ids: &[ActionId],
+) -> ActionId {
+    let mut best: Option<(ActionId, f64)> = None;
+    for action_id in action_ids {
+        let value = q_value(q_values, state_id, *action_id);
+        if best.is_none_or(|(_, best_value)| value > best_value) {
+            best = Some((*action_id, value));
+        }
+    }
+    best.expect("tried acting in a state without actions").0
+}
+
+fn epsilon_greedy<K: StateKey>(
+    q_values: &HashMap<(StateId<K>, ActionId), f64>,
+    state_id: &StateId<K>,
+    action_ids: &[ActionId],
+    epsilon: f64,
+    rng: &mut StdRng,
+) -> ActionId {
+    if !action_ids.is_empty() && rng.gen::<f64>() < epsilon {
+        return action_ids[rng.gen_range(0..action_ids.len())];
+    }
+    greedy_action(q_values, state_id, action_ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::greedy_policy;
+    use crate::environment::ProbabilityT;
+    use pretty_assertions::assert_eq;
+
+    // State 0 either stays for a reward of 1 or moves on to state 1 for
+    // nothing, state 1 always returns for a reward of 3. Cycling between
+    // both gains 1.5 per step.
+    fn two_state_table() -> TransitionTable {
+        let transition = |action, new_state, reward| StateTransition {
+            action_id: ActionId(action),
+            new_state_id: StateId(new_state),
+            reward: RewardT(reward),
+            prob: ProbabilityT(1.0),
+        };
+        HashMap::from([
+            (
+                StateId(0),
+                vec![transition(0, 0, 1.0), transition(1, 1, 0.0)],
+            ),
+            (StateId(1), vec![transition(0, 0, 3.0)]),
+        ])
+    }
+
+    #[test]
+    fn relative_values_of_a_periodic_cycle() {
+        let table = two_state_table();
+        let config = DPConfig::default();
+        let (gain, values) = relative_value_iteration(&table, &config, &StateId(0));
+        assert!((gain - 1.5).abs() < 1e-6);
+        assert_eq!(values[&StateId(0)], 0.0);
+        // Being in state 1 means getting 3 next instead of the 1.5 on average.
+        assert!((values[&StateId(1)] - 1.5).abs() < 1e-6);
+        let policy = greedy_policy(&table, &values, 1.0);
+        assert_eq!(policy[&StateId(0)], ActionId(1));
+
+        let staying = HashMap::from([(StateId(0), ActionId(0)), (StateId(1), ActionId(0))]);
+        let (gain, _) = average_reward_policy_evaluation(&table, &staying, &config, &StateId(0));
+        assert!((gain - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn r_learning_learns_the_gain() {
+        let table = two_state_table();
+        let actions = |state_id: &StateId| -> Vec<ActionId> {
+            table[state_id].iter().map(|t| t.action_id).collect()
+        };
+        let mut agent = RLearning::new(0.1, 0.01, 0.1, 0);
+        let mut state_id = StateId(0);
+        for _ in 0..20000 {
+            let action_ids = actions(&state_id);
+            let action_id = agent.select_action(&state_id, &action_ids);
+            let transition = table[&state_id]
+                .iter()
+                .find(|t| t.action_id == action_id)
+                .unwrap();
+            let next_state_id = transition.new_state_id;
+            agent.learn(
+                &state_id,
+                &action_ids,
+                action_id,
+                transition.reward,
+                &next_state_id,
+                &actions(&next_state_id),
+            );
+            state_id = next_state_id;
+        }
+        assert_eq!(
+            agent.greedy_action(&StateId(0), &actions(&StateId(0))),
+            ActionId(1)
+        );
+        assert!((agent.average_reward() - 1.5).abs() < 0.1);
+    }
+}
@@ -0,0 +1,440 @@
+use crate::bandit::environment::{BanditAction, BanditEnvironment};
+use crate::distributions;
+use crate::environment::Environment;
+use itertools::Itertools;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub trait BanditAlgorithm {
+    fn select(&mut self) -> BanditAction;
+    fn update(&mut self, action: BanditAction, reward: f64);
+}
+
+// Plays `steps` rounds and returns the total reward, the regret is tracked by
+// the environment.
+pub fn run<A: BanditAlgorithm + ?Sized>(
+    env: &mut BanditEnvironment,
+    algorithm: &mut A,
+    steps: usize,
+) -> f64 {
+    let mut total_reward = 0.0;
+    for _ in 0..steps {
+        let action = algorithm.select();
+        let reward = env.apply_action(&action);
+        algorithm.update(action, reward.0);
+        total_reward += reward.0;
+    }
+    total_reward
+}
+
+// Index of the largest value, breaking ties uniformly at random.
+fn argmax(values: &[f64], rng: &mut StdRng) -> usize {
+    let best_value = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let best_indices: Vec<usize> = (0..values.len())
+        .filter(|&i| values[i] == best_value)
+        .collect();
+    best_indices[rng.gen_range(0..best_indices.len())]
+}
+
+// Action value estimates shared by the value based algorithms, either sample
+// averages or exponential recency-weighted averages for nonstationary arms.
+#[derive(Debug, Clone)]
+struct ActionValues {
+    estimates: Vec<f64>,
+    counts: Vec<usize>,
+    step_size: Option<f64>,
+}
+
+impl ActionValues {
+    fn new(num_arms: usize, initial_value: f64, step_size: Option<f64>) -> Self {
+        ActionValues {
+            estimates: vec![initial_value; num_arms],
+            counts: vec![0; num_arms],
+            step_size,
+        }
+    }
+
+    fn update(&mut self, arm: usize, reward: f64) {
+        self.counts[arm] += 1;
+        let step_size = self.step_size.unwrap_or(1.0 / self.counts[arm] as f64);
+        self.estimates[arm] += step_size * (reward - self.estimates[arm]);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EpsilonGreedy {
+    epsilon: f64,
+    values: ActionValues,
+    rng: StdRng,
+}
+
+impl EpsilonGreedy {
+    pub fn new(num_arms: usize, epsilon: f64, seed: u64) -> Self {
+        EpsilonGreedy {
+            epsilon,
+            values: ActionValues::new(num_arms, 0.0, None),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Optimistic initial values encourage exploration early on. Sample
+    // averages forget them after the first pull, so they are best combined
+    // with a constant step size.
+    pub fn with_initial_value(mut self, initial_value: f64) -> Self {
+        self.values.estimates.fill(initial_value);
+        self
+    }
+
+    // A constant step size instead of sample averages, for nonstationary
+    // arms.
+    pub fn with_step_size(mut self, step_size: f64) -> Self {
+        self.values.step_size = Some(step_size);
+        self
+    }
+
+    pub fn estimates(&self) -> &[f64] {
+        &self.values.estimates
+    }
+}
+
+impl BanditAlgorithm for EpsilonGreedy {
+    fn select(&mut self) -> BanditAction {
+        let num_arms = self.values.estimates.len();
+        if self.rng.gen::<f64>() < self.epsilon {
+            return BanditAction(self.rng.gen_range(0..num_arms));
+        }
+        BanditAction(argmax(&self.values.estimates, &mut self.rng))
+    }
+
+    fn update(&mut self, action: BanditAction, reward: f64) {
+        self.values.update(action.0, reward);
+    }
+}
+
+// Upper confidence bounds of Auer et al. (2002). Every arm is played once
+// before the bounds are used.
+#[derive(Debug, Clone)]
+pub struct Ucb1 {
+    exploration: f64,
+    values: ActionValues,
+    steps: usize,
+}
+
+impl Ucb1 {
+    pub fn new(num_arms: usize) -> Self {
+        Ucb1::with_exploration(num_arms, 2.0_f64.sqrt())
+    }
+
+    // Scales the confidence bonus, `sqrt(2)` is the original UCB1.
+    pub fn with_exploration(num_arms: usize, exploration: f64) -> Self {
+        Ucb1 {
+            exploration,
+            values: ActionValues::new(num_arms, 0.0, None),
+            steps: 0,
+        }
+    }
+}
+
+impl BanditAlgorithm for Ucb1 {
+    fn select(&mut self) -> BanditAction {
+        if let Some(arm) = self.values.counts.iter().position(|&count| count == 0) {
+            return BanditAction(arm);
+        }
+        let log_steps = (self.steps as f64).ln();
+        let bounds: Vec<f64> = self
+            .values
+            .estimates
+            .iter()
+            .zip(self.values.counts.iter())
+            .map(|(estimate, &count)| {
+                estimate + self.exploration * (log_steps / count as f64).sqrt()
+            })
+            .collect();
+        let best = bounds.iter().position_max_by(|a, b| a.total_cmp(b));
+        BanditAction(best.expect("a bandit has at least one arm"))
+    }
+
+    fn update(&mut self, action: BanditAction, reward: f64) {
+        self.steps += 1;
+        self.values.update(action.0, reward);
+    }
+}
+
+// Learns action preferences by stochastic gradient ascent on the expected
+// reward of a softmax policy (Sutton & Barto, section 2.8).
+#[derive(Debug, Clone)]
+pub struct GradientBandit {
+    step_size: f64,
+    preferences: Vec<f64>,
+    use_baseline: bool,
+    average_reward: f64,
+    steps: usize,
+    rng: StdRng,
+}
+
+impl GradientBandit {
+    pub fn new(num_arms: usize, step_size: f64, seed: u64) -> Self {
+        GradientBandit {
+            step_size,
+            preferences: vec![0.0; num_arms],
+            use_baseline: true,
+            average_reward: 0.0,
+            steps: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn without_baseline(mut self) -> Self {
+        self.use_baseline = false;
+        self
+    }
+
+    pub fn probabilities(&self) -> Vec<f64> {
+        let max = self
+            .preferences
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let exps: Vec<f64> = self.preferences.iter().map(|h| (h - max).exp()).collect();
+        let total: f64 = exps.iter().sum();
+        exps.iter().map(|e| e / total).collect()
+    }
+}
+
+impl BanditAlgorithm for GradientBandit {
+    fn select(&mut self) -> BanditAction {
+        let mut sample: f64 = self.rng.gen();
+        let probabilities = self.probabilities();
+        let arm = probabilities
+            .iter()
+            .position(|&p| {
+                sample -= p;
+                sample < 0.0
+            })
+            .unwrap_or(probabilities.len() - 1);
+        BanditAction(arm)
+    }
+
+    fn update(&mut self, action: BanditAction, reward: f64) {
+        self.steps += 1;
+        if self.use_baseline {
+            self.average_reward += (reward - self.average_reward) / self.steps as f64;
+        }
+        let advantage = reward - self.average_reward;
+        let probabilities = self.probabilities();
+        for (arm, (preference, probability)) in
+            self.preferences.iter_mut().zip(probabilities).enumerate()
+        {
+            let indicator = if arm == action.0 { 1.0 } else { 0.0 };
+            *preference += self.step_size * advantage * (indicator - probability);
+        }
+    }
+}
+
+// Thompson sampling with Beta posteriors for rewards in [0, 1]. Rewards
+// between 0 and 1 count as a success with that probability, as proposed by
+// Agrawal and Goyal (2012). `GaussianThompsonSampling` handles unbounded
+// rewards.
+#[derive(Debug, Clone)]
+pub struct ThompsonSampling {
+    successes: Vec<f64>,
+    failures: Vec<f64>,
+    rng: StdRng,
+}
+
+impl ThompsonSampling {
+    // Starts from a uniform Beta(1, 1) prior for every arm.
+    pub fn new(num_arms: usize, seed: u64) -> Self {
+        ThompsonSampling {
+            successes: vec![1.0; num_arms],
+            failures: vec![1.0; num_arms],
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl BanditAlgorithm for ThompsonSampling {
+    fn select(&mut self) -> BanditAction {
+        let samples: Vec<f64> = self
+            .successes
+            .iter()
+            .zip(self.failures.iter())
+            .map(|(&alpha, &beta)| distributions::beta(&mut self.rng, alpha, beta))
+            .collect();
+        BanditAction(argmax(&samples, &mut self.rng))
+    }
+
+    fn update(&mut self, action: BanditAction, reward: f64) {
+        if !(0.0..=1.0).contains(&reward) {
+            panic!(
+                "Beta-Bernoulli Thompson sampling needs rewards in [0, 1], got {}",
+                reward
+            );
+        }
+        if self.rng.gen_bool(reward) {
+            self.successes[action.0] += 1.0;
+        } else {
+            self.failures[action.0] += 1.0;
+        }
+    }
+}
+
+// Thompson sampling with Normal posteriors for Gaussian rewards of a known
+// variance. Every arm starts from the same Normal prior on its mean.
+#[derive(Debug, Clone)]
+pub struct GaussianThompsonSampling {
+    prior_mean: f64,
+    prior_variance: f64,
+    noise_variance: f64,
+    reward_sums: Vec<f64>,
+    counts: Vec<f64>,
+    rng: StdRng,
+}
+
+impl GaussianThompsonSampling {
+    // A standard normal prior and unit reward variance, which matches the
+    // Gaussian testbed.
+    pub fn new(num_arms: usize, seed: u64) -> Self {
+        GaussianThompsonSampling {
+            prior_mean: 0.0,
+            prior_variance: 1.0,
+            noise_variance: 1.0,
+            reward_sums: vec![0.0; num_arms],
+            counts: vec![0.0; num_arms],
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn with_prior(mut self, mean: f64, std_dev: f64) -> Self {
+        if std_dev <= 0.0 {
+            panic!(
+                "the prior needs a positive standard deviation, got {}",
+                std_dev
+            );
+        }
+        self.prior_mean = mean;
+        self.prior_variance = std_dev * std_dev;
+        self
+    }
+
+    pub fn with_reward_std_dev(mut self, std_dev: f64) -> Self {
+        if std_dev <= 0.0 {
+            panic!(
+                "rewards need a positive standard deviation, got {}",
+                std_dev
+            );
+        }
+        self.noise_variance = std_dev * std_dev;
+        self
+    }
+
+    // The mean and variance of the posterior of an arm's mean.
+    pub fn posterior(&self, action: BanditAction) -> (f64, f64) {
+        let precision = 1.0 / self.prior_variance + self.counts[action.0] / self.noise_variance;
+        let mean = (self.prior_mean / self.prior_variance
+            + self.reward_sums[action.0] / self.noise_variance)
+            / precision;
+        (mean, 1.0 / precision)
+    }
+}
+
+impl BanditAlgorithm for GaussianThompsonSampling {
+    fn select(&mut self) -> BanditAction {
+        let samples: Vec<f64> = (0..self.counts.len())
+            .map(|arm| {
+                let (mean, variance) = self.posterior(BanditAction(arm));
+                distributions::normal(&mut self.rng, mean, variance.sqrt())
+            })
+            .collect();
+        BanditAction(argmax(&samples, &mut self.rng))
+    }
+
+    fn update(&mut self, action: BanditAction, reward: f64) {
+        self.reward_sums[action.0] += reward;
+        self.counts[action.0] += 1.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBS: [f64; 4] = [0.1, 0.3, 0.5, 0.7];
+    const STEPS: usize = 3000;
+
+    // Pulling arms uniformly at random loses 0.3 per step. The environment
+    // uses a different seed than the algorithms, identically seeded random
+    // number generators would correlate rewards and choices.
+    fn regret(algorithm: &mut dyn BanditAlgorithm) -> f64 {
+        let mut env = BanditEnvironment::bernoulli(&PROBS, 100);
+        run(&mut env, algorithm, STEPS);
+        env.regret().total() / STEPS as f64
+    }
+
+    #[test]
+    fn algorithms_beat_random_play() {
+        let algorithms: Vec<(&str, Box<dyn BanditAlgorithm>)> = vec![
+            ("epsilon greedy", Box::new(EpsilonGreedy::new(4, 0.1, 0))),
+            (
+                "optimistic greedy",
+                Box::new(
+                    EpsilonGreedy::new(4, 0.0, 0)
+                        .with_initial_value(1.0)
+                        .with_step_size(0.1),
+                ),
+            ),
+            ("ucb1", Box::new(Ucb1::new(4))),
+            ("gradient", Box::new(GradientBandit::new(4, 0.1, 0))),
+            ("thompson", Box::new(ThompsonSampling::new(4, 0))),
+        ];
+        for (name, mut algorithm) in algorithms {
+            let regret = regret(algorithm.as_mut());
+            assert!(regret < 0.1, "{} has regret {} per step", name, regret);
+        }
+    }
+
+    #[test]
+    fn thompson_sampling_beats_epsilon_greedy() {
+        let thompson = regret(&mut ThompsonSampling::new(4, 1));
+        let epsilon_greedy = regret(&mut EpsilonGreedy::new(4, 0.1, 1));
+        assert!(thompson < epsilon_greedy, "{} {}", thompson, epsilon_greedy);
+    }
+
+    #[test]
+    fn gaussian_thompson_sampling_on_the_testbed() {
+        let regret = |algorithm: &mut dyn BanditAlgorithm| {
+            let mut env = BanditEnvironment::gaussian_testbed(10, 100);
+            run(&mut env, algorithm, STEPS);
+            env.regret().total() / STEPS as f64
+        };
+        let thompson = regret(&mut GaussianThompsonSampling::new(10, 3));
+        let epsilon_greedy = regret(&mut EpsilonGreedy::new(10, 0.1, 3));
+        assert!(thompson < epsilon_greedy, "{} {}", thompson, epsilon_greedy);
+    }
+
+    #[test]
+    fn gaussian_posteriors() {
+        let mut algorithm = GaussianThompsonSampling::new(2, 0).with_prior(1.0, 2.0);
+        assert_eq!(algorithm.posterior(BanditAction(0)), (1.0, 4.0));
+        algorithm.update(BanditAction(0), 3.0);
+        // Precision 1/4 + 1 and mean (1/4 + 3) / (5/4).
+        let (mean, variance) = algorithm.posterior(BanditAction(0));
+        assert!((mean - 2.6).abs() < 1e-12);
+        assert!((variance - 0.8).abs() < 1e-12);
+        assert_eq!(algorithm.posterior(BanditAction(1)), (1.0, 4.0));
+    }
+
+    #[test]
+    fn constant_step_size_tracks_drift() {
+        let mut env = BanditEnvironment::gaussian_testbed(10, 100).with_drift(0.05);
+        let mut algorithm = EpsilonGreedy::new(10, 0.1, 2).with_step_size(0.1);
+        run(&mut env, &mut algorithm, 5000);
+        let best = env
+            .arms()
+            .iter()
+            .map(|arm| arm.mean())
+            .fold(f64::NEG_INFINITY, f64::max);
+        let greedy = argmax(algorithm.estimates(), &mut StdRng::seed_from_u64(0));
+        assert!(best - env.arms()[greedy].mean() < 0.5);
+    }
+}
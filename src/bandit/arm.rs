@@ -0,0 +1,39 @@
+use crate::distributions;
+use rand::Rng;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arm {
+    Gaussian { mean: f64, std_dev: f64 },
+    // Pays 1 with probability `p` and 0 otherwise.
+    Bernoulli { p: f64 },
+}
+
+impl Arm {
+    pub fn mean(&self) -> f64 {
+        match *self {
+            Arm::Gaussian { mean, .. } => mean,
+            Arm::Bernoulli { p } => p,
+        }
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> f64 {
+        match *self {
+            Arm::Gaussian { mean, std_dev } => distributions::normal(rng, mean, std_dev),
+            Arm::Bernoulli { p } => {
+                if rng.gen_bool(p) {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    // Shifts the mean of the arm, Bernoulli arms stay within [0, 1].
+    pub fn drift(&mut self, delta: f64) {
+        match self {
+            Arm::Gaussian { mean, .. } => *mean += delta,
+            Arm::Bernoulli { p } => *p = (*p + delta).clamp(0.0, 1.0),
+        }
+    }
+}
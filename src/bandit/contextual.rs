@@ -0,0 +1,123 @@
+use crate::bandit::environment::BanditAction;
+use crate::bandit::regret::Regret;
+use crate::distributions;
+use crate::environment::{Environment, RewardT, State};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+// The context observed before choosing an arm.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextState {
+    context: Vec<f64>,
+}
+
+impl ContextState {
+    pub fn context(&self) -> &[f64] {
+        &self.context
+    }
+}
+
+impl State for ContextState {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+// A contextual bandit whose expected rewards are linear in the context: every
+// arm has hidden weights and pays `weights · context` plus Gaussian noise.
+// Contexts are drawn from a standard normal distribution at every step.
+#[derive(Debug)]
+pub struct LinearBanditEnvironment {
+    weights: Vec<Vec<f64>>,
+    noise_std_dev: f64,
+    state: ContextState,
+    regret: Regret,
+    rng: StdRng,
+}
+
+impl LinearBanditEnvironment {
+    pub fn new(weights: Vec<Vec<f64>>, noise_std_dev: f64, seed: u64) -> Self {
+        if weights.is_empty() || weights[0].is_empty() {
+            panic!("a linear bandit needs at least one arm and one feature");
+        }
+        if weights.iter().any(|w| w.len() != weights[0].len()) {
+            panic!("all arms need weights for the same number of features");
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let state = LinearBanditEnvironment::draw_context(weights[0].len(), &mut rng);
+        LinearBanditEnvironment {
+            weights,
+            noise_std_dev,
+            state,
+            regret: Regret::default(),
+            rng,
+        }
+    }
+
+    // Weights are drawn from a normal distribution scaled so that expected
+    // rewards have unit variance.
+    pub fn random(num_arms: usize, dim: usize, noise_std_dev: f64, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let scale = 1.0 / (dim as f64).sqrt();
+        let weights = (0..num_arms)
+            .map(|_| {
+                (0..dim)
+                    .map(|_| distributions::normal(&mut rng, 0.0, scale))
+                    .collect()
+            })
+            .collect();
+        LinearBanditEnvironment::new(weights, noise_std_dev, seed.wrapping_add(1))
+    }
+
+    fn draw_context(dim: usize, rng: &mut StdRng) -> ContextState {
+        ContextState {
+            context: (0..dim)
+                .map(|_| distributions::standard_normal(rng))
+                .collect(),
+        }
+    }
+
+    pub fn num_arms(&self) -> usize {
+        self.weights.len()
+    }
+
+    pub fn dim(&self) -> usize {
+        self.weights[0].len()
+    }
+
+    pub fn regret(&self) -> &Regret {
+        &self.regret
+    }
+
+    pub fn expected_reward(&self, action: &BanditAction) -> f64 {
+        dot(&self.weights[action.0], &self.state.context)
+    }
+}
+
+impl Environment for LinearBanditEnvironment {
+    type Action = BanditAction;
+    type State = ContextState;
+
+    fn state(&self) -> &ContextState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<BanditAction> {
+        (0..self.weights.len()).map(BanditAction).collect()
+    }
+    fn apply_action(&mut self, action: &BanditAction) -> RewardT {
+        let best = self
+            .actions()
+            .iter()
+            .map(|a| self.expected_reward(a))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let mean = self.expected_reward(action);
+        self.regret.record(best, mean);
+        let reward = distributions::normal(&mut self.rng, mean, self.noise_std_dev);
+        self.state = LinearBanditEnvironment::draw_context(self.dim(), &mut self.rng);
+        RewardT(reward)
+    }
+}
@@ -0,0 +1,158 @@
+use crate::bandit::arm::Arm;
+use crate::bandit::regret::Regret;
+use crate::distributions;
+use crate::environment::{ActionId, Environment, RewardT, State};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BanditAction(pub usize);
+
+impl BanditAction {
+    pub fn id(&self) -> ActionId {
+        ActionId(self.0)
+    }
+}
+
+// Bandits have a single state that never ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct BanditState;
+
+impl State for BanditState {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+// A k-armed bandit. With drift the arms are nonstationary: after every pull
+// all means take an independent random step.
+#[derive(Debug)]
+pub struct BanditEnvironment {
+    arms: Vec<Arm>,
+    drift_std_dev: f64,
+    state: BanditState,
+    regret: Regret,
+    rng: StdRng,
+}
+
+impl BanditEnvironment {
+    pub fn new(arms: Vec<Arm>, seed: u64) -> Self {
+        if arms.is_empty() {
+            panic!("a bandit needs at least one arm");
+        }
+        if let Some(arm) = arms
+            .iter()
+            .find(|arm| matches!(arm, Arm::Bernoulli { p } if !(0.0..=1.0).contains(p)))
+        {
+            panic!("{:?} doesn't have a probability", arm);
+        }
+        BanditEnvironment {
+            arms,
+            drift_std_dev: 0.0,
+            state: BanditState,
+            regret: Regret::default(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // The 10-armed testbed of Sutton & Barto (section 2.3): the means are
+    // drawn from a standard normal distribution and rewards have unit
+    // variance.
+    pub fn gaussian_testbed(num_arms: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let arms = (0..num_arms)
+            .map(|_| Arm::Gaussian {
+                mean: distributions::standard_normal(&mut rng),
+                std_dev: 1.0,
+            })
+            .collect();
+        BanditEnvironment::new(arms, seed)
+    }
+
+    pub fn bernoulli(probs: &[f64], seed: u64) -> Self {
+        let arms = probs.iter().map(|&p| Arm::Bernoulli { p }).collect();
+        BanditEnvironment::new(arms, seed)
+    }
+
+    pub fn with_drift(mut self, std_dev: f64) -> Self {
+        self.drift_std_dev = std_dev;
+        self
+    }
+
+    pub fn arms(&self) -> &[Arm] {
+        &self.arms
+    }
+
+    pub fn num_arms(&self) -> usize {
+        self.arms.len()
+    }
+
+    pub fn regret(&self) -> &Regret {
+        &self.regret
+    }
+
+    fn best_mean(&self) -> f64 {
+        self.arms
+            .iter()
+            .map(|arm| arm.mean())
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+}
+
+impl Environment for BanditEnvironment {
+    type Action = BanditAction;
+    type State = BanditState;
+
+    fn state(&self) -> &BanditState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<BanditAction> {
+        (0..self.arms.len()).map(BanditAction).collect()
+    }
+    fn apply_action(&mut self, action: &BanditAction) -> RewardT {
+        let arm = self.arms[action.0];
+        self.regret.record(self.best_mean(), arm.mean());
+        let reward = arm.sample(&mut self.rng);
+        if self.drift_std_dev > 0.0 {
+            for arm in self.arms.iter_mut() {
+                arm.drift(distributions::normal(
+                    &mut self.rng,
+                    0.0,
+                    self.drift_std_dev,
+                ));
+            }
+        }
+        RewardT(reward)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn regret_of_pulls() {
+        let mut env = BanditEnvironment::bernoulli(&[0.2, 0.5, 0.9], 0);
+        for arm in [2, 0, 1, 2] {
+            env.apply_action(&BanditAction(arm));
+        }
+        let regret = env.regret();
+        assert_eq!(regret.steps(), 4);
+        assert!((regret.total() - 1.1).abs() < 1e-12);
+        assert_eq!(regret.optimal_fraction(), 0.5);
+        assert!((regret.cumulative()[1] - 0.7).abs() < 1e-12);
+    }
+
+    #[test]
+    fn drifting_arms_move() {
+        let mut env = BanditEnvironment::gaussian_testbed(10, 0).with_drift(0.01);
+        let means: Vec<f64> = env.arms().iter().map(|arm| arm.mean()).collect();
+        for _ in 0..100 {
+            env.apply_action(&BanditAction(0));
+        }
+        for (arm, mean) in env.arms().iter().zip(means) {
+            assert!(arm.mean() != mean);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use crate::bandit::contextual::LinearBanditEnvironment;
+use crate::bandit::environment::BanditAction;
+use crate::environment::Environment;
+use itertools::Itertools;
+
+pub trait ContextualBanditAlgorithm {
+    fn select(&mut self, context: &[f64]) -> BanditAction;
+    fn update(&mut self, context: &[f64], action: BanditAction, reward: f64);
+}
+
+// Plays `steps` rounds, each with a freshly drawn context that `algorithm`
+// sees both when it picks an arm and when it learns that arm's reward.
+// Returns the total reward. The environment records the regret against the
+// best arm for every context, not against a single best arm.
+pub fn run<A: ContextualBanditAlgorithm + ?Sized>(
+    env: &mut LinearBanditEnvironment,
+    algorithm: &mut A,
+    steps: usize,
+) -> f64 {
+    let mut total_reward = 0.0;
+    for _ in 0..steps {
+        let context = env.state().context().to_vec();
+        let action = algorithm.select(&context);
+        let reward = env.apply_action(&action);
+        algorithm.update(&context, action, reward.0);
+        total_reward += reward.0;
+    }
+    total_reward
+}
+
+// Disjoint LinUCB of Li et al. (2010): a ridge regression per arm plus a
+// bonus for contexts the arm's estimate is uncertain about.
+#[derive(Debug, Clone)]
+pub struct LinUcb {
+    alpha: f64,
+    // The inverse of every arm's design matrix `λI + Σ x xᵀ`, kept up to date
+    // with the Sherman-Morrison formula instead of inverting it every step.
+    inverses: Vec<Vec<Vec<f64>>>,
+    // `Σ r x` of every arm.
+    targets: Vec<Vec<f64>>,
+}
+
+impl LinUcb {
+    // `alpha` scales the confidence bonus, `regularization` is the ridge
+    // penalty λ.
+    pub fn new(num_arms: usize, dim: usize, alpha: f64, regularization: f64) -> Self {
+        if regularization <= 0.0 {
+            panic!(
+                "the regularization has to be positive, got {}",
+                regularization
+            );
+        }
+        let inverse: Vec<Vec<f64>> = (0..dim)
+            .map(|i| {
+                (0..dim)
+                    .map(|j| if i == j { 1.0 / regularization } else { 0.0 })
+                    .collect()
+            })
+            .collect();
+        LinUcb {
+            alpha,
+            inverses: vec![inverse; num_arms],
+            targets: vec![vec![0.0; dim]; num_arms],
+        }
+    }
+
+    fn multiply(matrix: &[Vec<f64>], vector: &[f64]) -> Vec<f64> {
+        matrix
+            .iter()
+            .map(|row| row.iter().zip(vector).map(|(a, b)| a * b).sum())
+            .collect()
+    }
+
+    // The ridge regression estimate of an arm's weights.
+    pub fn weights(&self, action: BanditAction) -> Vec<f64> {
+        LinUcb::multiply(&self.inverses[action.0], &self.targets[action.0])
+    }
+
+    fn upper_bound(&self, arm: usize, context: &[f64]) -> f64 {
+        let estimate: f64 = self
+            .weights(BanditAction(arm))
+            .iter()
+            .zip(context)
+            .map(|(w, x)| w * x)
+            .sum();
+        let projected = LinUcb::multiply(&self.inverses[arm], context);
+        let variance: f64 = projected.iter().zip(context).map(|(p, x)| p * x).sum();
+        estimate + self.alpha * variance.max(0.0).sqrt()
+    }
+}
+
+impl ContextualBanditAlgorithm for LinUcb {
+    fn select(&mut self, context: &[f64]) -> BanditAction {
+        let bounds: Vec<f64> = (0..self.inverses.len())
+            .map(|arm| self.upper_bound(arm, context))
+            .collect();
+        let best = bounds.iter().position_max_by(|a, b| a.total_cmp(b));
+        BanditAction(best.expect("a bandit has at least one arm"))
+    }
+
+    fn update(&mut self, context: &[f64], action: BanditAction, reward: f64) {
+        let inverse = &mut self.inverses[action.0];
+        // A⁻¹ ← A⁻¹ - (A⁻¹x)(A⁻¹x)ᵀ / (1 + xᵀA⁻¹x), A⁻¹ is symmetric.
+        let projected = LinUcb::multiply(inverse, context);
+        let denominator = 1.0
+            + projected
+                .iter()
+                .zip(context)
+                .map(|(p, x)| p * x)
+                .sum::<f64>();
+        for (i, row) in inverse.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value -= projected[i] * projected[j] / denominator;
+            }
+        }
+        for (target, x) in self.targets[action.0].iter_mut().zip(context) {
+            *target += reward * x;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    struct RandomPlay(StdRng, usize);
+
+    impl ContextualBanditAlgorithm for RandomPlay {
+        fn select(&mut self, _context: &[f64]) -> BanditAction {
+            BanditAction(self.0.gen_range(0..self.1))
+        }
+        fn update(&mut self, _context: &[f64], _action: BanditAction, _reward: f64) {}
+    }
+
+    #[test]
+    fn linucb_learns_weights() {
+        let mut env = LinearBanditEnvironment::new(
+            vec![
+                vec![1.0, 0.0, 0.5],
+                vec![-1.0, 1.0, 0.0],
+                vec![0.0, -0.5, 1.0],
+            ],
+            0.1,
+            0,
+        );
+        let mut algorithm = LinUcb::new(3, 3, 1.0, 1.0);
+        run(&mut env, &mut algorithm, 2000);
+        let weights = algorithm.weights(BanditAction(1));
+        assert!((weights[0] + 1.0).abs() < 0.1 && (weights[1] - 1.0).abs() < 0.1);
+
+        let mut random_env = LinearBanditEnvironment::new(
+            vec![
+                vec![1.0, 0.0, 0.5],
+                vec![-1.0, 1.0, 0.0],
+                vec![0.0, -0.5, 1.0],
+            ],
+            0.1,
+            0,
+        );
+        run(
+            &mut random_env,
+            &mut RandomPlay(StdRng::seed_from_u64(1), 3),
+            2000,
+        );
+        let regret = env.regret().total();
+        assert!(regret < 0.1 * random_env.regret().total(), "{}", regret);
+    }
+
+    #[test]
+    fn random_linear_bandit() {
+        let mut env = LinearBanditEnvironment::random(5, 8, 0.5, 3);
+        let mut algorithm = LinUcb::new(5, 8, 0.5, 1.0);
+        run(&mut env, &mut algorithm, 3000);
+        let regret = env.regret();
+        // The regret grows sublinearly, so later steps are mostly optimal.
+        let late_regret = regret.total() - regret.cumulative()[1999];
+        let early_regret = regret.cumulative()[999];
+        assert!(
+            late_regret < 0.2 * early_regret,
+            "{} {}",
+            late_regret,
+            early_regret
+        );
+    }
+}
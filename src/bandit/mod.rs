@@ -0,0 +1,6 @@
+pub mod algorithm;
+pub mod arm;
+pub mod contextual;
+pub mod environment;
+pub mod linucb;
+pub mod regret;
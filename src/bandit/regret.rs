@@ -0,0 +1,37 @@
+// Tracks the expected regret of the chosen actions, i.e. how much expected
+// reward was lost compared to always playing the best action.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Regret {
+    cumulative: Vec<f64>,
+    optimal_steps: usize,
+}
+
+impl Regret {
+    pub fn record(&mut self, best_mean: f64, chosen_mean: f64) {
+        let regret = best_mean - chosen_mean;
+        if regret <= 0.0 {
+            self.optimal_steps += 1;
+        }
+        self.cumulative.push(self.total() + regret.max(0.0));
+    }
+
+    pub fn total(&self) -> f64 {
+        self.cumulative.last().copied().unwrap_or(0.0)
+    }
+
+    pub fn steps(&self) -> usize {
+        self.cumulative.len()
+    }
+
+    // The total regret after every step, e.g. for plotting learning curves.
+    pub fn cumulative(&self) -> &[f64] {
+        &self.cumulative
+    }
+
+    pub fn optimal_fraction(&self) -> f64 {
+        if self.cumulative.is_empty() {
+            return 0.0;
+        }
+        self.optimal_steps as f64 / self.steps() as f64
+    }
+}
@@ -0,0 +1,307 @@
+use crate::environment::{
+    ActionId, DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AccessControlAction {
+    Accept,
+    Reject,
+}
+
+impl AccessControlAction {
+    pub fn all() -> [AccessControlAction; 2] {
+        [AccessControlAction::Accept, AccessControlAction::Reject]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            AccessControlAction::Accept => ActionId(0),
+            AccessControlAction::Reject => ActionId(1),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> AccessControlAction {
+        AccessControlAction::all()[action_id.0]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessControlConfig {
+    pub num_servers: usize,
+    // Probability of a busy server becoming free at every step.
+    pub free_prob: f64,
+    // Paid for serving a customer of every priority, all priorities are
+    // equally likely.
+    pub rewards: Vec<f64>,
+}
+
+// Example 10.2 of Sutton & Barto.
+impl Default for AccessControlConfig {
+    fn default() -> Self {
+        AccessControlConfig {
+            num_servers: 10,
+            free_prob: 0.06,
+            rewards: vec![1.0, 2.0, 4.0, 8.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AccessControlState {
+    free_servers: usize,
+    // Index into `AccessControlConfig::rewards` of the customer at the head
+    // of the queue.
+    priority: usize,
+}
+
+impl AccessControlState {
+    pub fn new(free_servers: usize, priority: usize) -> AccessControlState {
+        AccessControlState {
+            free_servers,
+            priority,
+        }
+    }
+
+    pub fn free_servers(&self) -> usize {
+        self.free_servers
+    }
+
+    pub fn priority(&self) -> usize {
+        self.priority
+    }
+}
+
+// The queue never runs empty.
+impl State for AccessControlState {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+// The access-control queuing task: customers of different priorities arrive
+// at a queue one by one, and the customer at its head is either given one of
+// the free servers, paying the reward of its priority, or turned away. A
+// customer arriving while all servers are busy is always turned away. The
+// task is continuing, so it's solved for the average reward per step.
+#[derive(Debug)]
+pub struct AccessControlEnvironment {
+    config: AccessControlConfig,
+    state: AccessControlState,
+    rng: StdRng,
+}
+
+impl AccessControlEnvironment {
+    pub fn new(config: AccessControlConfig, seed: u64) -> Self {
+        if config.rewards.is_empty() {
+            panic!("there has to be at least one priority");
+        }
+        if !(0.0..=1.0).contains(&config.free_prob) {
+            panic!("free probability {} is not a probability", config.free_prob);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let priority = rng.gen_range(0..config.rewards.len());
+        AccessControlEnvironment {
+            state: AccessControlState::new(config.num_servers, priority),
+            config,
+            rng,
+        }
+    }
+
+    pub fn with_state(mut self, state: AccessControlState) -> Self {
+        if state.free_servers > self.config.num_servers {
+            panic!("there are only {} servers", self.config.num_servers);
+        }
+        if state.priority >= self.config.rewards.len() {
+            panic!("there is no priority {}", state.priority);
+        }
+        self.state = state;
+        self
+    }
+
+    pub fn state_id(&self, state: &AccessControlState) -> StateId {
+        StateId(state.free_servers * self.config.rewards.len() + state.priority)
+    }
+
+    // The free servers after serving the customer at the head of the queue
+    // or not, and the reward for it.
+    fn serve(&self, state: &AccessControlState, action: AccessControlAction) -> (usize, RewardT) {
+        if action == AccessControlAction::Accept && state.free_servers > 0 {
+            (
+                state.free_servers - 1,
+                RewardT(self.config.rewards[state.priority]),
+            )
+        } else {
+            (state.free_servers, RewardT(0.0))
+        }
+    }
+}
+
+impl Environment for AccessControlEnvironment {
+    type Action = AccessControlAction;
+    type State = AccessControlState;
+
+    fn state(&self) -> &AccessControlState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<AccessControlAction> {
+        AccessControlAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &AccessControlAction) -> RewardT {
+        let (free_servers, reward) = self.serve(&self.state, *action);
+        let busy_servers = self.config.num_servers - free_servers;
+        let freed = (0..busy_servers)
+            .filter(|_| self.rng.gen_bool(self.config.free_prob))
+            .count();
+        self.state = AccessControlState::new(
+            free_servers + freed,
+            self.rng.gen_range(0..self.config.rewards.len()),
+        );
+        reward
+    }
+}
+
+impl DPEnvironment for AccessControlEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        let num_priorities = self.config.rewards.len();
+        let mut table = HashMap::new();
+        for free_servers in 0..=self.config.num_servers {
+            for priority in 0..num_priorities {
+                let state = AccessControlState::new(free_servers, priority);
+                let mut transitions = vec![];
+                for action in AccessControlAction::all() {
+                    let (free_servers, reward) = self.serve(&state, action);
+                    let busy_servers = self.config.num_servers - free_servers;
+                    let freed_probs = binomial(busy_servers, self.config.free_prob);
+                    for (freed, freed_prob) in freed_probs.into_iter().enumerate() {
+                        for next_priority in 0..num_priorities {
+                            let next = AccessControlState::new(free_servers + freed, next_priority);
+                            transitions.push(StateTransition {
+                                action_id: action.id(),
+                                new_state_id: self.state_id(&next),
+                                reward,
+                                prob: ProbabilityT(freed_prob / num_priorities as f64),
+                            });
+                        }
+                    }
+                }
+                table.insert(self.state_id(&state), transitions);
+            }
+        }
+        table
+    }
+}
+
+// Probabilities of 0, 1, ..., `n` successes out of `n` trials.
+fn binomial(n: usize, p: f64) -> Vec<f64> {
+    let mut probs = vec![1.0];
+    for _ in 0..n {
+        let mut next = vec![0.0; probs.len() + 1];
+        for (successes, prob) in probs.iter().enumerate() {
+            next[successes] += prob * (1.0 - p);
+            next[successes + 1] += prob * p;
+        }
+        probs = next;
+    }
+    probs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::average_reward::{
+        average_reward_policy_evaluation, relative_value_iteration, DifferentialSarsa,
+    };
+    use crate::dp::{greedy_policy, DPConfig};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn serving_customers() {
+        let config = AccessControlConfig {
+            free_prob: 0.0,
+            ..Default::default()
+        };
+        let mut env =
+            AccessControlEnvironment::new(config, 0).with_state(AccessControlState::new(1, 3));
+        assert_eq!(env.apply_action(&AccessControlAction::Accept), RewardT(8.0));
+        assert_eq!(env.state().free_servers(), 0);
+        // Without free servers, customers are turned away.
+        assert_eq!(env.apply_action(&AccessControlAction::Accept), RewardT(0.0));
+        assert_eq!(env.state().free_servers(), 0);
+    }
+
+    #[test]
+    fn state_transitions() {
+        let env = AccessControlEnvironment::new(AccessControlConfig::default(), 0);
+        let table = env.state_transitions();
+        assert_eq!(table.len(), 11 * 4);
+        for transitions in table.values() {
+            let total: f64 = transitions.iter().map(|t| t.prob.0).sum();
+            assert!((total - 2.0).abs() < 1e-9);
+        }
+        assert!((binomial(3, 0.5)[1] - 0.375).abs() < 1e-12);
+    }
+
+    #[test]
+    fn low_priorities_wait_for_free_servers() {
+        let env = AccessControlEnvironment::new(AccessControlConfig::default(), 0);
+        let table = env.state_transitions();
+        let reference = env.state_id(&AccessControlState::new(0, 0));
+        let (gain, values) = relative_value_iteration(&table, &DPConfig::default(), &reference);
+        let policy = greedy_policy(&table, &values, 1.0);
+        let action = |free_servers, priority| {
+            let state_id = env.state_id(&AccessControlState::new(free_servers, priority));
+            AccessControlAction::action_with_id(policy[&state_id])
+        };
+        // The lowest priority isn't worth a server at all, the next one only
+        // when there are plenty.
+        for free_servers in 1..=10 {
+            assert_eq!(action(free_servers, 0), AccessControlAction::Reject);
+            assert_eq!(action(free_servers, 3), AccessControlAction::Accept);
+        }
+        assert_eq!(action(1, 1), AccessControlAction::Reject);
+        assert_eq!(action(10, 1), AccessControlAction::Accept);
+        let (policy_gain, _) =
+            average_reward_policy_evaluation(&table, &policy, &DPConfig::default(), &reference);
+        assert!((policy_gain - gain).abs() < 1e-6);
+
+        // Accepting everyone keeps the servers too busy for the valuable
+        // customers.
+        let accept_all = table
+            .keys()
+            .map(|id| (*id, AccessControlAction::Accept.id()))
+            .collect();
+        let (accept_all_gain, _) =
+            average_reward_policy_evaluation(&table, &accept_all, &DPConfig::default(), &reference);
+        assert!(accept_all_gain < gain - 0.1);
+    }
+
+    #[test]
+    fn differential_sarsa_learns_to_wait_for_free_servers() {
+        let mut env = AccessControlEnvironment::new(AccessControlConfig::default(), 0);
+        let table = env.state_transitions();
+        let reference = env.state_id(&AccessControlState::new(0, 0));
+        let (gain, _) = relative_value_iteration(&table, &DPConfig::default(), &reference);
+
+        let action_ids = AccessControlAction::all().map(|action| action.id());
+        let mut agent = DifferentialSarsa::new(0.1, 0.01, 0.1, 0);
+        let mut state_id = env.state_id(env.state());
+        let mut action_id = agent.select_action(&state_id, &action_ids);
+        for _ in 0..50000 {
+            let reward = env.apply_action(&AccessControlAction::action_with_id(action_id));
+            let next_state_id = env.state_id(env.state());
+            let next_action_id = agent.select_action(&next_state_id, &action_ids);
+            agent.learn(&state_id, action_id, reward, &next_state_id, next_action_id);
+            (state_id, action_id) = (next_state_id, next_action_id);
+        }
+        let policy = table
+            .keys()
+            .map(|id| (*id, agent.greedy_action(id, &action_ids)))
+            .collect();
+        let (learned_gain, _) =
+            average_reward_policy_evaluation(&table, &policy, &DPConfig::default(), &reference);
+        assert!(learned_gain > 0.95 * gain);
+    }
+}
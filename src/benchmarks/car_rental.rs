@@ -0,0 +1,356 @@
+use crate::environment::{
+    ActionId, DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CarRentalConfig {
+    pub max_cars: usize,
+    pub max_move: usize,
+    pub rental_reward: f64,
+    pub move_cost: f64,
+    // Poisson rates of both locations.
+    pub request_rates: [f64; 2],
+    pub return_rates: [f64; 2],
+}
+
+// Example 4.2 of Sutton & Barto.
+impl Default for CarRentalConfig {
+    fn default() -> Self {
+        CarRentalConfig {
+            max_cars: 20,
+            max_move: 5,
+            rental_reward: 10.0,
+            move_cost: 2.0,
+            request_rates: [3.0, 4.0],
+            return_rates: [3.0, 2.0],
+        }
+    }
+}
+
+// Number of cars moved from the first location to the second one overnight,
+// negative numbers move cars the other way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CarRentalAction(pub isize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CarRentalState {
+    cars: [usize; 2],
+}
+
+impl CarRentalState {
+    pub fn new(cars: [usize; 2]) -> CarRentalState {
+        CarRentalState { cars }
+    }
+
+    pub fn cars(&self) -> [usize; 2] {
+        self.cars
+    }
+}
+
+// Renting out cars goes on forever.
+impl State for CarRentalState {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+// Probabilities of a Poisson variable being 0, 1, ..., `max - 1` and at least
+// `max` in the last entry.
+fn poisson_probabilities(rate: f64, max: usize) -> Vec<f64> {
+    let mut probs = vec![];
+    let mut prob = (-rate).exp();
+    for k in 0..max {
+        probs.push(prob);
+        prob *= rate / (k + 1) as f64;
+    }
+    probs.push((1.0 - probs.iter().sum::<f64>()).max(0.0));
+    probs
+}
+
+// Knuth's algorithm, which is fine for small rates.
+fn sample_poisson(rate: f64, rng: &mut StdRng) -> usize {
+    let limit = (-rate).exp();
+    let mut count = 0;
+    let mut product: f64 = rng.gen();
+    while product > limit {
+        count += 1;
+        product *= rng.gen::<f64>();
+    }
+    count
+}
+
+// A location's next morning, given the cars it has after moving: the
+// probability of every number of cars and the expected number of rentals
+// leading to it.
+#[derive(Debug, Clone)]
+struct LocationDynamics {
+    probs: Vec<f64>,
+    expected_rentals: Vec<f64>,
+}
+
+impl LocationDynamics {
+    fn new(cars: usize, request_rate: f64, return_rate: f64, max_cars: usize) -> Self {
+        let mut probs = vec![0.0; max_cars + 1];
+        let mut rentals = vec![0.0; max_cars + 1];
+        // Requests beyond the available cars can't be served, returns beyond
+        // the capacity go back to the nationwide company.
+        for (rented, request_prob) in poisson_probabilities(request_rate, cars)
+            .into_iter()
+            .enumerate()
+        {
+            let remaining = cars - rented;
+            for (returned, return_prob) in poisson_probabilities(return_rate, max_cars - remaining)
+                .into_iter()
+                .enumerate()
+            {
+                let prob = request_prob * return_prob;
+                probs[remaining + returned] += prob;
+                rentals[remaining + returned] += prob * rented as f64;
+            }
+        }
+        let expected_rentals = rentals
+            .iter()
+            .zip(probs.iter())
+            .map(|(rentals, &prob)| if prob > 0.0 { rentals / prob } else { 0.0 })
+            .collect();
+        LocationDynamics {
+            probs,
+            expected_rentals,
+        }
+    }
+}
+
+// Jack manages two car rental locations and moves cars between them overnight
+// to meet the next day's demand.
+#[derive(Debug)]
+pub struct CarRentalEnvironment {
+    config: CarRentalConfig,
+    state: CarRentalState,
+    rng: StdRng,
+}
+
+impl CarRentalEnvironment {
+    pub fn new(config: CarRentalConfig, seed: u64) -> Self {
+        let half = config.max_cars / 2;
+        CarRentalEnvironment {
+            config,
+            state: CarRentalState::new([half, half]),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn with_state(mut self, state: CarRentalState) -> Self {
+        if state.cars.iter().any(|&cars| cars > self.config.max_cars) {
+            panic!("{:?} has more than {} cars", state, self.config.max_cars);
+        }
+        self.state = state;
+        self
+    }
+
+    pub fn action_id(&self, action: &CarRentalAction) -> ActionId {
+        ActionId((action.0 + self.config.max_move as isize) as usize)
+    }
+
+    pub fn action_with_id(&self, action_id: ActionId) -> CarRentalAction {
+        CarRentalAction(action_id.0 as isize - self.config.max_move as isize)
+    }
+
+    pub fn state_id(&self, state: &CarRentalState) -> StateId {
+        StateId(state.cars[0] * (self.config.max_cars + 1) + state.cars[1])
+    }
+
+    fn actions_in(&self, state: &CarRentalState) -> Vec<CarRentalAction> {
+        let max_move = self.config.max_move as isize;
+        (-max_move..=max_move)
+            .filter(|&moved| moved <= state.cars[0] as isize && -moved <= state.cars[1] as isize)
+            .map(CarRentalAction)
+            .collect()
+    }
+
+    // Cars at both locations after moving, surplus cars are given away.
+    fn cars_after_move(&self, state: &CarRentalState, action: &CarRentalAction) -> [usize; 2] {
+        let max_cars = self.config.max_cars as isize;
+        [
+            (state.cars[0] as isize - action.0).min(max_cars) as usize,
+            (state.cars[1] as isize + action.0).min(max_cars) as usize,
+        ]
+    }
+
+    fn move_reward(&self, action: &CarRentalAction) -> f64 {
+        -self.config.move_cost * action.0.unsigned_abs() as f64
+    }
+}
+
+impl Environment for CarRentalEnvironment {
+    type Action = CarRentalAction;
+    type State = CarRentalState;
+
+    fn state(&self) -> &CarRentalState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<CarRentalAction> {
+        self.actions_in(&self.state)
+    }
+    fn apply_action(&mut self, action: &CarRentalAction) -> RewardT {
+        if !self.actions().contains(action) {
+            panic!("can't move {:?} cars in {:?}", action, self.state);
+        }
+        let mut reward = self.move_reward(action);
+        let cars = self.cars_after_move(&self.state, action);
+        for (location, &cars) in cars.iter().enumerate() {
+            let requested = sample_poisson(self.config.request_rates[location], &mut self.rng);
+            let rented = requested.min(cars);
+            let returned = sample_poisson(self.config.return_rates[location], &mut self.rng);
+            reward += self.config.rental_reward * rented as f64;
+            self.state.cars[location] = (cars - rented + returned).min(self.config.max_cars);
+        }
+        RewardT(reward)
+    }
+}
+
+// Both locations evolve independently, so their dynamics are computed
+// separately and combined. Transitions carry the expected reward given the
+// next state, which leaves the expected returns unchanged and keeps the table
+// at one transition per next state.
+impl DPEnvironment for CarRentalEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        let max_cars = self.config.max_cars;
+        let dynamics: Vec<Vec<LocationDynamics>> = (0..2)
+            .map(|location| {
+                (0..=max_cars)
+                    .map(|cars| {
+                        LocationDynamics::new(
+                            cars,
+                            self.config.request_rates[location],
+                            self.config.return_rates[location],
+                            max_cars,
+                        )
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let mut transition_table = HashMap::new();
+        for cars in itertools::iproduct!(0..=max_cars, 0..=max_cars) {
+            let state = CarRentalState::new([cars.0, cars.1]);
+            let mut transitions = vec![];
+            for action in self.actions_in(&state) {
+                let [first, second] = self.cars_after_move(&state, &action);
+                let (first, second) = (&dynamics[0][first], &dynamics[1][second]);
+                for (next_first, &first_prob) in first.probs.iter().enumerate() {
+                    for (next_second, &second_prob) in second.probs.iter().enumerate() {
+                        let prob = first_prob * second_prob;
+                        if prob == 0.0 {
+                            continue;
+                        }
+                        let rentals = first.expected_rentals[next_first]
+                            + second.expected_rentals[next_second];
+                        let next_state = CarRentalState::new([next_first, next_second]);
+                        transitions.push(StateTransition {
+                            action_id: self.action_id(&action),
+                            new_state_id: self.state_id(&next_state),
+                            reward: RewardT(
+                                self.move_reward(&action) + self.config.rental_reward * rentals,
+                            ),
+                            prob: ProbabilityT(prob),
+                        });
+                    }
+                }
+            }
+            transition_table.insert(self.state_id(&state), transitions);
+        }
+        transition_table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{greedy_policy, value_iteration, DPConfig};
+    use pretty_assertions::assert_eq;
+
+    fn small_config() -> CarRentalConfig {
+        CarRentalConfig {
+            max_cars: 8,
+            max_move: 3,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn location_dynamics_are_distributions() {
+        let dynamics = LocationDynamics::new(4, 3.0, 2.0, 8);
+        assert_eq!(dynamics.probs.len(), 9);
+        assert!((dynamics.probs.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        // Ending up with no cars means all four were rented out.
+        assert!((dynamics.expected_rentals[0] - 4.0).abs() < 1e-12);
+        assert!(dynamics.expected_rentals.iter().all(|&r| r <= 4.0));
+    }
+
+    #[test]
+    fn expected_rewards_match_samples() {
+        let env = CarRentalEnvironment::new(small_config(), 0);
+        let state = CarRentalState::new([6, 2]);
+        let action = CarRentalAction(2);
+        let expected: f64 = env.state_transitions()[&env.state_id(&state)]
+            .iter()
+            .filter(|t| t.action_id == env.action_id(&action))
+            .map(|t| t.prob.0 * t.reward.0)
+            .sum();
+
+        let mut env = env;
+        let samples = 20000;
+        let mut total = 0.0;
+        for _ in 0..samples {
+            env.state = state;
+            total += env.apply_action(&action).0;
+        }
+        let average = total / samples as f64;
+        assert!((average - expected).abs() < 0.3, "{} {}", average, expected);
+    }
+
+    #[test]
+    fn optimal_policy_moves_cars_to_busier_location() {
+        let env = CarRentalEnvironment::new(CarRentalConfig::default(), 0);
+        let table = env.state_transitions();
+        assert_eq!(table.len(), 441);
+        for transitions in table.values() {
+            let total: f64 = transitions.iter().map(|t| t.prob.0).sum();
+            let num_actions = transitions
+                .iter()
+                .map(|t| t.action_id)
+                .collect::<std::collections::HashSet<_>>()
+                .len();
+            assert!((total - num_actions as f64).abs() < 1e-9);
+        }
+
+        let config = DPConfig {
+            discount: 0.9,
+            theta: 1e-4,
+            ..Default::default()
+        };
+        let values = value_iteration(&table, &config);
+        let policy = greedy_policy(&table, &values, 0.9);
+        let action = |cars: [usize; 2]| {
+            env.action_with_id(policy[&env.state_id(&CarRentalState::new(cars))])
+        };
+        // Figure 4.2: the second location rents out more cars than are
+        // returned to it, so cars are moved there unless the first one is
+        // almost empty.
+        assert_eq!(action([20, 0]), CarRentalAction(5));
+        assert_eq!(action([10, 0]), CarRentalAction(4));
+        assert_eq!(action([20, 10]), CarRentalAction(2));
+        assert_eq!(action([12, 4]), CarRentalAction(2));
+        assert_eq!(action([5, 0]), CarRentalAction(1));
+        assert_eq!(action([0, 0]), CarRentalAction(0));
+        assert_eq!(action([10, 10]), CarRentalAction(0));
+        assert_eq!(action([20, 20]), CarRentalAction(0));
+        assert_eq!(action([4, 20]), CarRentalAction(-1));
+        assert_eq!(action([2, 15]), CarRentalAction(-1));
+        assert_eq!(action([1, 20]), CarRentalAction(-3));
+        assert_eq!(action([0, 20]), CarRentalAction(-4));
+    }
+}
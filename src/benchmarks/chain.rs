@@ -0,0 +1,247 @@
+use crate::environment::{
+    ActionId, DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ChainAction {
+    Forward,
+    Return,
+}
+
+impl ChainAction {
+    pub fn all() -> [ChainAction; 2] {
+        [ChainAction::Forward, ChainAction::Return]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            ChainAction::Forward => ActionId(0),
+            ChainAction::Return => ActionId(1),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> ChainAction {
+        ChainAction::all()[action_id.0]
+    }
+
+    fn opposite(&self) -> ChainAction {
+        match self {
+            ChainAction::Forward => ChainAction::Return,
+            ChainAction::Return => ChainAction::Forward,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChainConfig {
+    pub num_states: usize,
+    // Probability of doing the opposite of the chosen action.
+    pub slip_prob: f64,
+    // Given for staying at the end of the chain.
+    pub end_reward: f64,
+    // Given for going back to the start.
+    pub return_reward: f64,
+}
+
+// The chain of Strens (2000).
+impl Default for ChainConfig {
+    fn default() -> Self {
+        ChainConfig {
+            num_states: 5,
+            slip_prob: 0.2,
+            end_reward: 10.0,
+            return_reward: 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChainState {
+    position: usize,
+}
+
+impl ChainState {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn id(&self) -> StateId {
+        StateId(self.position)
+    }
+}
+
+// The chain goes on forever, so it needs discounting.
+impl State for ChainState {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+// Walking forward along the chain pays off only at its end, while going back
+// to the start gives a small reward right away. Myopic agents get stuck
+// returning to the start.
+#[derive(Debug)]
+pub struct ChainEnvironment {
+    config: ChainConfig,
+    state: ChainState,
+    rng: StdRng,
+}
+
+impl ChainEnvironment {
+    pub fn new(config: ChainConfig, seed: u64) -> Self {
+        if config.num_states < 2 {
+            panic!("a chain needs at least two states");
+        }
+        if !(0.0..=1.0).contains(&config.slip_prob) {
+            panic!("slip probability {} is not a probability", config.slip_prob);
+        }
+        ChainEnvironment {
+            config,
+            state: ChainState { position: 0 },
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    fn outcome(&self, position: usize, action: ChainAction) -> (usize, RewardT) {
+        let last = self.config.num_states - 1;
+        match action {
+            ChainAction::Forward if position == last => (last, RewardT(self.config.end_reward)),
+            ChainAction::Forward => (position + 1, RewardT(0.0)),
+            ChainAction::Return => (0, RewardT(self.config.return_reward)),
+        }
+    }
+}
+
+impl Environment for ChainEnvironment {
+    type Action = ChainAction;
+    type State = ChainState;
+
+    fn state(&self) -> &ChainState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<ChainAction> {
+        ChainAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &ChainAction) -> RewardT {
+        let action = if self.rng.gen_bool(self.config.slip_prob) {
+            action.opposite()
+        } else {
+            *action
+        };
+        let (position, reward) = self.outcome(self.state.position, action);
+        self.state.position = position;
+        reward
+    }
+}
+
+impl DPEnvironment for ChainEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        (0..self.config.num_states)
+            .map(|position| {
+                let transitions = ChainAction::all()
+                    .iter()
+                    .flat_map(|&action| {
+                        [
+                            (action, 1.0 - self.config.slip_prob),
+                            (action.opposite(), self.config.slip_prob),
+                        ]
+                        .map(|(actual_action, prob)| {
+                            let (new_position, reward) = self.outcome(position, actual_action);
+                            StateTransition {
+                                action_id: action.id(),
+                                new_state_id: StateId(new_position),
+                                reward,
+                                prob: ProbabilityT(prob),
+                            }
+                        })
+                    })
+                    .collect();
+                (StateId(position), transitions)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{
+        greedy_policy, lp_occupancy, lp_values, occupancy_policy, policy_evaluation, rms_error,
+        value_iteration, DPConfig,
+    };
+    use pretty_assertions::assert_eq;
+
+    fn config(discount: f64) -> DPConfig {
+        DPConfig {
+            discount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn far_sighted_agents_walk_forward() {
+        let env = ChainEnvironment::new(ChainConfig::default(), 0);
+        let table = env.state_transitions();
+
+        let values = value_iteration(&table, &config(0.99));
+        let policy = greedy_policy(&table, &values, 0.99);
+        assert!(policy.values().all(|&a| a == ChainAction::Forward.id()));
+
+        let values = value_iteration(&table, &config(0.5));
+        let policy = greedy_policy(&table, &values, 0.5);
+        assert_eq!(policy[&StateId(0)], ChainAction::Return.id());
+    }
+
+    #[test]
+    fn linear_programs_agree_with_value_iteration() {
+        let env = ChainEnvironment::new(ChainConfig::default(), 0);
+        let table = env.state_transitions();
+        for discount in [0.5, 0.99] {
+            let values = value_iteration(&table, &config(discount));
+            let lp_values = lp_values(&table, discount).unwrap();
+            assert!(rms_error(&lp_values, &values) < 1e-6, "{}", discount);
+
+            let initial = HashMap::from([(StateId(0), 1.0)]);
+            let occupancy = lp_occupancy(&table, discount, &initial).unwrap();
+            // The occupancies of a start distribution sum up to the
+            // effective horizon.
+            let total: f64 = occupancy.values().sum();
+            assert!((total - 1.0 / (1.0 - discount)).abs() < 1e-6);
+            let policy = occupancy_policy(&table, &occupancy);
+            let greedy = greedy_policy(&table, &values, discount);
+            assert_eq!(policy[&StateId(0)], greedy[&StateId(0)]);
+        }
+    }
+
+    #[test]
+    fn policy_values_match_sampled_returns() {
+        let mut env = ChainEnvironment::new(ChainConfig::default(), 0);
+        let policy = (0..5)
+            .map(|position| (StateId(position), ChainAction::Forward.id()))
+            .collect();
+        let values = policy_evaluation(&env.state_transitions(), &policy, &config(0.9));
+
+        let episodes = 2000;
+        let mut total = 0.0;
+        for _ in 0..episodes {
+            env.state = ChainState { position: 0 };
+            // 0.9^200 is small enough to ignore the rest of the return.
+            let mut discount = 1.0;
+            for _ in 0..200 {
+                total += discount * env.apply_action(&ChainAction::Forward).0;
+                discount *= 0.9;
+            }
+        }
+        let average = total / episodes as f64;
+        let expected = values[&StateId(0)];
+        assert!(
+            (average - expected).abs() < 0.05 * expected,
+            "{} {}",
+            average,
+            expected
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use crate::environment::{
+    Environment, MultiObjectiveDPEnvironment, ProbabilityT, RewardT, State, StateId, VectorRewardT,
+    VectorStateTransition,
+};
+use crate::gridworld::action::GridAction;
+use std::collections::HashMap;
+
+const ROWS: usize = 11;
+const COLS: usize = 10;
+// The row of the sea floor and the treasure lying there, for every column.
+const FLOOR: [usize; COLS] = [1, 2, 3, 4, 4, 4, 7, 7, 9, 10];
+const TREASURES: [f64; COLS] = [1.0, 2.0, 3.0, 5.0, 8.0, 16.0, 24.0, 50.0, 74.0, 124.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeepSeaTreasureState {
+    position: (usize, usize),
+}
+
+impl DeepSeaTreasureState {
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    pub fn id(&self) -> StateId {
+        StateId(self.position.0 * COLS + self.position.1)
+    }
+
+    // The treasure found once the episode is over.
+    pub fn treasure(&self) -> Option<f64> {
+        let (row, col) = self.position;
+        (row == FLOOR[col]).then_some(TREASURES[col])
+    }
+}
+
+impl State for DeepSeaTreasureState {
+    fn is_terminal(&self) -> bool {
+        self.treasure().is_some()
+    }
+}
+
+// The Deep Sea Treasure of Vamplew et al. (2011): a submarine starts at the
+// surface in the top left corner and dives for one of the treasures on the
+// sea floor. The farther ones are worth more but take longer to reach. The
+// rewards are the treasure and -1 for every move, and the Pareto front of
+// the start is not convex. The scalar rewards of `Environment` are weighted
+// sums of the two, by default their plain sum.
+#[derive(Debug)]
+pub struct DeepSeaTreasureEnvironment {
+    state: DeepSeaTreasureState,
+    weights: [f64; 2],
+}
+
+impl DeepSeaTreasureEnvironment {
+    pub fn new() -> Self {
+        DeepSeaTreasureEnvironment {
+            state: DeepSeaTreasureState { position: (0, 0) },
+            weights: [1.0, 1.0],
+        }
+    }
+
+    pub fn with_weights(mut self, weights: [f64; 2]) -> Self {
+        self.weights = weights;
+        self
+    }
+
+    pub fn reset(&mut self) {
+        self.state.position = (0, 0);
+    }
+
+    // Moving into the sea floor or out of the grid keeps the submarine in
+    // place.
+    fn outcome(
+        state: &DeepSeaTreasureState,
+        action: GridAction,
+    ) -> (DeepSeaTreasureState, [f64; 2]) {
+        let (row, col) = state.position;
+        let (row_offset, col_offset) = action.offset();
+        let (new_row, new_col) = (row as isize + row_offset, col as isize + col_offset);
+        let inside = (0..ROWS as isize).contains(&new_row) && (0..COLS as isize).contains(&new_col);
+        let next = if inside && new_row as usize <= FLOOR[new_col as usize] {
+            DeepSeaTreasureState {
+                position: (new_row as usize, new_col as usize),
+            }
+        } else {
+            *state
+        };
+        (next, [next.treasure().unwrap_or(0.0), -1.0])
+    }
+}
+
+impl Default for DeepSeaTreasureEnvironment {
+    fn default() -> Self {
+        DeepSeaTreasureEnvironment::new()
+    }
+}
+
+impl Environment for DeepSeaTreasureEnvironment {
+    type Action = GridAction;
+    type State = DeepSeaTreasureState;
+
+    fn state(&self) -> &DeepSeaTreasureState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<GridAction> {
+        if self.state.is_terminal() {
+            return vec![];
+        }
+        GridAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &GridAction) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("can't move after the episode is over");
+        }
+        let (next, reward) = Self::outcome(&self.state, *action);
+        self.state = next;
+        RewardT(reward[0] * self.weights[0] + reward[1] * self.weights[1])
+    }
+}
+
+impl MultiObjectiveDPEnvironment for DeepSeaTreasureEnvironment {
+    fn num_objectives(&self) -> usize {
+        2
+    }
+    fn vector_state_transitions(&self) -> HashMap<StateId, Vec<VectorStateTransition>> {
+        let mut table = HashMap::new();
+        for (col, floor) in FLOOR.iter().enumerate() {
+            for row in 0..=*floor {
+                let state = DeepSeaTreasureState {
+                    position: (row, col),
+                };
+                let mut transitions = vec![];
+                if !state.is_terminal() {
+                    for action in GridAction::all() {
+                        let (next, reward) = Self::outcome(&state, action);
+                        transitions.push(VectorStateTransition {
+                            action_id: action.id(),
+                            new_state_id: next.id(),
+                            reward: VectorRewardT(reward.to_vec()),
+                            prob: ProbabilityT(1.0),
+                        });
+                    }
+                }
+                table.insert(state.id(), transitions);
+            }
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::DPConfig;
+    use crate::multi_objective::{dominates, linear_scalarization, pareto_value_iteration};
+    use pretty_assertions::assert_eq;
+
+    // Every treasure is on the front, reached on the shortest path.
+    fn treasure_front() -> Vec<Vec<f64>> {
+        (0..COLS)
+            .map(|col| vec![TREASURES[col], -((FLOOR[col] + col) as f64)])
+            .collect()
+    }
+
+    #[test]
+    fn diving_for_treasures() {
+        let mut env = DeepSeaTreasureEnvironment::new().with_weights([1.0, 0.0]);
+        assert_eq!(env.apply_action(&GridAction::Left), RewardT(0.0));
+        assert_eq!(env.apply_action(&GridAction::Down), RewardT(1.0));
+        assert!(env.state().is_terminal());
+
+        env.reset();
+        env.apply_action(&GridAction::Up);
+        assert_eq!(env.state().position(), (0, 0));
+        env.apply_action(&GridAction::Right);
+        env.apply_action(&GridAction::Down);
+        assert_eq!(env.state().position(), (1, 1));
+        assert_eq!(env.apply_action(&GridAction::Down), RewardT(2.0));
+    }
+
+    #[test]
+    fn pareto_value_iteration_finds_every_treasure() {
+        let table = DeepSeaTreasureEnvironment::new().vector_state_transitions();
+        let fronts = pareto_value_iteration(&table, &DPConfig::default());
+        assert_eq!(fronts[&StateId(0)], treasure_front());
+    }
+
+    #[test]
+    fn linear_scalarization_misses_the_dents_of_the_front() {
+        let table = DeepSeaTreasureEnvironment::new().vector_state_transitions();
+        let front = treasure_front();
+        let mut found: Vec<Vec<f64>> = vec![];
+        for step in 1..20 {
+            let weight = step as f64 / 20.0;
+            let (_, values) =
+                linear_scalarization(&table, &[weight, 1.0 - weight], &DPConfig::default());
+            let point = values[&StateId(0)].clone();
+            assert!(front.contains(&point));
+            if !found.contains(&point) {
+                found.push(point);
+            }
+        }
+        assert!(found.contains(&front[0]));
+        assert!(found.contains(&front[COLS - 1]));
+        // The treasure worth 24 lies below the line between its neighbours
+        // on the front, so no weights make it optimal.
+        assert!(!found.contains(&front[6]));
+        assert!(found.len() < COLS);
+        assert!(!found.iter().any(|a| found.iter().any(|b| dominates(a, b))));
+    }
+}
@@ -0,0 +1,194 @@
+use crate::environment::{
+    ActionId, DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// The stake of a bet, between 1 and the capital or the amount still missing
+// to the goal, whichever is smaller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamblerAction(pub usize);
+
+impl GamblerAction {
+    pub fn id(&self) -> ActionId {
+        ActionId(self.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GamblerState {
+    capital: usize,
+    goal: usize,
+}
+
+impl GamblerState {
+    pub fn capital(&self) -> usize {
+        self.capital
+    }
+
+    pub fn id(&self) -> StateId {
+        StateId(self.capital)
+    }
+
+    pub fn actions(&self) -> Vec<GamblerAction> {
+        if self.is_terminal() {
+            return vec![];
+        }
+        (1..=self.capital.min(self.goal - self.capital))
+            .map(GamblerAction)
+            .collect()
+    }
+}
+
+impl State for GamblerState {
+    fn is_terminal(&self) -> bool {
+        self.capital == 0 || self.capital == self.goal
+    }
+}
+
+// Example 4.3 of Sutton & Barto: a gambler bets on coin flips until they
+// either reach the goal, which is rewarded with 1, or lose all their money.
+#[derive(Debug)]
+pub struct GamblerEnvironment {
+    state: GamblerState,
+    head_prob: f64,
+    rng: StdRng,
+}
+
+impl GamblerEnvironment {
+    pub fn new(goal: usize, head_prob: f64, seed: u64) -> Self {
+        if goal < 2 {
+            panic!("the goal needs to be at least 2, got {}", goal);
+        }
+        if !(0.0..=1.0).contains(&head_prob) {
+            panic!("head probability {} is not a probability", head_prob);
+        }
+        GamblerEnvironment {
+            state: GamblerState {
+                capital: goal / 2,
+                goal,
+            },
+            head_prob,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn with_capital(mut self, capital: usize) -> Self {
+        if capital > self.state.goal {
+            panic!("capital {} exceeds the goal {}", capital, self.state.goal);
+        }
+        self.state.capital = capital;
+        self
+    }
+
+    fn reward_for_capital(&self, capital: usize) -> RewardT {
+        if capital == self.state.goal {
+            RewardT(1.0)
+        } else {
+            RewardT(0.0)
+        }
+    }
+}
+
+impl Environment for GamblerEnvironment {
+    type Action = GamblerAction;
+    type State = GamblerState;
+
+    fn state(&self) -> &GamblerState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<GamblerAction> {
+        self.state.actions()
+    }
+    fn apply_action(&mut self, action: &GamblerAction) -> RewardT {
+        if !self.state.actions().contains(action) {
+            panic!("can't bet {:?} in {:?}", action, self.state);
+        }
+        if self.rng.gen_bool(self.head_prob) {
+            self.state.capital += action.0;
+        } else {
+            self.state.capital -= action.0;
+        }
+        self.reward_for_capital(self.state.capital)
+    }
+}
+
+impl DPEnvironment for GamblerEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        (0..=self.state.goal)
+            .map(|capital| {
+                let state = GamblerState {
+                    capital,
+                    goal: self.state.goal,
+                };
+                let transitions = state
+                    .actions()
+                    .iter()
+                    .flat_map(|action| {
+                        [
+                            (capital + action.0, self.head_prob),
+                            (capital - action.0, 1.0 - self.head_prob),
+                        ]
+                        .map(|(new_capital, prob)| StateTransition {
+                            action_id: action.id(),
+                            new_state_id: StateId(new_capital),
+                            reward: self.reward_for_capital(new_capital),
+                            prob: ProbabilityT(prob),
+                        })
+                    })
+                    .collect();
+                (state.id(), transitions)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{action_values, lp_values, rms_error, value_iteration, DPConfig};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn optimal_values_of_bold_play() {
+        let env = GamblerEnvironment::new(100, 0.4, 0);
+        let table = env.state_transitions();
+        assert_eq!(table.len(), 101);
+        let values = value_iteration(&table, &DPConfig::default());
+        // Betting everything at 50 wins with the head probability, which is
+        // the best the gambler can do when the coin is against them.
+        assert!((values[&StateId(50)] - 0.4).abs() < 1e-6);
+        assert!((values[&StateId(25)] - 0.16).abs() < 1e-6);
+        assert!((values[&StateId(75)] - (0.4 + 0.6 * 0.4)).abs() < 1e-6);
+
+        let action_values = action_values(&table[&StateId(50)], &values, 1.0);
+        let best = action_values
+            .iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        assert_eq!(best.0, GamblerAction(50).id());
+    }
+
+    #[test]
+    fn linear_program_agrees_with_value_iteration() {
+        // Every policy ends in ruin or at the goal, so the LP has a solution
+        // without discounting.
+        let table = GamblerEnvironment::new(20, 0.4, 0).state_transitions();
+        let values = value_iteration(&table, &DPConfig::default());
+        let lp_values = lp_values(&table, 1.0).unwrap();
+        assert!(rms_error(&lp_values, &values) < 1e-6);
+    }
+
+    #[test]
+    fn episode_ends_at_goal_or_ruin() {
+        let mut env = GamblerEnvironment::new(10, 0.5, 3).with_capital(3);
+        let mut reward = RewardT(0.0);
+        while !env.state().is_terminal() {
+            let action = *env.actions().last().unwrap();
+            reward = env.apply_action(&action);
+        }
+        let won = env.state().capital() == 10;
+        assert_eq!(reward, RewardT(if won { 1.0 } else { 0.0 }));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod access_control;
+pub mod car_rental;
+pub mod chain;
+pub mod deep_sea_treasure;
+pub mod gambler;
+pub mod random_walk;
@@ -0,0 +1,201 @@
+use crate::dp::ValueTable;
+use crate::environment::{
+    ActionId, DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// The walk has no decisions, every step goes left or right with equal
+// probability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Step;
+
+impl Step {
+    pub fn id(&self) -> ActionId {
+        ActionId(0)
+    }
+}
+
+// Position 0 and `num_states + 1` are the terminal states at both ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RandomWalkState {
+    position: usize,
+    num_states: usize,
+}
+
+impl RandomWalkState {
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn id(&self) -> StateId {
+        StateId(self.position)
+    }
+}
+
+impl State for RandomWalkState {
+    fn is_terminal(&self) -> bool {
+        self.position == 0 || self.position == self.num_states + 1
+    }
+}
+
+// The random walks of Example 6.2 and 7.1 of Sutton & Barto, which start in
+// the middle and end at either side with the side's reward.
+#[derive(Debug)]
+pub struct RandomWalkEnvironment {
+    state: RandomWalkState,
+    left_reward: f64,
+    right_reward: f64,
+    rng: StdRng,
+}
+
+impl RandomWalkEnvironment {
+    pub fn new(num_states: usize, left_reward: f64, right_reward: f64, seed: u64) -> Self {
+        if num_states == 0 {
+            panic!("a random walk needs at least one non-terminal state");
+        }
+        RandomWalkEnvironment {
+            state: RandomWalkState {
+                position: num_states.div_ceil(2),
+                num_states,
+            },
+            left_reward,
+            right_reward,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // Example 6.2: only the right end is rewarded.
+    pub fn five_states(seed: u64) -> Self {
+        RandomWalkEnvironment::new(5, 0.0, 1.0, seed)
+    }
+
+    // Example 7.1: the left end is punished.
+    pub fn nineteen_states(seed: u64) -> Self {
+        RandomWalkEnvironment::new(19, -1.0, 1.0, seed)
+    }
+
+    pub fn reset(&mut self) {
+        self.state.position = self.state.num_states.div_ceil(2);
+    }
+
+    pub fn num_states(&self) -> usize {
+        self.state.num_states
+    }
+
+    // Undiscounted values of the non-terminal states. The probability of
+    // ending on the right grows linearly from one end to the other.
+    pub fn true_values(&self) -> ValueTable {
+        let n = self.state.num_states;
+        (1..=n)
+            .map(|position| {
+                let right_prob = position as f64 / (n + 1) as f64;
+                let value = self.left_reward + right_prob * (self.right_reward - self.left_reward);
+                (StateId(position), value)
+            })
+            .collect()
+    }
+
+    fn reward_for_position(&self, position: usize) -> RewardT {
+        if position == 0 {
+            RewardT(self.left_reward)
+        } else if position == self.state.num_states + 1 {
+            RewardT(self.right_reward)
+        } else {
+            RewardT(0.0)
+        }
+    }
+}
+
+impl Environment for RandomWalkEnvironment {
+    type Action = Step;
+    type State = RandomWalkState;
+
+    fn state(&self) -> &RandomWalkState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<Step> {
+        if self.state.is_terminal() {
+            return vec![];
+        }
+        vec![Step]
+    }
+    fn apply_action(&mut self, _: &Step) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("tried applying an action to a terminal state");
+        }
+        if self.rng.gen_bool(0.5) {
+            self.state.position += 1;
+        } else {
+            self.state.position -= 1;
+        }
+        self.reward_for_position(self.state.position)
+    }
+}
+
+impl DPEnvironment for RandomWalkEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        let n = self.state.num_states;
+        let mut transition_table: HashMap<StateId, Vec<StateTransition>> = (1..=n)
+            .map(|position| {
+                let transitions = [position - 1, position + 1]
+                    .map(|new_position| StateTransition {
+                        action_id: Step.id(),
+                        new_state_id: StateId(new_position),
+                        reward: self.reward_for_position(new_position),
+                        prob: ProbabilityT(0.5),
+                    })
+                    .to_vec();
+                (StateId(position), transitions)
+            })
+            .collect();
+        transition_table.insert(StateId(0), vec![]);
+        transition_table.insert(StateId(n + 1), vec![]);
+        transition_table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{rms_error, value_iteration, DPConfig};
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn true_values_match_dp() {
+        for env in [
+            RandomWalkEnvironment::five_states(0),
+            RandomWalkEnvironment::nineteen_states(0),
+        ] {
+            let values = value_iteration(&env.state_transitions(), &DPConfig::default());
+            assert!(rms_error(&values, &env.true_values()) < 1e-6);
+        }
+        let true_values = RandomWalkEnvironment::five_states(0).true_values();
+        assert_eq!(true_values[&StateId(1)], 1.0 / 6.0);
+        assert_eq!(true_values[&StateId(3)], 0.5);
+    }
+
+    #[test]
+    fn td_zero_converges() {
+        let mut env = RandomWalkEnvironment::nineteen_states(7);
+        let mut values: ValueTable = HashMap::new();
+        let step_size = 0.05;
+        for _ in 0..1000 {
+            env.reset();
+            while !env.state().is_terminal() {
+                let state_id = env.state().id();
+                let reward = env.apply_action(&Step);
+                let next_value = if env.state().is_terminal() {
+                    0.0
+                } else {
+                    *values.get(&env.state().id()).unwrap_or(&0.0)
+                };
+                let value = values.entry(state_id).or_insert(0.0);
+                *value += step_size * (reward.0 + next_value - *value);
+            }
+        }
+        let error = rms_error(&values, &env.true_values());
+        assert!(error < 0.1, "{}", error);
+    }
+}
@@ -0,0 +1,24 @@
+use crate::environment::ActionId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BlackjackAction {
+    Hit,
+    Stick,
+}
+
+impl BlackjackAction {
+    pub fn all() -> [BlackjackAction; 2] {
+        [BlackjackAction::Hit, BlackjackAction::Stick]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            BlackjackAction::Hit => ActionId(0),
+            BlackjackAction::Stick => ActionId(1),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> BlackjackAction {
+        BlackjackAction::all()[action_id.0]
+    }
+}
@@ -0,0 +1,126 @@
+use rand::Rng;
+
+// Cards are identified by their value, aces count as 1 and face cards as 10.
+pub const ACE: usize = 1;
+pub const MAX_CARD: usize = 10;
+
+pub const BLACKJACK: usize = 21;
+pub const DEALER_STICKS_AT: usize = 17;
+
+// Cards come from an infinite deck, i.e. they are drawn with replacement.
+pub fn draw<R: Rng>(rng: &mut R) -> usize {
+    rng.gen_range(1..=13).min(MAX_CARD)
+}
+
+// The probability of drawing every card value, tens, jacks, queens and kings
+// all count as 10.
+pub fn card_probabilities() -> impl Iterator<Item = (usize, f64)> {
+    (ACE..=MAX_CARD).map(|card| {
+        let ranks = if card == MAX_CARD { 4.0 } else { 1.0 };
+        (card, ranks / 13.0)
+    })
+}
+
+// The probability that the dealer's hidden card makes a natural with the
+// showing one.
+pub fn natural_probability(showing: usize) -> f64 {
+    card_probabilities()
+        .filter(|&(hidden, _)| Hand::default().add(showing).add(hidden).sum() == BLACKJACK)
+        .map(|(_, prob)| prob)
+        .sum()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Hand {
+    sum: usize,
+    // An ace that is counted as 11 without going bust.
+    usable_ace: bool,
+}
+
+impl Hand {
+    pub fn new(sum: usize, usable_ace: bool) -> Hand {
+        Hand { sum, usable_ace }
+    }
+
+    pub fn sum(&self) -> usize {
+        self.sum
+    }
+
+    pub fn usable_ace(&self) -> bool {
+        self.usable_ace
+    }
+
+    pub fn is_bust(&self) -> bool {
+        self.sum > BLACKJACK
+    }
+
+    pub fn add(&self, card: usize) -> Hand {
+        let mut hand = Hand {
+            sum: self.sum + card,
+            usable_ace: self.usable_ace,
+        };
+        if card == ACE && !hand.usable_ace && hand.sum + 10 <= BLACKJACK {
+            hand.sum += 10;
+            hand.usable_ace = true;
+        }
+        if hand.sum > BLACKJACK && hand.usable_ace {
+            hand.sum -= 10;
+            hand.usable_ace = false;
+        }
+        hand
+    }
+}
+
+// Probabilities of the dealer's final sum being 17, 18, 19, 20 or 21, the
+// last entry is the probability of going bust.
+pub type DealerOutcomes = [f64; BLACKJACK - DEALER_STICKS_AT + 2];
+
+// The dealer hits until reaching 17 or more, soft 17 included.
+pub fn dealer_outcomes(showing: usize) -> DealerOutcomes {
+    fn play(hand: Hand, prob: f64, outcomes: &mut DealerOutcomes) {
+        if hand.is_bust() {
+            outcomes[outcomes.len() - 1] += prob;
+        } else if hand.sum() >= DEALER_STICKS_AT {
+            outcomes[hand.sum() - DEALER_STICKS_AT] += prob;
+        } else {
+            for (card, card_prob) in card_probabilities() {
+                play(hand.add(card), prob * card_prob, outcomes);
+            }
+        }
+    }
+
+    let mut outcomes = [0.0; BLACKJACK - DEALER_STICKS_AT + 2];
+    play(Hand::default().add(showing), 1.0, &mut outcomes);
+    outcomes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn aces_count_as_eleven_while_possible() {
+        let hand = Hand::default().add(ACE);
+        assert_eq!(hand, Hand::new(11, true));
+        let hand = hand.add(ACE);
+        assert_eq!(hand, Hand::new(12, true));
+        let hand = hand.add(MAX_CARD);
+        assert_eq!(hand, Hand::new(12, false));
+        let hand = hand.add(ACE);
+        assert_eq!(hand, Hand::new(13, false));
+        assert!(hand.add(9).is_bust());
+    }
+
+    #[test]
+    fn dealer_outcomes_are_distributions() {
+        for showing in ACE..=MAX_CARD {
+            let total: f64 = dealer_outcomes(showing).iter().sum();
+            assert!((total - 1.0).abs() < 1e-12);
+        }
+        // A dealer showing a six busts most often.
+        let bust = |showing| dealer_outcomes(showing)[5];
+        assert!((ACE..=MAX_CARD).all(|showing| bust(showing) <= bust(6)));
+        assert!((bust(6) - 0.4228).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,332 @@
+use crate::blackjack::action::BlackjackAction;
+use crate::blackjack::card::{self, Hand, BLACKJACK, DEALER_STICKS_AT};
+use crate::blackjack::state::{BlackjackState, Outcome, MIN_DECISION_SUM};
+use crate::environment::{
+    DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::collections::HashMap;
+
+// Blackjack as in Example 5.1 of Sutton & Barto: cards come from an infinite
+// deck and the dealer sticks on 17 or more. A player dealt a natural wins
+// unless the dealer has one as well, which is a draw. The game is settled by
+// whichever action comes next, so its reward is paid like any other.
+#[derive(Debug)]
+pub struct BlackjackEnvironment {
+    state: BlackjackState,
+    rng: StdRng,
+}
+
+impl BlackjackEnvironment {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let state = BlackjackEnvironment::deal(&mut rng);
+        BlackjackEnvironment { state, rng }
+    }
+
+    // Starts the game from a decision state, the dealer's hidden card is only
+    // drawn once the player sticks.
+    pub fn with_state(state: BlackjackState, seed: u64) -> Self {
+        BlackjackEnvironment {
+            state,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = BlackjackEnvironment::deal(&mut self.rng);
+    }
+
+    fn deal(rng: &mut StdRng) -> BlackjackState {
+        let mut player = Hand::default().add(card::draw(rng)).add(card::draw(rng));
+        if player.sum() == BLACKJACK {
+            return BlackjackState::natural(card::draw(rng));
+        }
+        while player.sum() < MIN_DECISION_SUM {
+            player = player.add(card::draw(rng));
+        }
+        BlackjackState::new(player, card::draw(rng))
+    }
+
+    fn play_dealer(&mut self) -> Hand {
+        let mut dealer = Hand::default().add(self.state.dealer_showing());
+        while dealer.sum() < DEALER_STICKS_AT {
+            dealer = dealer.add(card::draw(&mut self.rng));
+        }
+        dealer
+    }
+}
+
+impl Environment for BlackjackEnvironment {
+    type Action = BlackjackAction;
+    type State = BlackjackState;
+
+    fn state(&self) -> &BlackjackState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<BlackjackAction> {
+        if self.state.is_terminal() {
+            return vec![];
+        }
+        BlackjackAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &BlackjackAction) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("tried applying an action to a terminal state");
+        }
+        if self.state.is_natural() {
+            let dealer = Hand::default()
+                .add(self.state.dealer_showing())
+                .add(card::draw(&mut self.rng));
+            let outcome = if dealer.sum() == BLACKJACK {
+                Outcome::Draw
+            } else {
+                Outcome::Win
+            };
+            self.state = self.state.finished(outcome);
+            return outcome.reward();
+        }
+        match action {
+            BlackjackAction::Hit => {
+                let player = self.state.player().add(card::draw(&mut self.rng));
+                self.state = BlackjackState::new(player, self.state.dealer_showing());
+                if player.is_bust() {
+                    self.state = self.state.finished(Outcome::Loss);
+                }
+            }
+            BlackjackAction::Stick => {
+                let dealer = self.play_dealer();
+                let outcome = Outcome::of_showdown(self.state.player(), dealer);
+                self.state = self.state.finished(outcome);
+            }
+        }
+        match self.state.outcome() {
+            Some(outcome) => outcome.reward(),
+            None => RewardT(0.0),
+        }
+    }
+}
+
+fn add_transition(
+    transitions: &mut Vec<StateTransition>,
+    action: BlackjackAction,
+    new_state_id: StateId,
+    reward: RewardT,
+    prob: f64,
+) {
+    match transitions
+        .iter_mut()
+        .find(|t| t.action_id == action.id() && t.new_state_id == new_state_id)
+    {
+        Some(transition) => transition.prob.0 += prob,
+        None => transitions.push(StateTransition {
+            action_id: action.id(),
+            new_state_id,
+            reward,
+            prob: ProbabilityT(prob),
+        }),
+    }
+}
+
+// The exact model of all decision states and naturals, computed from the
+// card probabilities. Every outcome of the game has its own terminal state.
+impl DPEnvironment for BlackjackEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        let mut transition_table = HashMap::new();
+        for state in BlackjackState::decision_states() {
+            let mut transitions = vec![];
+            for (card, prob) in card::card_probabilities() {
+                let player = state.player().add(card);
+                let new_state = BlackjackState::new(player, state.dealer_showing());
+                let new_state = if player.is_bust() {
+                    new_state.finished(Outcome::Loss)
+                } else {
+                    new_state
+                };
+                let reward = match new_state.outcome() {
+                    Some(outcome) => outcome.reward(),
+                    None => RewardT(0.0),
+                };
+                add_transition(
+                    &mut transitions,
+                    BlackjackAction::Hit,
+                    new_state.id(),
+                    reward,
+                    prob,
+                );
+            }
+            let dealer_outcomes = card::dealer_outcomes(state.dealer_showing());
+            for (index, &prob) in dealer_outcomes.iter().enumerate() {
+                // The last entry is the dealer going bust, which 17 + 5 does.
+                let dealer = Hand::new(DEALER_STICKS_AT + index, false);
+                let outcome = Outcome::of_showdown(state.player(), dealer);
+                add_transition(
+                    &mut transitions,
+                    BlackjackAction::Stick,
+                    BlackjackState::terminal_id(outcome),
+                    outcome.reward(),
+                    prob,
+                );
+            }
+            transition_table.insert(state.id(), transitions);
+        }
+        for state in BlackjackState::natural_states() {
+            let draw_prob = card::natural_probability(state.dealer_showing());
+            let mut transitions = vec![];
+            for action in BlackjackAction::all() {
+                for (outcome, prob) in [(Outcome::Draw, draw_prob), (Outcome::Win, 1.0 - draw_prob)]
+                {
+                    if prob > 0.0 {
+                        add_transition(
+                            &mut transitions,
+                            action,
+                            BlackjackState::terminal_id(outcome),
+                            outcome.reward(),
+                            prob,
+                        );
+                    }
+                }
+            }
+            transition_table.insert(state.id(), transitions);
+        }
+        for outcome in Outcome::all() {
+            transition_table.insert(BlackjackState::terminal_id(outcome), vec![]);
+        }
+        transition_table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blackjack::card::ACE;
+    use crate::dp::{greedy_policy, value_iteration, DPConfig, Policy};
+    use pretty_assertions::assert_eq;
+
+    fn optimal_action(
+        policy: &Policy,
+        sum: usize,
+        usable_ace: bool,
+        showing: usize,
+    ) -> BlackjackAction {
+        let state = BlackjackState::new(Hand::new(sum, usable_ace), showing);
+        BlackjackAction::action_with_id(policy[&state.id()])
+    }
+
+    #[test]
+    fn optimal_policy_matches_sutton_barto() {
+        let env = BlackjackEnvironment::new(0);
+        let table = env.state_transitions();
+        assert_eq!(table.len(), 213);
+        let values = value_iteration(&table, &DPConfig::default());
+        let policy = greedy_policy(&table, &values, 1.0);
+
+        // Figure 5.2: without a usable ace stick on 13 to 16 against a weak
+        // dealer card only.
+        assert_eq!(optimal_action(&policy, 12, false, 2), BlackjackAction::Hit);
+        assert_eq!(
+            optimal_action(&policy, 12, false, 5),
+            BlackjackAction::Stick
+        );
+        assert_eq!(
+            optimal_action(&policy, 13, false, 2),
+            BlackjackAction::Stick
+        );
+        assert_eq!(optimal_action(&policy, 16, false, 7), BlackjackAction::Hit);
+        assert_eq!(
+            optimal_action(&policy, 16, false, ACE),
+            BlackjackAction::Hit
+        );
+        assert_eq!(
+            optimal_action(&policy, 17, false, 10),
+            BlackjackAction::Stick
+        );
+        // With a usable ace hit on 18 against a strong dealer card.
+        assert_eq!(optimal_action(&policy, 17, true, 6), BlackjackAction::Hit);
+        assert_eq!(optimal_action(&policy, 18, true, 8), BlackjackAction::Stick);
+        assert_eq!(optimal_action(&policy, 18, true, 9), BlackjackAction::Hit);
+        assert_eq!(
+            optimal_action(&policy, 19, true, 10),
+            BlackjackAction::Stick
+        );
+    }
+
+    #[test]
+    fn model_matches_sampled_episodes() {
+        // Sticking on 20 with a dealer showing a ten.
+        let state = BlackjackState::new(Hand::new(20, false), 10);
+        let table = BlackjackEnvironment::new(0).state_transitions();
+        let expected: f64 = table[&state.id()]
+            .iter()
+            .filter(|t| t.action_id == BlackjackAction::Stick.id())
+            .map(|t| t.prob.0 * t.reward.0)
+            .sum();
+
+        let episodes = 20000;
+        let mut env = BlackjackEnvironment::with_state(state, 0);
+        let mut total = 0.0;
+        for _ in 0..episodes {
+            env.state = state;
+            total += env.apply_action(&BlackjackAction::Stick).0;
+            assert!(env.state().is_terminal());
+        }
+        let average = total / episodes as f64;
+        assert!(
+            (average - expected).abs() < 0.02,
+            "{} {}",
+            average,
+            expected
+        );
+    }
+
+    #[test]
+    fn episodes_start_at_decisions() {
+        let mut env = BlackjackEnvironment::new(1);
+        for _ in 0..100 {
+            env.reset();
+            assert!(env.state().player().sum() >= MIN_DECISION_SUM);
+            let natural = env.state().is_natural();
+            while !env.state().is_terminal() {
+                let reward = env.apply_action(&BlackjackAction::Hit);
+                assert_eq!(reward.0 != 0.0, env.state().is_terminal());
+            }
+            if !natural {
+                assert_eq!(env.state().outcome(), Some(Outcome::Loss));
+            }
+        }
+    }
+
+    #[test]
+    fn naturals_win_unless_the_dealer_has_one() {
+        let table = BlackjackEnvironment::new(0).state_transitions();
+        let value = |showing| {
+            let state = BlackjackState::natural(showing);
+            table[&state.id()]
+                .iter()
+                .filter(|t| t.action_id == BlackjackAction::Hit.id())
+                .map(|t| t.prob.0 * t.reward.0)
+                .sum::<f64>()
+        };
+        assert_eq!(value(5), 1.0);
+        assert!((value(ACE) - 9.0 / 13.0).abs() < 1e-12);
+        assert!((value(10) - 12.0 / 13.0).abs() < 1e-12);
+
+        // Even hitting on a natural doesn't lose it.
+        let mut env = BlackjackEnvironment::with_state(BlackjackState::natural(5), 0);
+        assert_eq!(env.apply_action(&BlackjackAction::Hit), RewardT(1.0));
+        assert_eq!(env.state().outcome(), Some(Outcome::Win));
+
+        let episodes = 10000;
+        let mut draws = 0;
+        for seed in 0..episodes {
+            let mut env = BlackjackEnvironment::with_state(BlackjackState::natural(ACE), seed);
+            if env.apply_action(&BlackjackAction::Stick) == RewardT(0.0) {
+                assert_eq!(env.state().outcome(), Some(Outcome::Draw));
+                draws += 1;
+            }
+        }
+        let draw_rate = draws as f64 / episodes as f64;
+        assert!((draw_rate - 4.0 / 13.0).abs() < 0.02, "{}", draw_rate);
+    }
+}
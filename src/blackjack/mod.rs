@@ -0,0 +1,4 @@
+pub mod action;
+pub mod card;
+pub mod environment;
+pub mod state;
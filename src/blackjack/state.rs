@@ -0,0 +1,176 @@
+use crate::blackjack::card::{Hand, ACE, BLACKJACK, MAX_CARD};
+use crate::environment::{RewardT, State, StateId};
+
+// Below this sum hitting can't go bust, so the player hits automatically and
+// only sums from 12 on are decisions.
+pub const MIN_DECISION_SUM: usize = 12;
+const NUM_SUMS: usize = BLACKJACK - MIN_DECISION_SUM + 1;
+// Player sum x dealer's showing card x usable ace.
+pub const NUM_DECISION_STATES: usize = NUM_SUMS * MAX_CARD * 2;
+// A natural against every showing card.
+pub const NUM_NATURAL_STATES: usize = MAX_CARD;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    pub fn all() -> [Outcome; 3] {
+        [Outcome::Loss, Outcome::Draw, Outcome::Win]
+    }
+
+    pub fn reward(&self) -> RewardT {
+        match self {
+            Outcome::Loss => RewardT(-1.0),
+            Outcome::Draw => RewardT(0.0),
+            Outcome::Win => RewardT(1.0),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            Outcome::Loss => 0,
+            Outcome::Draw => 1,
+            Outcome::Win => 2,
+        }
+    }
+
+    // Compares the player's sum to the dealer's final one.
+    pub fn of_showdown(player: Hand, dealer: Hand) -> Outcome {
+        if player.is_bust() {
+            Outcome::Loss
+        } else if dealer.is_bust() || player.sum() > dealer.sum() {
+            Outcome::Win
+        } else if player.sum() == dealer.sum() {
+            Outcome::Draw
+        } else {
+            Outcome::Loss
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlackjackState {
+    player: Hand,
+    dealer_showing: usize,
+    // The player was dealt an ace and a ten, which settles the game before
+    // any decision.
+    natural: bool,
+    outcome: Option<Outcome>,
+}
+
+impl BlackjackState {
+    pub fn new(player: Hand, dealer_showing: usize) -> BlackjackState {
+        if !(ACE..=MAX_CARD).contains(&dealer_showing) {
+            panic!("{} is not a card", dealer_showing);
+        }
+        BlackjackState {
+            player,
+            dealer_showing,
+            natural: false,
+            outcome: None,
+        }
+    }
+
+    pub fn natural(dealer_showing: usize) -> BlackjackState {
+        BlackjackState {
+            natural: true,
+            ..BlackjackState::new(Hand::new(BLACKJACK, true), dealer_showing)
+        }
+    }
+
+    pub fn player(&self) -> Hand {
+        self.player
+    }
+
+    pub fn dealer_showing(&self) -> usize {
+        self.dealer_showing
+    }
+
+    pub fn is_natural(&self) -> bool {
+        self.natural
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    pub fn finished(&self, outcome: Outcome) -> BlackjackState {
+        BlackjackState {
+            outcome: Some(outcome),
+            ..*self
+        }
+    }
+
+    // Decision states come first, followed by the naturals and one terminal
+    // state per outcome. The hands of finished games don't matter anymore.
+    pub fn id(&self) -> StateId {
+        if let Some(outcome) = self.outcome {
+            return BlackjackState::terminal_id(outcome);
+        }
+        if self.natural {
+            return StateId(NUM_DECISION_STATES + self.dealer_showing - ACE);
+        }
+        if self.player.sum() < MIN_DECISION_SUM || self.player.is_bust() {
+            panic!("{:?} is not a decision state", self);
+        }
+        let usable_ace = self.player.usable_ace() as usize;
+        StateId(
+            (usable_ace * NUM_SUMS + self.player.sum() - MIN_DECISION_SUM) * MAX_CARD
+                + self.dealer_showing
+                - ACE,
+        )
+    }
+
+    pub fn terminal_id(outcome: Outcome) -> StateId {
+        StateId(NUM_DECISION_STATES + NUM_NATURAL_STATES + outcome.index())
+    }
+
+    // All states the player has to decide in.
+    pub fn decision_states() -> impl Iterator<Item = BlackjackState> {
+        [false, true].into_iter().flat_map(|usable_ace| {
+            (MIN_DECISION_SUM..=BLACKJACK).flat_map(move |sum| {
+                (ACE..=MAX_CARD)
+                    .map(move |showing| BlackjackState::new(Hand::new(sum, usable_ace), showing))
+            })
+        })
+    }
+
+    // The states right after dealing a natural.
+    pub fn natural_states() -> impl Iterator<Item = BlackjackState> {
+        (ACE..=MAX_CARD).map(BlackjackState::natural)
+    }
+}
+
+impl State for BlackjackState {
+    fn is_terminal(&self) -> bool {
+        self.outcome.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ids_are_dense() {
+        let mut ids: Vec<usize> = BlackjackState::decision_states()
+            .chain(BlackjackState::natural_states())
+            .map(|state| state.id().0)
+            .chain(
+                Outcome::all()
+                    .iter()
+                    .map(|&o| BlackjackState::terminal_id(o).0),
+            )
+            .collect();
+        ids.sort();
+        assert_eq!(
+            ids,
+            (0..NUM_DECISION_STATES + NUM_NATURAL_STATES + 3).collect::<Vec<usize>>()
+        );
+    }
+}
@@ -0,0 +1,424 @@
+// Counterfactual regret minimization (Zinkevich et al., 2007) for
+// `ExtensiveFormGame`s. Every information set runs regret matching on its
+// counterfactual regrets, and the average strategy of all iterations
+// converges to an equilibrium of two-player zero-sum games.
+use crate::environment::Player;
+use crate::extensive_form::{ExtensiveFormGame, NodeKind};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CfrVariant {
+    // Walks the whole tree every iteration.
+    Vanilla,
+    // CFR+ of Tammelin (2014): regrets are floored at zero and later
+    // iterations weigh more in the average strategy.
+    Plus,
+    // Monte Carlo CFR of Lanctot et al. (2009) sampling chance and the
+    // opponent, but every action of the player whose regrets are updated.
+    ExternalSampling,
+    // Monte Carlo CFR sampling a single terminal history per iteration. The
+    // updated player explores uniformly with probability `exploration`.
+    OutcomeSampling { exploration: f64 },
+}
+
+#[derive(Debug, Clone)]
+struct InfoSetNode {
+    regrets: Vec<f64>,
+    strategy_sum: Vec<f64>,
+}
+
+impl InfoSetNode {
+    fn new(num_actions: usize) -> Self {
+        InfoSetNode {
+            regrets: vec![0.0; num_actions],
+            strategy_sum: vec![0.0; num_actions],
+        }
+    }
+
+    // Regret matching: actions are played in proportion to their positive
+    // regret, uniformly if there is none.
+    fn strategy(&self) -> Vec<f64> {
+        let positive: Vec<f64> = self.regrets.iter().map(|r| r.max(0.0)).collect();
+        let total: f64 = positive.iter().sum();
+        if total > 0.0 {
+            positive.iter().map(|r| r / total).collect()
+        } else {
+            vec![1.0 / self.regrets.len() as f64; self.regrets.len()]
+        }
+    }
+
+    fn average_strategy(&self) -> Vec<f64> {
+        let total: f64 = self.strategy_sum.iter().sum();
+        if total > 0.0 {
+            self.strategy_sum.iter().map(|s| s / total).collect()
+        } else {
+            vec![1.0 / self.strategy_sum.len() as f64; self.strategy_sum.len()]
+        }
+    }
+}
+
+// Each iteration updates the regrets of the first and then of the second
+// player. The regrets collected while walking the tree only take effect once
+// the walk is done, so all histories of an information set see the same
+// strategy.
+#[derive(Debug)]
+pub struct Cfr<G: ExtensiveFormGame> {
+    game: G,
+    variant: CfrVariant,
+    nodes: HashMap<G::InfoSet, InfoSetNode>,
+    pending_regrets: HashMap<G::InfoSet, Vec<f64>>,
+    iterations: usize,
+    rng: StdRng,
+}
+
+impl<G: ExtensiveFormGame> Cfr<G> {
+    pub fn new(game: G, variant: CfrVariant, seed: u64) -> Self {
+        if let CfrVariant::OutcomeSampling { exploration } = variant {
+            if !(0.0..=1.0).contains(&exploration) {
+                panic!("the exploration has to be in [0, 1], got {}", exploration);
+            }
+        }
+        Cfr {
+            game,
+            variant,
+            nodes: HashMap::new(),
+            pending_regrets: HashMap::new(),
+            iterations: 0,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    pub fn iterations(&self) -> usize {
+        self.iterations
+    }
+
+    // Information sets that have been visited so far.
+    pub fn num_info_sets(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn run(&mut self, iterations: usize) {
+        for _ in 0..iterations {
+            self.iterations += 1;
+            for player in [Player::First, Player::Second] {
+                let root = self.game.root();
+                self.traverse(&root, player, [1.0, 1.0], 1.0);
+                self.apply_regrets();
+            }
+        }
+    }
+
+    // The strategy of the current iteration, which doesn't converge by itself.
+    pub fn current_strategy(&self, info_set: &G::InfoSet, actions: &[G::Action]) -> Vec<f64> {
+        match self.nodes.get(info_set) {
+            Some(node) => node.strategy(),
+            None => vec![1.0 / actions.len() as f64; actions.len()],
+        }
+    }
+
+    // The probabilities of `actions` in `info_set` under the average
+    // strategy, uniform for information sets that were never reached. This
+    // can be passed to `expected_rewards` and `exploitability`.
+    pub fn average_strategy(&self, info_set: &G::InfoSet, actions: &[G::Action]) -> Vec<f64> {
+        match self.nodes.get(info_set) {
+            Some(node) => node.average_strategy(),
+            None => vec![1.0 / actions.len() as f64; actions.len()],
+        }
+    }
+
+    fn apply_regrets(&mut self) {
+        let floor = self.variant == CfrVariant::Plus;
+        for (info_set, deltas) in self.pending_regrets.drain() {
+            let node = self.nodes.get_mut(&info_set).unwrap();
+            for (regret, delta) in node.regrets.iter_mut().zip(deltas) {
+                *regret += delta;
+                if floor {
+                    *regret = regret.max(0.0);
+                }
+            }
+        }
+    }
+
+    fn add_regrets(&mut self, info_set: G::InfoSet, regrets: Vec<f64>) {
+        let pending = self
+            .pending_regrets
+            .entry(info_set)
+            .or_insert_with(|| vec![0.0; regrets.len()]);
+        for (total, regret) in pending.iter_mut().zip(regrets) {
+            *total += regret;
+        }
+    }
+
+    fn add_to_average(&mut self, info_set: &G::InfoSet, strategy: &[f64], weight: f64) {
+        let node = self.nodes.get_mut(info_set).unwrap();
+        for (sum, prob) in node.strategy_sum.iter_mut().zip(strategy) {
+            *sum += weight * prob;
+        }
+    }
+
+    fn sample(&mut self, probs: &[f64]) -> usize {
+        let mut sample: f64 = self.rng.gen();
+        for (i, prob) in probs.iter().enumerate() {
+            if sample < *prob {
+                return i;
+            }
+            sample -= prob;
+        }
+        probs.len() - 1
+    }
+
+    // Returns the value of `history` for `player`, exact for the full-width
+    // variants and an unbiased estimate for the sampling ones. `reach` holds
+    // the probabilities of both players to play towards `history`, `chance`
+    // that of chance. Outcome sampling keeps the probability that `player`'s
+    // sampling reaches `history` in `chance` instead, which is what its
+    // updates are divided by.
+    fn traverse(
+        &mut self,
+        history: &G::History,
+        player: Player,
+        reach: [f64; 2],
+        chance: f64,
+    ) -> f64 {
+        let full_width = matches!(self.variant, CfrVariant::Vanilla | CfrVariant::Plus);
+        let acting = match self.game.node_kind(history) {
+            NodeKind::Terminal => return self.game.rewards(history)[player.index()].0,
+            NodeKind::Chance => {
+                let outcomes = self.game.chance_outcomes(history);
+                if !full_width {
+                    let probs: Vec<f64> = outcomes.iter().map(|(_, prob)| prob.0).collect();
+                    let (outcome, _) = &outcomes[self.sample(&probs)];
+                    let next = self.game.apply(history, outcome);
+                    return self.traverse(&next, player, reach, chance);
+                }
+                let mut value = 0.0;
+                for (outcome, prob) in outcomes {
+                    let next = self.game.apply(history, &outcome);
+                    value += prob.0 * self.traverse(&next, player, reach, chance * prob.0);
+                }
+                return value;
+            }
+            NodeKind::Decision(acting) => acting,
+        };
+
+        let info_set = self.game.info_set(history);
+        let actions = self.game.actions(history);
+        let strategy = self
+            .nodes
+            .entry(info_set.clone())
+            .or_insert_with(|| InfoSetNode::new(actions.len()))
+            .strategy();
+        let index = acting.index();
+
+        match self.variant {
+            CfrVariant::Vanilla | CfrVariant::Plus => {
+                let mut values = vec![0.0; actions.len()];
+                let mut value = 0.0;
+                for (i, action) in actions.iter().enumerate() {
+                    let mut next_reach = reach;
+                    next_reach[index] *= strategy[i];
+                    let next = self.game.apply(history, action);
+                    values[i] = self.traverse(&next, player, next_reach, chance);
+                    value += strategy[i] * values[i];
+                }
+                if acting == player {
+                    let counterfactual = reach[1 - index] * chance;
+                    let regrets = values
+                        .iter()
+                        .map(|v| counterfactual * (v - value))
+                        .collect();
+                    self.add_regrets(info_set.clone(), regrets);
+                    let weight = match self.variant {
+                        CfrVariant::Plus => self.iterations as f64,
+                        _ => 1.0,
+                    };
+                    self.add_to_average(&info_set, &strategy, weight * reach[index]);
+                }
+                value
+            }
+            CfrVariant::ExternalSampling => {
+                if acting != player {
+                    // The opponent's average is updated where it's sampled
+                    // on-policy, which weighs it by its own reach.
+                    self.add_to_average(&info_set, &strategy, 1.0);
+                    let i = self.sample(&strategy);
+                    let next = self.game.apply(history, &actions[i]);
+                    return self.traverse(&next, player, reach, chance);
+                }
+                let mut values = vec![0.0; actions.len()];
+                for (i, action) in actions.iter().enumerate() {
+                    let next = self.game.apply(history, action);
+                    values[i] = self.traverse(&next, player, reach, chance);
+                }
+                let value: f64 = values.iter().zip(&strategy).map(|(v, p)| v * p).sum();
+                let regrets = values.iter().map(|v| v - value).collect();
+                self.add_regrets(info_set, regrets);
+                value
+            }
+            CfrVariant::OutcomeSampling { exploration } => {
+                let sampling: Vec<f64> = if acting == player {
+                    let uniform = 1.0 / actions.len() as f64;
+                    strategy
+                        .iter()
+                        .map(|p| exploration * uniform + (1.0 - exploration) * p)
+                        .collect()
+                } else {
+                    strategy.clone()
+                };
+                let i = self.sample(&sampling);
+                let next = self.game.apply(history, &actions[i]);
+                if acting != player {
+                    self.add_to_average(&info_set, &strategy, 1.0 / chance);
+                    return self.traverse(&next, player, reach, chance);
+                }
+                // Importance-weighted estimates of the action values, zero for
+                // the actions that weren't sampled.
+                let sampled =
+                    self.traverse(&next, player, reach, chance * sampling[i]) / sampling[i];
+                let value = strategy[i] * sampled;
+                let regrets = (0..actions.len())
+                    .map(|j| {
+                        let estimate = if j == i { sampled } else { 0.0 };
+                        (estimate - value) / chance
+                    })
+                    .collect();
+                self.add_regrets(info_set, regrets);
+                value
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::State;
+    use crate::extensive_form::{expected_rewards, exploitability};
+    use crate::poker::kuhn::KuhnPoker;
+    use crate::poker::leduc::LeducPoker;
+    use crate::tictactoe::action::TicTacToeAction;
+    use crate::tictactoe::board::BoardShape;
+    use crate::tictactoe::cell::CellValue;
+    use crate::tictactoe::game::TicTacToeGame;
+    use crate::tictactoe::state::TicTacToeState;
+
+    fn solve<G: ExtensiveFormGame>(game: G, variant: CfrVariant, iterations: usize) -> (f64, f64) {
+        let mut cfr = Cfr::new(game, variant, 7);
+        cfr.run(iterations);
+        let strategy =
+            |info_set: &G::InfoSet, actions: &[G::Action]| cfr.average_strategy(info_set, actions);
+        let value = expected_rewards(cfr.game(), &strategy)[0];
+        (value, exploitability(cfr.game(), &strategy))
+    }
+
+    #[test]
+    fn kuhn_full_width() {
+        for variant in [CfrVariant::Vanilla, CfrVariant::Plus] {
+            let (value, exploitability) = solve(KuhnPoker::new(), variant, 1000);
+            assert!(exploitability < 0.005, "{:?} {}", variant, exploitability);
+            assert!(
+                (value + 1.0 / 18.0).abs() < 0.005,
+                "{:?} {}",
+                variant,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn cfr_plus_converges_faster() {
+        let (_, vanilla) = solve(KuhnPoker::new(), CfrVariant::Vanilla, 200);
+        let (_, plus) = solve(KuhnPoker::new(), CfrVariant::Plus, 200);
+        assert!(plus < vanilla, "{} {}", plus, vanilla);
+    }
+
+    #[test]
+    fn kuhn_sampling() {
+        let (value, exploitability) = solve(KuhnPoker::new(), CfrVariant::ExternalSampling, 20000);
+        assert!(exploitability < 0.02, "{}", exploitability);
+        assert!((value + 1.0 / 18.0).abs() < 0.02, "{}", value);
+
+        let variant = CfrVariant::OutcomeSampling { exploration: 0.6 };
+        let (value, exploitability) = solve(KuhnPoker::new(), variant, 50000);
+        assert!(exploitability < 0.05, "{}", exploitability);
+        assert!((value + 1.0 / 18.0).abs() < 0.05, "{}", value);
+    }
+
+    #[test]
+    fn leduc() {
+        // The first player loses about 0.086 chips per game in equilibrium.
+        let (value, exploitability) = solve(LeducPoker::new(), CfrVariant::Plus, 200);
+        assert!(exploitability < 0.02, "{}", exploitability);
+        assert!((value + 0.0856).abs() < 0.01, "{}", value);
+    }
+
+    fn minimax(state: &TicTacToeState) -> f64 {
+        if state.is_terminal() {
+            return match state.has_winning_value() {
+                CellValue::Cross => 1.0,
+                CellValue::Circle => -1.0,
+                CellValue::None => 0.0,
+            };
+        }
+        let values = state
+            .actions()
+            .into_iter()
+            .map(|a| minimax(&state.apply_action(&a)));
+        match state.current_player() {
+            Player::First => values.fold(f64::NEG_INFINITY, f64::max),
+            Player::Second => values.fold(f64::INFINITY, f64::min),
+        }
+    }
+
+    fn position(moves: &[(CellValue, usize)]) -> TicTacToeState {
+        moves.iter().fold(
+            TicTacToeState::empty(BoardShape::tic_tac_toe()),
+            |state, (value, index)| state.apply_action(&TicTacToeAction::new(*value, *index)),
+        )
+    }
+
+    fn assert_converges_to_minimax(state: TicTacToeState) {
+        let expected = minimax(&state);
+        for (variant, iterations) in [
+            (CfrVariant::Vanilla, 100),
+            (CfrVariant::Plus, 100),
+            (CfrVariant::ExternalSampling, 600),
+        ] {
+            let game = TicTacToeGame::with_state(state);
+            let (value, exploitability) = solve(game, variant, iterations);
+            assert!((value - expected).abs() < 0.05, "{:?} {}", variant, value);
+            assert!(exploitability < 0.05, "{:?} {}", variant, exploitability);
+        }
+    }
+
+    #[test]
+    fn tictactoe_converges_to_minimax() {
+        // The second player answered a corner with the adjacent edge, which
+        // loses to the first player taking the center and then forking.
+        let state = position(&[
+            (CellValue::Cross, 0),
+            (CellValue::Circle, 1),
+            (CellValue::Cross, 4),
+            (CellValue::Circle, 8),
+        ]);
+        assert_eq!(minimax(&state), 1.0);
+        assert_converges_to_minimax(state);
+
+        // Opposite corners against the center and an edge: every threat can
+        // be blocked, so best play fills the board.
+        let state = position(&[
+            (CellValue::Cross, 0),
+            (CellValue::Circle, 4),
+            (CellValue::Cross, 8),
+            (CellValue::Circle, 1),
+        ]);
+        assert_eq!(minimax(&state), 0.0);
+        assert_converges_to_minimax(state);
+    }
+}
@@ -0,0 +1,31 @@
+use crate::environment::ActionId;
+use crate::tictactoe::cell::CellValue;
+
+#[derive(Debug, Ord, Eq, PartialEq, PartialOrd, Clone, Copy)]
+pub struct Connect4Action {
+    cell_value: CellValue,
+    column: usize,
+}
+
+impl Connect4Action {
+    // There is at most one legal action per column, so the column alone
+    // identifies the action.
+    pub fn id(&self) -> ActionId {
+        ActionId(self.column)
+    }
+
+    pub fn value(&self) -> CellValue {
+        self.cell_value
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn new(value: CellValue, column: usize) -> Connect4Action {
+        Connect4Action {
+            cell_value: value,
+            column,
+        }
+    }
+}
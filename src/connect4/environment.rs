@@ -0,0 +1,135 @@
+use crate::connect4::action::Connect4Action;
+use crate::connect4::state::Connect4State;
+use crate::environment::{
+    AfterstateEnvironment, DPEnvironment, Environment, Player, ProbabilityT, RewardT, StateId,
+    StateTransition, TwoPlayerEnvironment,
+};
+use crate::tictactoe::cell::CellValue;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+pub struct Connect4Environment {
+    state: Connect4State,
+}
+
+impl Connect4Environment {
+    pub fn new() -> Self {
+        Connect4Environment {
+            state: Connect4State::new(),
+        }
+    }
+
+    // Starts the game from an arbitrary position, e.g. to solve endgames.
+    pub fn with_state(state: Connect4State) -> Self {
+        Connect4Environment { state }
+    }
+
+    fn reward_for_state(state: &Connect4State) -> RewardT {
+        match state.has_winning_value() {
+            CellValue::Circle => RewardT(-1.0),
+            CellValue::Cross => RewardT(1.0),
+            CellValue::None => RewardT(0.0),
+        }
+    }
+}
+
+impl Environment for Connect4Environment {
+    type Action = Connect4Action;
+    type State = Connect4State;
+
+    fn state(&self) -> &Connect4State {
+        &self.state
+    }
+    fn actions(&self) -> Vec<Connect4Action> {
+        self.state.actions()
+    }
+    fn apply_action(&mut self, action: &Connect4Action) -> RewardT {
+        self.state = self.state.apply_action(action);
+        Connect4Environment::reward_for_state(&self.state)
+    }
+}
+
+impl AfterstateEnvironment<u64> for Connect4Environment {
+    fn afterstate_id(&self, action: &Connect4Action) -> StateId<u64> {
+        self.state.apply_action(action).id()
+    }
+}
+
+impl TwoPlayerEnvironment for Connect4Environment {
+    fn current_player(&self) -> Player {
+        self.state.current_player()
+    }
+}
+
+// The table covers the states reachable from the current state. There are
+// trillions of them from the empty board, so this is only practical for
+// positions close to the end of the game.
+impl DPEnvironment<u64> for Connect4Environment {
+    fn state_transitions(&self) -> HashMap<StateId<u64>, Vec<StateTransition<u64>>> {
+        let mut transition_table = HashMap::new();
+        let mut stack = vec![self.state];
+        while let Some(state) = stack.pop() {
+            if transition_table.contains_key(&state.id()) {
+                continue;
+            }
+            let transitions = state
+                .actions()
+                .iter()
+                .map(|a| {
+                    let new_state = state.apply_action(a);
+                    stack.push(new_state);
+                    StateTransition {
+                        action_id: a.id(),
+                        prob: ProbabilityT(1.0),
+                        new_state_id: new_state.id(),
+                        reward: Connect4Environment::reward_for_state(&new_state),
+                    }
+                })
+                .collect();
+            transition_table.insert(state.id(), transitions);
+        }
+
+        transition_table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{value_iteration, DPConfig};
+    use crate::environment::State;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn state_transitions_of_endgame() {
+        // Fills all columns but the last one without anybody winning.
+        let columns = [
+            2, 1, 1, 5, 3, 1, 1, 5, 5, 0, 1, 1, 0, 2, 5, 0, 4, 5, 4, 4, 5, 0, 3, 2, 3, 3, 3, 0, 3,
+            2, 2, 2, 4, 4, 0, 4,
+        ];
+        let mut env = Connect4Environment::new();
+        for col in columns {
+            let action = env
+                .actions()
+                .into_iter()
+                .find(|a| a.column() == col)
+                .unwrap();
+            env.apply_action(&action);
+        }
+        assert!(!env.state().is_terminal(), "state={}", env.state());
+        assert_eq!(env.actions().len(), 1);
+
+        let transitions = env.state_transitions();
+        // Dropping the remaining six pieces into the last column ends in a
+        // draw.
+        assert_eq!(transitions.len(), 7);
+        assert!(transitions
+            .values()
+            .flatten()
+            .all(|transition| transition.reward == RewardT(0.0)));
+        let terminal_states = transitions.values().filter(|t| t.is_empty()).count();
+        assert_eq!(terminal_states, 1);
+        let values = value_iteration(&transitions, &DPConfig::default());
+        assert_eq!(values[&env.state().id()], 0.0);
+    }
+}
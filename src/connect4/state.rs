@@ -0,0 +1,299 @@
+use crate::connect4::action::Connect4Action;
+use crate::environment::{Player, State, StateId};
+use crate::tictactoe::cell::CellValue;
+use std::fmt;
+
+pub const ROWS: usize = 6;
+pub const COLS: usize = 7;
+
+// Every column takes `ROWS + 1` bits of the bitboards, from the bottom row
+// upwards. The extra bit on top of each column stays empty so that shifted
+// bitboards don't wrap from one column into the next.
+const COLUMN_HEIGHT: usize = ROWS + 1;
+const BOTTOM_MASK: u64 = bottom_mask();
+const BOARD_MASK: u64 = BOTTOM_MASK * ((1 << ROWS) - 1);
+
+const fn bottom_mask() -> u64 {
+    let mut mask = 0;
+    let mut col = 0;
+    while col < COLS {
+        mask |= 1 << (col * COLUMN_HEIGHT);
+        col += 1;
+    }
+    mask
+}
+
+fn bit(row: usize, col: usize) -> u64 {
+    1 << (col * COLUMN_HEIGHT + row)
+}
+
+fn column_mask(col: usize) -> u64 {
+    ((1 << ROWS) - 1) << (col * COLUMN_HEIGHT)
+}
+
+// Checks for four in a line by shifting the bitboard along each direction:
+// vertical, horizontal and both diagonals.
+fn has_four_in_a_row(pieces: u64) -> bool {
+    [1, COLUMN_HEIGHT, COLUMN_HEIGHT - 1, COLUMN_HEIGHT + 1]
+        .iter()
+        .any(|&shift| {
+            let pairs = pieces & (pieces >> shift);
+            pairs & (pairs >> (2 * shift)) != 0
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Connect4State {
+    crosses: u64,
+    circles: u64,
+    winning_value: CellValue,
+}
+
+impl fmt::Display for Connect4State {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let num_dashes = COLS * 2 + 1;
+        let separator_line = "-".repeat(num_dashes) + "\n";
+        f.write_str(&separator_line)?;
+        for row in (0..ROWS).rev() {
+            f.write_str("|")?;
+            let row: Vec<String> = (0..COLS).map(|col| self.cell(row, col).into()).collect();
+            f.write_str(&row.join("|"))?;
+            f.write_str("|\n")?;
+            f.write_str(&separator_line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Connect4State {
+    pub fn new() -> Self {
+        Connect4State {
+            crosses: 0,
+            circles: 0,
+            winning_value: CellValue::None,
+        }
+    }
+
+    fn mask(&self) -> u64 {
+        self.crosses | self.circles
+    }
+
+    // Rows are counted from the bottom.
+    pub fn cell(&self, row: usize, col: usize) -> CellValue {
+        if self.crosses & bit(row, col) != 0 {
+            CellValue::Cross
+        } else if self.circles & bit(row, col) != 0 {
+            CellValue::Circle
+        } else {
+            CellValue::None
+        }
+    }
+
+    pub fn column_height(&self, col: usize) -> usize {
+        (self.mask() & column_mask(col)).count_ones() as usize
+    }
+
+    // Crosses always move first.
+    pub fn current_player(&self) -> Player {
+        if self.mask().count_ones().is_multiple_of(2) {
+            Player::First
+        } else {
+            Player::Second
+        }
+    }
+
+    fn next_cell_value(&self) -> CellValue {
+        if self.is_terminal() {
+            return CellValue::None;
+        }
+        match self.current_player() {
+            Player::First => CellValue::Cross,
+            Player::Second => CellValue::Circle,
+        }
+    }
+
+    pub fn has_winning_value(&self) -> CellValue {
+        self.winning_value
+    }
+
+    fn all_cells_set(&self) -> bool {
+        self.mask() == BOARD_MASK
+    }
+
+    pub fn apply_action(&self, action: &Connect4Action) -> Connect4State {
+        if self.is_terminal() {
+            panic!("tried applying an action to a terminal state");
+        }
+        if action.column() >= COLS || self.column_height(action.column()) == ROWS {
+            panic!(
+                "action tried dropping a piece into a full column. state={:?} action={:?}",
+                self, action
+            );
+        }
+
+        let mut new_state = *self;
+        // Adding the bottom bit of the column to the occupied cells carries
+        // over to the lowest empty cell.
+        let new_piece = (self.mask() + (BOTTOM_MASK & column_mask(action.column())))
+            & column_mask(action.column());
+        let pieces = match action.value() {
+            CellValue::Cross => &mut new_state.crosses,
+            CellValue::Circle => &mut new_state.circles,
+            CellValue::None => panic!("tried dropping an empty piece"),
+        };
+        *pieces |= new_piece;
+        if has_four_in_a_row(*pieces) {
+            new_state.winning_value = action.value();
+        }
+        new_state
+    }
+
+    pub fn actions(&self) -> Vec<Connect4Action> {
+        if self.is_terminal() {
+            return vec![];
+        }
+        let next_cell_value = self.next_cell_value();
+        (0..COLS)
+            .filter(|&col| self.column_height(col) < ROWS)
+            .map(|col| Connect4Action::new(next_cell_value, col))
+            .collect()
+    }
+
+    // Every column is encoded by its crosses plus a marker bit on top of the
+    // highest piece, which fits all positions into 49 bits.
+    pub fn id(&self) -> StateId<u64> {
+        StateId(self.crosses + self.mask() + BOTTOM_MASK)
+    }
+
+    pub fn create_state_with_id(state_id: StateId<u64>) -> Option<Connect4State> {
+        let key = state_id.0;
+        let mut state = Connect4State::new();
+        for col in 0..COLS {
+            let column_bits = (key >> (col * COLUMN_HEIGHT)) & ((1 << COLUMN_HEIGHT) - 1);
+            if column_bits == 0 {
+                return None;
+            }
+            let height = 63 - column_bits.leading_zeros() as usize;
+            let pieces = (1 << height) - 1;
+            let crosses = column_bits & pieces;
+            state.crosses |= crosses << (col * COLUMN_HEIGHT);
+            state.circles |= (pieces & !crosses) << (col * COLUMN_HEIGHT);
+        }
+        if key >> (COLS * COLUMN_HEIGHT) != 0 {
+            return None;
+        }
+        state.winning_value = if has_four_in_a_row(state.crosses) {
+            CellValue::Cross
+        } else if has_four_in_a_row(state.circles) {
+            CellValue::Circle
+        } else {
+            CellValue::None
+        };
+        Some(state)
+    }
+}
+
+impl Default for Connect4State {
+    fn default() -> Self {
+        Connect4State::new()
+    }
+}
+
+impl State for Connect4State {
+    fn is_terminal(&self) -> bool {
+        self.winning_value != CellValue::None || self.all_cells_set()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn play(columns: &[usize]) -> Connect4State {
+        columns.iter().fold(Connect4State::new(), |state, &col| {
+            let value = state.next_cell_value();
+            state.apply_action(&Connect4Action::new(value, col))
+        })
+    }
+
+    #[test]
+    fn fmt_1() {
+        let state = play(&[3, 3, 4]);
+        assert_eq!(
+            state.to_string(),
+            "\
+---------------
+| | | | | | | |
+---------------
+| | | | | | | |
+---------------
+| | | | | | | |
+---------------
+| | | | | | | |
+---------------
+| | | |o| | | |
+---------------
+| | | |x|x| | |
+---------------
+"
+        );
+    }
+
+    #[test]
+    fn vertical_win() {
+        let state = play(&[0, 1, 0, 1, 0, 1]);
+        assert_eq!(state.has_winning_value(), CellValue::None);
+        let state = state.apply_action(&Connect4Action::new(CellValue::Cross, 0));
+        assert_eq!(state.has_winning_value(), CellValue::Cross);
+        assert!(state.is_terminal());
+        assert_eq!(state.actions(), vec![]);
+    }
+
+    #[test]
+    fn wins_do_not_wrap_columns() {
+        // Crosses fill the top of column 0 and the bottom of column 1, which
+        // are neighbours on the bitboard but not on the board.
+        let state = play(&[0, 0, 1, 0, 0, 6, 0, 6, 0]);
+        assert_eq!(state.cell(5, 0), CellValue::Cross);
+        assert_eq!(state.cell(0, 1), CellValue::Cross);
+        assert_eq!(state.has_winning_value(), CellValue::None);
+
+        let state = play(&[0, 0, 1, 1, 2, 2, 3]);
+        assert_eq!(state.has_winning_value(), CellValue::Cross);
+    }
+
+    #[test]
+    fn diagonal_wins() {
+        let state = play(&[0, 1, 1, 2, 2, 3, 2, 3, 3, 6, 3]);
+        assert_eq!(state.has_winning_value(), CellValue::Cross);
+
+        let state = play(&[6, 5, 5, 4, 4, 3, 4, 3, 3, 0, 3]);
+        assert_eq!(state.has_winning_value(), CellValue::Cross);
+    }
+
+    #[test]
+    fn full_columns_have_no_actions() {
+        let state = play(&[2, 2, 2, 2, 2, 2]);
+        assert_eq!(state.column_height(2), 6);
+        let columns: Vec<usize> = state.actions().iter().map(|a| a.column()).collect();
+        assert_eq!(columns, vec![0, 1, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn ids_are_bijective() {
+        let mut state = Connect4State::new();
+        let mut ids = vec![state.id()];
+        for col in [3, 3, 2, 4, 4, 5, 6, 0, 0, 0, 1] {
+            let value = state.next_cell_value();
+            state = state.apply_action(&Connect4Action::new(value, col));
+            ids.push(state.id());
+        }
+        assert_eq!(ids[0], StateId(BOTTOM_MASK));
+        for id in ids {
+            let state = Connect4State::create_state_with_id(id).unwrap();
+            assert_eq!(state.id(), id);
+        }
+        assert_eq!(Connect4State::create_state_with_id(StateId(0)), None);
+    }
+}
@@ -0,0 +1,425 @@
+// Constrained MDPs: maximize the expected return from a start distribution
+// while keeping the expected total of every cost channel within a budget.
+// Optimal policies may have to randomize, e.g. take a risky action only
+// some of the time.
+use crate::dp::{
+    greedy_policy, occupancy_program, policy_evaluation, value_iteration, DPConfig,
+    OccupancyMeasure, Policy, StochasticPolicy, TransitionTable, ValueTable,
+};
+use crate::environment::{
+    ActionId, CostedStateTransition, RewardT, StateId, StateKey, StateTransition,
+};
+use crate::lp::{LpError, Relation};
+use std::collections::HashMap;
+
+pub type CostedTransitionTable<K = usize> = HashMap<StateId<K>, Vec<CostedStateTransition<K>>>;
+
+// The table without its costs.
+pub fn reward_table<K: StateKey>(table: &CostedTransitionTable<K>) -> TransitionTable<K> {
+    table
+        .iter()
+        .map(|(state_id, transitions)| {
+            let transitions = transitions.iter().map(|t| t.transition.clone()).collect();
+            (state_id.clone(), transitions)
+        })
+        .collect()
+}
+
+// The table with the costs of `channel` as rewards, so that evaluating a
+// policy on it gives its expected costs.
+pub fn cost_table<K: StateKey>(
+    table: &CostedTransitionTable<K>,
+    channel: usize,
+) -> TransitionTable<K> {
+    table
+        .iter()
+        .map(|(state_id, transitions)| {
+            let transitions = transitions
+                .iter()
+                .map(|t| StateTransition {
+                    reward: RewardT(t.costs[channel].0),
+                    ..t.transition.clone()
+                })
+                .collect();
+            (state_id.clone(), transitions)
+        })
+        .collect()
+}
+
+// The expected return and costs of a policy from the start distribution
+// `initial`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstrainedValue {
+    pub expected_return: f64,
+    pub expected_costs: Vec<f64>,
+}
+
+impl ConstrainedValue {
+    pub fn is_within(&self, budgets: &[f64]) -> bool {
+        // Some slack for the rounding errors of the solvers.
+        self.expected_costs
+            .iter()
+            .zip(budgets)
+            .all(|(cost, budget)| *cost <= budget + 1e-6)
+    }
+}
+
+pub fn evaluate_policy<K: StateKey>(
+    table: &CostedTransitionTable<K>,
+    policy: &Policy<K>,
+    config: &DPConfig,
+    initial: &HashMap<StateId<K>, f64>,
+) -> ConstrainedValue {
+    let expected = |table: &TransitionTable<K>| {
+        let values = policy_evaluation(table, policy, config);
+        start_value(&values, initial)
+    };
+    ConstrainedValue {
+        expected_return: expected(&reward_table(table)),
+        expected_costs: (0..num_cost_channels(table))
+            .map(|channel| expected(&cost_table(table, channel)))
+            .collect(),
+    }
+}
+
+// The occupancy LP of `dp::lp_occupancy` with a budget constraint on the
+// expected total of every cost channel. It's infeasible if no policy keeps
+// within the budgets.
+pub fn constrained_occupancy<K: StateKey + Ord>(
+    table: &CostedTransitionTable<K>,
+    discount: f64,
+    initial: &HashMap<StateId<K>, f64>,
+    budgets: &[f64],
+) -> Result<OccupancyMeasure<K>, LpError> {
+    if budgets.len() != num_cost_channels(table) {
+        panic!(
+            "expected {} budgets, got {}",
+            num_cost_channels(table),
+            budgets.len()
+        );
+    }
+    let (mut program, pairs) = occupancy_program(&reward_table(table), discount, initial);
+    for (channel, budget) in budgets.iter().enumerate() {
+        let coefficients = pairs
+            .iter()
+            .map(|(state_id, action_id)| {
+                table[state_id]
+                    .iter()
+                    .filter(|t| t.transition.action_id == *action_id)
+                    .map(|t| t.transition.prob.0 * t.costs[channel].0)
+                    .sum()
+            })
+            .collect();
+        program.add_constraint(coefficients, Relation::LessEqual, *budget);
+    }
+    let solution = program.solve()?;
+    Ok(pairs.into_iter().zip(solution.variables).collect())
+}
+
+// The expected return and costs of the policy behind `occupancy`.
+pub fn occupancy_value<K: StateKey>(
+    table: &CostedTransitionTable<K>,
+    occupancy: &OccupancyMeasure<K>,
+) -> ConstrainedValue {
+    let mut value = ConstrainedValue {
+        expected_return: 0.0,
+        expected_costs: vec![0.0; num_cost_channels(table)],
+    };
+    for ((state_id, action_id), x) in occupancy.iter() {
+        for t in table[state_id]
+            .iter()
+            .filter(|t| t.transition.action_id == *action_id)
+        {
+            value.expected_return += x * t.transition.prob.0 * t.transition.reward.0;
+            for (total, cost) in value.expected_costs.iter_mut().zip(&t.costs) {
+                *total += x * t.transition.prob.0 * cost.0;
+            }
+        }
+    }
+    value
+}
+
+// The policy behind `occupancy`: actions are taken in proportion to their
+// occupancy. States that are never visited get uniform probabilities.
+pub fn stochastic_occupancy_policy<K: StateKey>(
+    table: &CostedTransitionTable<K>,
+    occupancy: &OccupancyMeasure<K>,
+) -> StochasticPolicy<K> {
+    table
+        .iter()
+        .filter(|(_, transitions)| !transitions.is_empty())
+        .map(|(state_id, transitions)| {
+            let mut actions: Vec<(ActionId, f64)> = vec![];
+            for t in transitions.iter() {
+                let action_id = t.transition.action_id;
+                if actions.iter().all(|(id, _)| *id != action_id) {
+                    let key = (state_id.clone(), action_id);
+                    actions.push((action_id, occupancy.get(&key).unwrap_or(&0.0).max(0.0)));
+                }
+            }
+            let total: f64 = actions.iter().map(|(_, x)| x).sum();
+            let num_actions = actions.len() as f64;
+            for (_, prob) in actions.iter_mut() {
+                *prob = if total > 0.0 {
+                    *prob / total
+                } else {
+                    1.0 / num_actions
+                };
+            }
+            (state_id.clone(), actions)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct LagrangianConfig {
+    pub dp: DPConfig,
+    pub iterations: usize,
+    // The multipliers move by `step_size / sqrt(iteration)` times the budget
+    // violation.
+    pub step_size: f64,
+}
+
+impl Default for LagrangianConfig {
+    fn default() -> Self {
+        LagrangianConfig {
+            dp: DPConfig::default(),
+            iterations: 100,
+            step_size: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LagrangianSolution<K: StateKey = usize> {
+    // The multipliers of the cost channels `policy` is greedy for.
+    pub multipliers: Vec<f64>,
+    pub policy: Policy<K>,
+    pub value: ConstrainedValue,
+}
+
+// Lagrangian relaxation: value iteration on the reward minus the costs
+// weighted by a multiplier per channel, whose multipliers follow the
+// subgradient of the budget violations of the greedy policy. The policies
+// found that way are deterministic, so the best of them within the budgets
+// is returned, or `None` if there is none. It can fall short of the
+// randomized optimum of `constrained_occupancy`.
+pub fn lagrangian_value_iteration<K: StateKey>(
+    table: &CostedTransitionTable<K>,
+    config: &LagrangianConfig,
+    initial: &HashMap<StateId<K>, f64>,
+    budgets: &[f64],
+) -> Option<LagrangianSolution<K>> {
+    let channels = num_cost_channels(table);
+    if budgets.len() != channels {
+        panic!("expected {} budgets, got {}", channels, budgets.len());
+    }
+    let mut multipliers = vec![0.0; channels];
+    let mut best: Option<LagrangianSolution<K>> = None;
+    for iteration in 1..=config.iterations {
+        let penalized = penalized_table(table, &multipliers);
+        let values = value_iteration(&penalized, &config.dp);
+        let policy = greedy_policy(&penalized, &values, config.dp.discount);
+        let value = evaluate_policy(table, &policy, &config.dp, initial);
+        let improves = best
+            .as_ref()
+            .is_none_or(|best| value.expected_return > best.value.expected_return);
+        if value.is_within(budgets) && improves {
+            best = Some(LagrangianSolution {
+                multipliers: multipliers.clone(),
+                policy,
+                value: value.clone(),
+            });
+        }
+        let step = config.step_size / (iteration as f64).sqrt();
+        for ((multiplier, cost), budget) in multipliers
+            .iter_mut()
+            .zip(&value.expected_costs)
+            .zip(budgets)
+        {
+            *multiplier = (*multiplier + step * (cost - budget)).max(0.0);
+        }
+    }
+    best
+}
+
+// Rewards minus the costs weighted by `multipliers`.
+fn penalized_table<K: StateKey>(
+    table: &CostedTransitionTable<K>,
+    multipliers: &[f64],
+) -> TransitionTable<K> {
+    table
+        .iter()
+        .map(|(state_id, transitions)| {
+            let transitions = transitions
+                .iter()
+                .map(|t| {
+                    let penalty: f64 = t
+                        .costs
+                        .iter()
+                        .zip(multipliers)
+                        .map(|(cost, multiplier)| cost.0 * multiplier)
+                        .sum();
+                    StateTransition {
+                        reward: RewardT(t.transition.reward.0 - penalty),
+                        ..t.transition.clone()
+                    }
+                })
+                .collect();
+            (state_id.clone(), transitions)
+        })
+        .collect()
+}
+
+fn num_cost_channels<K>(table: &CostedTransitionTable<K>) -> usize {
+    table
+        .values()
+        .flatten()
+        .next()
+        .map_or(0, |transition| transition.costs.len())
+}
+
+fn start_value<K: StateKey>(values: &ValueTable<K>, initial: &HashMap<StateId<K>, f64>) -> f64 {
+    initial
+        .iter()
+        .map(|(state_id, prob)| prob * values.get(state_id).unwrap_or(&0.0))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{ConstrainedDPEnvironment, CostT, Environment, ProbabilityT};
+    use crate::tictactoe::action::TicTacToeAction;
+    use crate::tictactoe::cell::CellValue;
+    use crate::tictactoe::random_opponent::RandomOpponentEnvironment;
+    use pretty_assertions::assert_eq;
+
+    fn transition(action: usize, reward: f64, cost: f64) -> CostedStateTransition<&'static str> {
+        CostedStateTransition {
+            transition: StateTransition {
+                action_id: ActionId(action),
+                new_state_id: StateId("end"),
+                reward: RewardT(reward),
+                prob: ProbabilityT(1.0),
+            },
+            costs: vec![CostT(cost)],
+        }
+    }
+
+    // A safe action worth 1 and a risky one worth 3 that costs 1.
+    fn make_table() -> CostedTransitionTable<&'static str> {
+        HashMap::from([
+            (
+                StateId("start"),
+                vec![transition(0, 1.0, 0.0), transition(1, 3.0, 1.0)],
+            ),
+            (StateId("end"), vec![]),
+        ])
+    }
+
+    #[test]
+    fn randomized_policies_can_beat_deterministic_ones() {
+        let table = make_table();
+        let initial = HashMap::from([(StateId("start"), 1.0)]);
+        let occupancy = constrained_occupancy(&table, 1.0, &initial, &[0.5]).unwrap();
+        let value = occupancy_value(&table, &occupancy);
+        assert!((value.expected_return - 2.0).abs() < 1e-9);
+        assert!((value.expected_costs[0] - 0.5).abs() < 1e-9);
+        let policy = stochastic_occupancy_policy(&table, &occupancy);
+        assert!((policy[&StateId("start")][1].1 - 0.5).abs() < 1e-9);
+
+        let solution =
+            lagrangian_value_iteration(&table, &LagrangianConfig::default(), &initial, &[0.5])
+                .unwrap();
+        assert_eq!(solution.policy[&StateId("start")], ActionId(0));
+        assert_eq!(solution.value.expected_return, 1.0);
+        assert!(solution.multipliers[0] >= 1.0);
+
+        let infeasible = constrained_occupancy(&table, 1.0, &initial, &[-1.0]);
+        assert_eq!(infeasible, Err(LpError::Infeasible));
+    }
+
+    #[test]
+    fn tic_tac_toe_with_a_budget_for_unsafe_moves() {
+        let env = RandomOpponentEnvironment::new(0);
+        let table = env.costed_state_transitions();
+        assert_eq!(env.num_cost_channels(), 1);
+        let initial = HashMap::from([(env.state().canonical_id(), 1.0)]);
+        let config = LagrangianConfig {
+            iterations: 20,
+            ..Default::default()
+        };
+
+        // Against a random opponent the best play never needs to leave a
+        // win to the circles, so even a zero budget costs nothing.
+        let occupancy = constrained_occupancy(&table, 1.0, &initial, &[0.0]).unwrap();
+        let value = occupancy_value(&table, &occupancy);
+        assert!(value.is_within(&[0.0]));
+        let best = value_iteration(&reward_table(&table), &config.dp);
+        let start = env.state().canonical_id();
+        assert!((value.expected_return - best[&start]).abs() < 1e-6);
+
+        let solution = lagrangian_value_iteration(&table, &config, &initial, &[0.0]).unwrap();
+        assert!(solution.value.is_within(&[0.0]));
+        assert!((solution.value.expected_return - value.expected_return).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tic_tac_toe_with_a_budget_for_corner_openings() {
+        // Charges opening in a corner, which is the best opening against a
+        // random opponent.
+        let env = RandomOpponentEnvironment::new(0);
+        let start = env.state().canonical_id();
+        let corners: Vec<ActionId> = [0, 2, 6, 8]
+            .into_iter()
+            .map(|index| TicTacToeAction::new(CellValue::Cross, index).id())
+            .collect();
+        let centre = TicTacToeAction::new(CellValue::Cross, 4).id();
+        let table: CostedTransitionTable = env
+            .costed_state_transitions()
+            .into_iter()
+            .map(|(id, transitions)| {
+                let transitions = transitions
+                    .into_iter()
+                    .map(|mut t| {
+                        let opens_corner = id == start && corners.contains(&t.transition.action_id);
+                        let cost = if opens_corner { 1.0 } else { 0.0 };
+                        t.costs = vec![CostT(cost)];
+                        t
+                    })
+                    .collect();
+                (id, transitions)
+            })
+            .collect();
+        let initial = HashMap::from([(start, 1.0)]);
+        let best = value_iteration(&reward_table(&table), &DPConfig::default())[&start];
+
+        // With a zero budget the crosses open in the centre instead and win a
+        // little less often.
+        let occupancy = constrained_occupancy(&table, 1.0, &initial, &[0.0]).unwrap();
+        let banned = occupancy_value(&table, &occupancy);
+        assert!(banned.expected_return < best - 1e-3);
+        let policy = stochastic_occupancy_policy(&table, &occupancy);
+        let (_, centre_prob) = policy[&start]
+            .iter()
+            .find(|(action_id, _)| *action_id == centre)
+            .unwrap();
+        assert!((centre_prob - 1.0).abs() < 1e-6);
+
+        // Half a corner opening mixes the two and splits the difference.
+        let occupancy = constrained_occupancy(&table, 1.0, &initial, &[0.5]).unwrap();
+        let half = occupancy_value(&table, &occupancy);
+        assert!((half.expected_costs[0] - 0.5).abs() < 1e-6);
+        let midpoint = (best + banned.expected_return) / 2.0;
+        assert!((half.expected_return - midpoint).abs() < 1e-6);
+
+        let config = LagrangianConfig {
+            iterations: 20,
+            ..Default::default()
+        };
+        let solution = lagrangian_value_iteration(&table, &config, &initial, &[0.0]).unwrap();
+        assert!(solution.value.is_within(&[0.0]));
+        assert_eq!(solution.policy[&start], centre);
+    }
+}
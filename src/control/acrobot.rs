@@ -0,0 +1,276 @@
+use crate::control::wrap_angle;
+use crate::environment::{ActionId, Environment, ObservableState, RewardT, State};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+
+const LINK_LENGTH: f64 = 1.0;
+const LINK_MASS: f64 = 1.0;
+// Position of the center of mass along a link.
+const LINK_CENTER: f64 = 0.5;
+const LINK_MOMENT_OF_INERTIA: f64 = 1.0;
+const GRAVITY: f64 = 9.8;
+const TIME_STEP: f64 = 0.2;
+pub const MAX_VELOCITY_1: f64 = 4.0 * PI;
+pub const MAX_VELOCITY_2: f64 = 9.0 * PI;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AcrobotAction {
+    Negative,
+    Zero,
+    Positive,
+}
+
+impl AcrobotAction {
+    pub fn all() -> [AcrobotAction; 3] {
+        [
+            AcrobotAction::Negative,
+            AcrobotAction::Zero,
+            AcrobotAction::Positive,
+        ]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            AcrobotAction::Negative => ActionId(0),
+            AcrobotAction::Zero => ActionId(1),
+            AcrobotAction::Positive => ActionId(2),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> AcrobotAction {
+        AcrobotAction::all()[action_id.0]
+    }
+
+    fn torque(&self) -> f64 {
+        match self {
+            AcrobotAction::Negative => -1.0,
+            AcrobotAction::Zero => 0.0,
+            AcrobotAction::Positive => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AcrobotState {
+    // Angles are in (-π, π], the first one is relative to hanging straight
+    // down and the second one relative to the first link.
+    angles: [f64; 2],
+    velocities: [f64; 2],
+    steps: usize,
+    max_steps: usize,
+}
+
+impl AcrobotState {
+    pub fn angles(&self) -> [f64; 2] {
+        self.angles
+    }
+
+    pub fn velocities(&self) -> [f64; 2] {
+        self.velocities
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // The height of the tip above the pivot, in link lengths.
+    pub fn tip_height(&self) -> f64 {
+        -self.angles[0].cos() - (self.angles[0] + self.angles[1]).cos()
+    }
+
+    pub fn reached_goal(&self) -> bool {
+        self.tip_height() > LINK_LENGTH
+    }
+
+    // Out of steps without swinging up.
+    pub fn is_truncated(&self) -> bool {
+        !self.is_terminal() && self.steps >= self.max_steps
+    }
+}
+
+impl State for AcrobotState {
+    fn is_terminal(&self) -> bool {
+        self.reached_goal()
+    }
+}
+
+// The angles are observed through their cosine and sine so that the
+// observation is continuous when they wrap around.
+impl ObservableState for AcrobotState {
+    fn observation(&self) -> Vec<f64> {
+        vec![
+            self.angles[0].cos(),
+            self.angles[0].sin(),
+            self.angles[1].cos(),
+            self.angles[1].sin(),
+            self.velocities[0],
+            self.velocities[1],
+        ]
+    }
+}
+
+// Section 11.3 of the first edition of Sutton & Barto: a two-link pendulum
+// actuated only at the joint between the links has to swing its tip one link
+// length above the pivot. Every step until then costs -1.
+#[derive(Debug)]
+pub struct AcrobotEnvironment {
+    state: AcrobotState,
+    rng: StdRng,
+}
+
+impl AcrobotEnvironment {
+    pub fn new(seed: u64) -> Self {
+        let mut env = AcrobotEnvironment {
+            state: AcrobotState {
+                angles: [0.0; 2],
+                velocities: [0.0; 2],
+                steps: 0,
+                max_steps: 500,
+            },
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.reset();
+        env
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.state.max_steps = max_steps;
+        self
+    }
+
+    // Starts hanging down, close to rest.
+    pub fn reset(&mut self) {
+        for value in self
+            .state
+            .angles
+            .iter_mut()
+            .chain(self.state.velocities.iter_mut())
+        {
+            *value = self.rng.gen_range(-0.1..0.1);
+        }
+        self.state.steps = 0;
+    }
+
+    // The time derivative of `[θ1, θ2, θ̇1, θ̇2]` under `torque`.
+    fn derivatives(s: [f64; 4], torque: f64) -> [f64; 4] {
+        let [theta1, theta2, velocity1, velocity2] = s;
+        let (m, l, lc, inertia) = (LINK_MASS, LINK_LENGTH, LINK_CENTER, LINK_MOMENT_OF_INERTIA);
+        let d1 = m * lc * lc + m * (l * l + lc * lc + 2.0 * l * lc * theta2.cos()) + 2.0 * inertia;
+        let d2 = m * (lc * lc + l * lc * theta2.cos()) + inertia;
+        let phi2 = m * lc * GRAVITY * (theta1 + theta2 - PI / 2.0).cos();
+        let phi1 = -m * l * lc * velocity2 * velocity2 * theta2.sin()
+            - 2.0 * m * l * lc * velocity2 * velocity1 * theta2.sin()
+            + (m * lc + m * l) * GRAVITY * (theta1 - PI / 2.0).cos()
+            + phi2;
+        let acceleration2 =
+            (torque + d2 / d1 * phi1 - m * l * lc * velocity1 * velocity1 * theta2.sin() - phi2)
+                / (m * lc * lc + inertia - d2 * d2 / d1);
+        let acceleration1 = -(d2 * acceleration2 + phi1) / d1;
+        [velocity1, velocity2, acceleration1, acceleration2]
+    }
+}
+
+impl Environment for AcrobotEnvironment {
+    type Action = AcrobotAction;
+    type State = AcrobotState;
+
+    fn state(&self) -> &AcrobotState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<AcrobotAction> {
+        if self.state.is_terminal() || self.state.is_truncated() {
+            return vec![];
+        }
+        AcrobotAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &AcrobotAction) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("tried applying an action to a terminal state");
+        }
+        if self.state.is_truncated() {
+            panic!("tried applying an action after the step limit");
+        }
+        let torque = action.torque();
+        let s = [
+            self.state.angles[0],
+            self.state.angles[1],
+            self.state.velocities[0],
+            self.state.velocities[1],
+        ];
+        // A single classic Runge-Kutta step.
+        let shifted = |k: [f64; 4], scale: f64| {
+            let mut result = s;
+            for (value, derivative) in result.iter_mut().zip(k) {
+                *value += scale * derivative;
+            }
+            result
+        };
+        let k1 = AcrobotEnvironment::derivatives(s, torque);
+        let k2 = AcrobotEnvironment::derivatives(shifted(k1, TIME_STEP / 2.0), torque);
+        let k3 = AcrobotEnvironment::derivatives(shifted(k2, TIME_STEP / 2.0), torque);
+        let k4 = AcrobotEnvironment::derivatives(shifted(k3, TIME_STEP), torque);
+        let mut next = s;
+        for i in 0..4 {
+            next[i] += TIME_STEP / 6.0 * (k1[i] + 2.0 * k2[i] + 2.0 * k3[i] + k4[i]);
+        }
+
+        let state = &mut self.state;
+        state.angles = [wrap_angle(next[0]), wrap_angle(next[1])];
+        state.velocities = [
+            next[2].clamp(-MAX_VELOCITY_1, MAX_VELOCITY_1),
+            next[3].clamp(-MAX_VELOCITY_2, MAX_VELOCITY_2),
+        ];
+        state.steps += 1;
+        if state.reached_goal() {
+            RewardT(0.0)
+        } else {
+            RewardT(-1.0)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn run_episode(
+        env: &mut AcrobotEnvironment,
+        policy: fn(&AcrobotState) -> AcrobotAction,
+    ) -> f64 {
+        let mut total = 0.0;
+        while !env.state().is_terminal() && !env.state().is_truncated() {
+            let action = policy(env.state());
+            total += env.apply_action(&action).0;
+        }
+        total
+    }
+
+    #[test]
+    fn idling_never_swings_up() {
+        let mut env = AcrobotEnvironment::new(0);
+        let total = run_episode(&mut env, |_| AcrobotAction::Zero);
+        assert_eq!(total, -500.0);
+        assert!(env.state().is_truncated());
+        // Without torque the energy is conserved, so it keeps hanging low.
+        assert!(env.state().tip_height() < -1.5);
+    }
+
+    #[test]
+    fn pumping_swings_up() {
+        for seed in 0..5 {
+            let mut env = AcrobotEnvironment::new(seed);
+            // Torquing along with the joint's motion pumps energy in.
+            let total = run_episode(&mut env, |state| {
+                if state.velocities()[1] > 0.0 {
+                    AcrobotAction::Positive
+                } else {
+                    AcrobotAction::Negative
+                }
+            });
+            assert!(env.state().reached_goal(), "{}", seed);
+            assert_eq!(total, -(env.state().steps() as f64 - 1.0));
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use crate::environment::{ActionId, Environment, ObservableState, RewardT, State};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const GRAVITY: f64 = 9.8;
+const CART_MASS: f64 = 1.0;
+const POLE_MASS: f64 = 0.1;
+// Half the length of the pole.
+const POLE_HALF_LENGTH: f64 = 0.5;
+const FORCE: f64 = 10.0;
+const TIME_STEP: f64 = 0.02;
+pub const MAX_POSITION: f64 = 2.4;
+// 12 degrees.
+pub const MAX_ANGLE: f64 = 12.0 * std::f64::consts::PI / 180.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum CartPoleAction {
+    Left,
+    Right,
+}
+
+impl CartPoleAction {
+    pub fn all() -> [CartPoleAction; 2] {
+        [CartPoleAction::Left, CartPoleAction::Right]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            CartPoleAction::Left => ActionId(0),
+            CartPoleAction::Right => ActionId(1),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> CartPoleAction {
+        CartPoleAction::all()[action_id.0]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CartPoleState {
+    position: f64,
+    velocity: f64,
+    // Radians, zero is upright and positive leans to the right.
+    angle: f64,
+    angular_velocity: f64,
+    steps: usize,
+    max_steps: usize,
+}
+
+impl CartPoleState {
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn velocity(&self) -> f64 {
+        self.velocity
+    }
+
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn angular_velocity(&self) -> f64 {
+        self.angular_velocity
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn has_fallen(&self) -> bool {
+        self.position.abs() > MAX_POSITION || self.angle.abs() > MAX_ANGLE
+    }
+
+    // The pole is still up at the step limit.
+    pub fn is_truncated(&self) -> bool {
+        !self.is_terminal() && self.steps >= self.max_steps
+    }
+}
+
+impl State for CartPoleState {
+    fn is_terminal(&self) -> bool {
+        self.has_fallen()
+    }
+}
+
+impl ObservableState for CartPoleState {
+    fn observation(&self) -> Vec<f64> {
+        vec![
+            self.position,
+            self.velocity,
+            self.angle,
+            self.angular_velocity,
+        ]
+    }
+}
+
+// Example 3.4 of Sutton & Barto with the physics of Barto, Sutton and
+// Anderson (1983): a pole has to be balanced on a cart by pushing the cart
+// left or right. Every step, including the one the pole falls in, gives +1.
+#[derive(Debug)]
+pub struct CartPoleEnvironment {
+    state: CartPoleState,
+    rng: StdRng,
+}
+
+impl CartPoleEnvironment {
+    pub fn new(seed: u64) -> Self {
+        let mut env = CartPoleEnvironment {
+            state: CartPoleState {
+                position: 0.0,
+                velocity: 0.0,
+                angle: 0.0,
+                angular_velocity: 0.0,
+                steps: 0,
+                max_steps: 500,
+            },
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.reset();
+        env
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.state.max_steps = max_steps;
+        self
+    }
+
+    // Starts close to upright and at rest.
+    pub fn reset(&mut self) {
+        self.state.position = self.rng.gen_range(-0.05..0.05);
+        self.state.velocity = self.rng.gen_range(-0.05..0.05);
+        self.state.angle = self.rng.gen_range(-0.05..0.05);
+        self.state.angular_velocity = self.rng.gen_range(-0.05..0.05);
+        self.state.steps = 0;
+    }
+}
+
+impl Environment for CartPoleEnvironment {
+    type Action = CartPoleAction;
+    type State = CartPoleState;
+
+    fn state(&self) -> &CartPoleState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<CartPoleAction> {
+        if self.state.is_terminal() || self.state.is_truncated() {
+            return vec![];
+        }
+        CartPoleAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &CartPoleAction) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("tried applying an action to a terminal state");
+        }
+        if self.state.is_truncated() {
+            panic!("tried applying an action after the step limit");
+        }
+        let force = match action {
+            CartPoleAction::Left => -FORCE,
+            CartPoleAction::Right => FORCE,
+        };
+        let state = &mut self.state;
+        let total_mass = CART_MASS + POLE_MASS;
+        let pole_moment = POLE_MASS * POLE_HALF_LENGTH;
+        let (sin, cos) = state.angle.sin_cos();
+        let temp = (force + pole_moment * state.angular_velocity.powi(2) * sin) / total_mass;
+        let angular_acceleration = (GRAVITY * sin - cos * temp)
+            / (POLE_HALF_LENGTH * (4.0 / 3.0 - POLE_MASS * cos.powi(2) / total_mass));
+        let acceleration = temp - pole_moment * angular_acceleration * cos / total_mass;
+
+        // Explicit Euler integration.
+        state.position += TIME_STEP * state.velocity;
+        state.velocity += TIME_STEP * acceleration;
+        state.angle += TIME_STEP * state.angular_velocity;
+        state.angular_velocity += TIME_STEP * angular_acceleration;
+        state.steps += 1;
+        RewardT(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn run_episode(
+        env: &mut CartPoleEnvironment,
+        policy: fn(&CartPoleState) -> CartPoleAction,
+    ) -> f64 {
+        let mut total = 0.0;
+        while !env.state().is_terminal() && !env.state().is_truncated() {
+            let action = policy(env.state());
+            total += env.apply_action(&action).0;
+        }
+        total
+    }
+
+    #[test]
+    fn pushing_one_way_topples_the_pole() {
+        let mut env = CartPoleEnvironment::new(0);
+        let total = run_episode(&mut env, |_| CartPoleAction::Right);
+        assert!(env.state().has_fallen());
+        assert!(total < 20.0, "{}", total);
+        // Pushing right tips the pole to the left.
+        assert!(env.state().angle() < 0.0);
+    }
+
+    #[test]
+    fn feedback_control_balances_the_pole() {
+        for seed in 0..5 {
+            let mut env = CartPoleEnvironment::new(seed);
+            let total = run_episode(&mut env, |state| {
+                if state.angle() + 0.5 * state.angular_velocity() > 0.0 {
+                    CartPoleAction::Right
+                } else {
+                    CartPoleAction::Left
+                }
+            });
+            assert_eq!(total, 500.0);
+            assert!(!env.state().is_terminal());
+            assert!(env.state().is_truncated());
+        }
+    }
+
+    #[test]
+    fn physics_are_deterministic() {
+        let mut first = CartPoleEnvironment::new(9);
+        let mut second = CartPoleEnvironment::new(9);
+        for action in [
+            CartPoleAction::Left,
+            CartPoleAction::Right,
+            CartPoleAction::Right,
+        ] {
+            first.apply_action(&action);
+            second.apply_action(&action);
+        }
+        assert_eq!(first.state(), second.state());
+    }
+}
@@ -0,0 +1,30 @@
+use std::f64::consts::PI;
+
+pub mod acrobot;
+pub mod cart_pole;
+pub mod mountain_car;
+pub mod pendulum;
+
+// Maps an angle into (-π, π].
+pub fn wrap_angle(angle: f64) -> f64 {
+    let wrapped = (angle + PI).rem_euclid(2.0 * PI) - PI;
+    if wrapped == -PI {
+        PI
+    } else {
+        wrapped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn angles_wrap_around() {
+        assert_eq!(wrap_angle(0.5), 0.5);
+        assert!((wrap_angle(2.0 * PI + 0.5) - 0.5).abs() < 1e-12);
+        assert!((wrap_angle(-PI - 0.5) - (PI - 0.5)).abs() < 1e-12);
+        assert_eq!(wrap_angle(-PI), PI);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::environment::{ActionId, Environment, ObservableState, RewardT, State};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+pub const MIN_POSITION: f64 = -1.2;
+pub const MAX_POSITION: f64 = 0.6;
+pub const MAX_SPEED: f64 = 0.07;
+pub const GOAL_POSITION: f64 = 0.5;
+const FORCE: f64 = 0.001;
+const GRAVITY: f64 = 0.0025;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum MountainCarAction {
+    Left,
+    Coast,
+    Right,
+}
+
+impl MountainCarAction {
+    pub fn all() -> [MountainCarAction; 3] {
+        [
+            MountainCarAction::Left,
+            MountainCarAction::Coast,
+            MountainCarAction::Right,
+        ]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            MountainCarAction::Left => ActionId(0),
+            MountainCarAction::Coast => ActionId(1),
+            MountainCarAction::Right => ActionId(2),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> MountainCarAction {
+        MountainCarAction::all()[action_id.0]
+    }
+
+    fn throttle(&self) -> f64 {
+        match self {
+            MountainCarAction::Left => -1.0,
+            MountainCarAction::Coast => 0.0,
+            MountainCarAction::Right => 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MountainCarState {
+    position: f64,
+    velocity: f64,
+    steps: usize,
+    max_steps: usize,
+}
+
+impl MountainCarState {
+    pub fn position(&self) -> f64 {
+        self.position
+    }
+
+    pub fn velocity(&self) -> f64 {
+        self.velocity
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    pub fn reached_goal(&self) -> bool {
+        self.position >= GOAL_POSITION
+    }
+
+    // Out of steps before reaching the goal. The episode is cut off, but the
+    // car isn't in a terminal state, so learners should still bootstrap.
+    pub fn is_truncated(&self) -> bool {
+        !self.is_terminal() && self.steps >= self.max_steps
+    }
+}
+
+impl State for MountainCarState {
+    fn is_terminal(&self) -> bool {
+        self.reached_goal()
+    }
+}
+
+impl ObservableState for MountainCarState {
+    fn observation(&self) -> Vec<f64> {
+        vec![self.position, self.velocity]
+    }
+}
+
+// Example 10.1 of Sutton & Barto: an underpowered car has to rock back and
+// forth to get up a steep hill. Every step costs -1.
+#[derive(Debug)]
+pub struct MountainCarEnvironment {
+    state: MountainCarState,
+    rng: StdRng,
+}
+
+impl MountainCarEnvironment {
+    pub fn new(seed: u64) -> Self {
+        let mut env = MountainCarEnvironment {
+            state: MountainCarState {
+                position: 0.0,
+                velocity: 0.0,
+                steps: 0,
+                max_steps: 200,
+            },
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.reset();
+        env
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.state.max_steps = max_steps;
+        self
+    }
+
+    // Starts at rest somewhere in the valley.
+    pub fn reset(&mut self) {
+        self.state.position = self.rng.gen_range(-0.6..-0.4);
+        self.state.velocity = 0.0;
+        self.state.steps = 0;
+    }
+}
+
+impl Environment for MountainCarEnvironment {
+    type Action = MountainCarAction;
+    type State = MountainCarState;
+
+    fn state(&self) -> &MountainCarState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<MountainCarAction> {
+        if self.state.is_terminal() || self.state.is_truncated() {
+            return vec![];
+        }
+        MountainCarAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &MountainCarAction) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("tried applying an action to a terminal state");
+        }
+        if self.state.is_truncated() {
+            panic!("tried applying an action after the step limit");
+        }
+        let state = &mut self.state;
+        state.velocity += action.throttle() * FORCE - GRAVITY * (3.0 * state.position).cos();
+        state.velocity = state.velocity.clamp(-MAX_SPEED, MAX_SPEED);
+        state.position = (state.position + state.velocity).clamp(MIN_POSITION, MAX_POSITION);
+        // The left wall is inelastic.
+        if state.position == MIN_POSITION && state.velocity < 0.0 {
+            state.velocity = 0.0;
+        }
+        state.steps += 1;
+        RewardT(-1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn run_episode(
+        env: &mut MountainCarEnvironment,
+        policy: fn(&MountainCarState) -> MountainCarAction,
+    ) -> f64 {
+        let mut total = 0.0;
+        while !env.state().is_terminal() && !env.state().is_truncated() {
+            let action = policy(env.state());
+            total += env.apply_action(&action).0;
+        }
+        total
+    }
+
+    #[test]
+    fn full_throttle_is_too_weak() {
+        let mut env = MountainCarEnvironment::new(0);
+        let total = run_episode(&mut env, |_| MountainCarAction::Right);
+        assert_eq!(total, -200.0);
+        assert!(env.state().is_truncated());
+        assert!(!env.state().is_terminal());
+        assert!(env.actions().is_empty());
+    }
+
+    #[test]
+    fn rocking_reaches_goal() {
+        for seed in 0..5 {
+            let mut env = MountainCarEnvironment::new(seed);
+            // Accelerating in the direction of motion pumps energy into the
+            // car.
+            run_episode(&mut env, |state| {
+                if state.velocity() < 0.0 {
+                    MountainCarAction::Left
+                } else {
+                    MountainCarAction::Right
+                }
+            });
+            assert!(env.state().reached_goal());
+            assert!(!env.state().is_truncated());
+            assert!(env.state().steps() < 200);
+        }
+    }
+
+    #[test]
+    fn resets_are_seeded() {
+        let first = MountainCarEnvironment::new(4).state().observation();
+        assert_eq!(MountainCarEnvironment::new(4).state().observation(), first);
+        assert!((-0.6..-0.4).contains(&first[0]));
+    }
+}
@@ -0,0 +1,211 @@
+use crate::control::wrap_angle;
+use crate::environment::{
+    ContinuousAction, ContinuousEnvironment, Environment, ObservableState, RewardT, State,
+};
+use crate::space::BoxSpace;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f64::consts::PI;
+
+pub const MAX_TORQUE: f64 = 2.0;
+pub const MAX_SPEED: f64 = 8.0;
+const GRAVITY: f64 = 10.0;
+const MASS: f64 = 1.0;
+const LENGTH: f64 = 1.0;
+const TIME_STEP: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PendulumState {
+    // Radians in (-π, π], zero is upright.
+    angle: f64,
+    angular_velocity: f64,
+    steps: usize,
+    max_steps: usize,
+}
+
+impl PendulumState {
+    pub fn angle(&self) -> f64 {
+        self.angle
+    }
+
+    pub fn angular_velocity(&self) -> f64 {
+        self.angular_velocity
+    }
+
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    // Episodes are only ever cut off here, there is no terminal state.
+    pub fn is_truncated(&self) -> bool {
+        self.steps >= self.max_steps
+    }
+}
+
+// A continuing task.
+impl State for PendulumState {
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+impl ObservableState for PendulumState {
+    fn observation(&self) -> Vec<f64> {
+        vec![self.angle.cos(), self.angle.sin(), self.angular_velocity]
+    }
+}
+
+// The inverted pendulum swing-up: a pendulum with a motor too weak to lift
+// it directly has to be swung up and kept upright. Every step costs the
+// squared angle plus small penalties on the velocity and torque, so the best
+// reward is 0.
+#[derive(Debug)]
+pub struct PendulumEnvironment {
+    state: PendulumState,
+    action_space: BoxSpace,
+    rng: StdRng,
+}
+
+impl PendulumEnvironment {
+    pub fn new(seed: u64) -> Self {
+        let mut env = PendulumEnvironment {
+            state: PendulumState {
+                angle: 0.0,
+                angular_velocity: 0.0,
+                steps: 0,
+                max_steps: 200,
+            },
+            action_space: BoxSpace::uniform(1, -MAX_TORQUE, MAX_TORQUE),
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.reset();
+        env
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.state.max_steps = max_steps;
+        self
+    }
+
+    pub fn with_state(mut self, angle: f64, angular_velocity: f64) -> Self {
+        self.state.angle = wrap_angle(angle);
+        self.state.angular_velocity = angular_velocity.clamp(-MAX_SPEED, MAX_SPEED);
+        self.state.steps = 0;
+        self
+    }
+
+    // Starts at a random angle with a small velocity.
+    pub fn reset(&mut self) {
+        self.state.angle = wrap_angle(self.rng.gen_range(-PI..PI));
+        self.state.angular_velocity = self.rng.gen_range(-1.0..1.0);
+        self.state.steps = 0;
+    }
+}
+
+impl Environment for PendulumEnvironment {
+    type Action = ContinuousAction;
+    type State = PendulumState;
+
+    fn state(&self) -> &PendulumState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<ContinuousAction> {
+        if self.state.is_truncated() {
+            return vec![];
+        }
+        vec![
+            ContinuousAction(vec![-MAX_TORQUE]),
+            ContinuousAction(vec![0.0]),
+            ContinuousAction(vec![MAX_TORQUE]),
+        ]
+    }
+    fn apply_action(&mut self, action: &ContinuousAction) -> RewardT {
+        if self.state.is_truncated() {
+            panic!("tried applying an action after the step limit");
+        }
+        let torque = self.action_space.clip(action).0[0];
+        let state = &mut self.state;
+        let cost =
+            state.angle.powi(2) + 0.1 * state.angular_velocity.powi(2) + 0.001 * torque.powi(2);
+
+        // Semi-implicit Euler integration.
+        let angular_acceleration = 3.0 * GRAVITY / (2.0 * LENGTH) * state.angle.sin()
+            + 3.0 / (MASS * LENGTH * LENGTH) * torque;
+        state.angular_velocity = (state.angular_velocity + TIME_STEP * angular_acceleration)
+            .clamp(-MAX_SPEED, MAX_SPEED);
+        state.angle = wrap_angle(state.angle + TIME_STEP * state.angular_velocity);
+        state.steps += 1;
+        RewardT(-cost)
+    }
+}
+
+impl ContinuousEnvironment for PendulumEnvironment {
+    fn action_space(&self) -> &BoxSpace {
+        &self.action_space
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn run_episode(env: &mut PendulumEnvironment, policy: fn(&PendulumState) -> f64) -> f64 {
+        let mut total = 0.0;
+        while !env.state().is_truncated() {
+            let torque = policy(env.state());
+            total += env.apply_action(&ContinuousAction(vec![torque])).0;
+        }
+        total
+    }
+
+    #[test]
+    fn torques_are_clipped() {
+        let mut clipped = PendulumEnvironment::new(0).with_state(1.0, 0.0);
+        let mut bounded = PendulumEnvironment::new(0).with_state(1.0, 0.0);
+        let reward = clipped.apply_action(&ContinuousAction(vec![-50.0]));
+        assert_eq!(
+            reward,
+            bounded.apply_action(&ContinuousAction(vec![-MAX_TORQUE]))
+        );
+        assert_eq!(clipped.state(), bounded.state());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't clip an invalid action")]
+    fn wrong_dimension_panics() {
+        PendulumEnvironment::new(0).apply_action(&ContinuousAction(vec![0.0, 1.0]));
+    }
+
+    #[test]
+    fn motor_is_too_weak_to_lift_directly() {
+        // Pushing with full torque from hanging at rest never gets the
+        // pendulum above the horizontal.
+        let mut env = PendulumEnvironment::new(0).with_state(PI, 0.0);
+        let mut highest = PI;
+        while !env.state().is_truncated() {
+            env.apply_action(&ContinuousAction(vec![MAX_TORQUE]));
+            highest = highest.min(env.state().angle().abs());
+        }
+        assert!(highest > PI / 2.0, "{}", highest);
+    }
+
+    #[test]
+    fn feedback_control_keeps_it_upright() {
+        let mut env = PendulumEnvironment::new(0).with_state(0.3, 0.0);
+        let total = run_episode(&mut env, |state| {
+            -10.0 * state.angle() - 2.0 * state.angular_velocity()
+        });
+        assert!(env.state().angle().abs() < 0.01);
+        assert!(total > -5.0, "{}", total);
+        assert_eq!(env.state().steps(), 200);
+        assert!(!env.state().is_terminal());
+    }
+
+    #[test]
+    fn resets_are_seeded() {
+        let first = PendulumEnvironment::new(3).state().observation();
+        assert_eq!(PendulumEnvironment::new(3).state().observation(), first);
+        assert!(first[2].abs() < 1.0);
+    }
+}
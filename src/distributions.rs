@@ -0,0 +1,85 @@
+// Samplers for the continuous distributions that `rand` itself doesn't offer.
+use rand::Rng;
+use std::f64::consts::PI;
+
+// Box-Muller transform.
+pub fn standard_normal<R: Rng + ?Sized>(rng: &mut R) -> f64 {
+    // `gen` samples from [0, 1), the logarithm needs (0, 1].
+    let u1: f64 = 1.0 - rng.gen::<f64>();
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * PI * u2).cos()
+}
+
+pub fn normal<R: Rng + ?Sized>(rng: &mut R, mean: f64, std_dev: f64) -> f64 {
+    mean + std_dev * standard_normal(rng)
+}
+
+// Marsaglia and Tsang's method, shapes below one are boosted by sampling
+// with `shape + 1` and scaling by U^(1 / shape).
+pub fn gamma<R: Rng + ?Sized>(rng: &mut R, shape: f64) -> f64 {
+    if shape <= 0.0 {
+        panic!(
+            "the shape of a gamma distribution has to be positive, got {}",
+            shape
+        );
+    }
+    if shape < 1.0 {
+        let u: f64 = 1.0 - rng.gen::<f64>();
+        return gamma(rng, shape + 1.0) * u.powf(1.0 / shape);
+    }
+    let d = shape - 1.0 / 3.0;
+    let c = 1.0 / (9.0 * d).sqrt();
+    loop {
+        let x = standard_normal(rng);
+        let v = (1.0 + c * x).powi(3);
+        if v <= 0.0 {
+            continue;
+        }
+        let u: f64 = 1.0 - rng.gen::<f64>();
+        if u.ln() < 0.5 * x * x + d - d * v + d * v.ln() {
+            return d * v;
+        }
+    }
+}
+
+pub fn beta<R: Rng + ?Sized>(rng: &mut R, alpha: f64, beta: f64) -> f64 {
+    let x = gamma(rng, alpha);
+    let y = gamma(rng, beta);
+    x / (x + y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn mean_and_variance(samples: &[f64]) -> (f64, f64) {
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let variance =
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+        (mean, variance)
+    }
+
+    #[test]
+    fn moments() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let n = 50000;
+
+        let samples: Vec<f64> = (0..n).map(|_| normal(&mut rng, 2.0, 3.0)).collect();
+        let (mean, variance) = mean_and_variance(&samples);
+        assert!((mean - 2.0).abs() < 0.05 && (variance - 9.0).abs() < 0.2);
+
+        for shape in [0.5, 1.0, 4.0] {
+            let samples: Vec<f64> = (0..n).map(|_| gamma(&mut rng, shape)).collect();
+            let (mean, variance) = mean_and_variance(&samples);
+            assert!((mean - shape).abs() < 0.05, "{} {}", shape, mean);
+            assert!((variance - shape).abs() < 0.15, "{} {}", shape, variance);
+        }
+
+        let samples: Vec<f64> = (0..n).map(|_| beta(&mut rng, 2.0, 6.0)).collect();
+        let (mean, variance) = mean_and_variance(&samples);
+        assert!((mean - 0.25).abs() < 0.01);
+        assert!((variance - 12.0 / (64.0 * 9.0)).abs() < 0.002);
+    }
+}
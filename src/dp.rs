@@ -0,0 +1,478 @@
+use crate::environment::{ActionId, StateId, StateKey, StateTransition};
+use crate::lp::{LinearProgram, LpError, Relation};
+use std::collections::HashMap;
+
+pub type TransitionTable<K = usize> = HashMap<StateId<K>, Vec<StateTransition<K>>>;
+pub type ValueTable<K = usize> = HashMap<StateId<K>, f64>;
+pub type Policy<K = usize> = HashMap<StateId<K>, ActionId>;
+// The probabilities of the actions of every state.
+pub type StochasticPolicy<K = usize> = HashMap<StateId<K>, Vec<(ActionId, f64)>>;
+// The expected discounted number of times every action is taken in every
+// state.
+pub type OccupancyMeasure<K = usize> = HashMap<(StateId<K>, ActionId), f64>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct DPConfig {
+    pub discount: f64,
+    // Sweeps stop once no value changes by more than `theta`.
+    pub theta: f64,
+    pub max_sweeps: usize,
+}
+
+impl Default for DPConfig {
+    fn default() -> Self {
+        DPConfig {
+            discount: 1.0,
+            theta: 1e-9,
+            max_sweeps: 10000,
+        }
+    }
+}
+
+// The step size of the sample-based learners, multiplied by `decay` after
+// every update. A decay of 1 keeps it constant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LearningRate {
+    pub rate: f64,
+    pub decay: f64,
+}
+
+impl LearningRate {
+    pub fn new(rate: f64, decay: f64) -> Self {
+        LearningRate { rate, decay }
+    }
+
+    // Returns the rate for the current update and decays it for the next one.
+    pub fn step(&mut self) -> f64 {
+        let rate = self.rate;
+        self.rate *= self.decay;
+        rate
+    }
+}
+
+// Expected returns of the actions in `transitions`, in the order the actions
+// first appear. States missing from `values` are worth zero.
+pub fn action_values<K: StateKey>(
+    transitions: &[StateTransition<K>],
+    values: &ValueTable<K>,
+    discount: f64,
+) -> Vec<(ActionId, f64)> {
+    let mut action_values: Vec<(ActionId, f64)> = vec![];
+    for transition in transitions.iter() {
+        let next_value = values.get(&transition.new_state_id).unwrap_or(&0.0);
+        let value = transition.prob.0 * (transition.reward.0 + discount * next_value);
+        match action_values
+            .iter_mut()
+            .find(|(action_id, _)| *action_id == transition.action_id)
+        {
+            Some((_, action_value)) => *action_value += value,
+            None => action_values.push((transition.action_id, value)),
+        }
+    }
+    action_values
+}
+
+// Runs in-place sweeps over all states of `table`, `backup` computes the new
+// value of a state from its transitions.
+pub(crate) fn sweep_until_converged<K, F>(
+    table: &TransitionTable<K>,
+    config: &DPConfig,
+    backup: F,
+) -> ValueTable<K>
+where
+    K: StateKey,
+    F: Fn(&StateId<K>, &[StateTransition<K>], &ValueTable<K>) -> f64,
+{
+    let mut values: ValueTable<K> = table.keys().map(|id| (id.clone(), 0.0)).collect();
+    for _ in 0..config.max_sweeps {
+        let mut max_delta: f64 = 0.0;
+        for (state_id, transitions) in table.iter() {
+            if transitions.is_empty() {
+                continue;
+            }
+            let new_value = backup(state_id, transitions, &values);
+            let old_value = values.insert(state_id.clone(), new_value).unwrap_or(0.0);
+            max_delta = max_delta.max((new_value - old_value).abs());
+        }
+        if max_delta <= config.theta {
+            break;
+        }
+    }
+    values
+}
+
+// Computes the values of a deterministic policy. States the policy doesn't
+// cover have to be terminal.
+pub fn policy_evaluation<K: StateKey>(
+    table: &TransitionTable<K>,
+    policy: &Policy<K>,
+    config: &DPConfig,
+) -> ValueTable<K> {
+    sweep_until_converged(table, config, |state_id, transitions, values| {
+        let action_id = policy
+            .get(state_id)
+            .unwrap_or_else(|| panic!("no action for non-terminal state {:?}", state_id));
+        action_values(transitions, values, config.discount)
+            .into_iter()
+            .find(|(id, _)| id == action_id)
+            .unwrap_or_else(|| panic!("{:?} is not available in {:?}", action_id, state_id))
+            .1
+    })
+}
+
+pub fn value_iteration<K: StateKey>(
+    table: &TransitionTable<K>,
+    config: &DPConfig,
+) -> ValueTable<K> {
+    sweep_until_converged(table, config, |_, transitions, values| {
+        best_action(action_values(transitions, values, config.discount)).1
+    })
+}
+
+// Picks the action with the highest expected return in every non-terminal
+// state, ties go to the action appearing first.
+pub fn greedy_policy<K: StateKey>(
+    table: &TransitionTable<K>,
+    values: &ValueTable<K>,
+    discount: f64,
+) -> Policy<K> {
+    table
+        .iter()
+        .filter(|(_, transitions)| !transitions.is_empty())
+        .map(|(state_id, transitions)| {
+            let (action_id, _) = best_action(action_values(transitions, values, discount));
+            (state_id.clone(), action_id)
+        })
+        .collect()
+}
+
+// Root mean squared error of `estimates` over the states of `true_values`,
+// states without an estimate count as zero.
+pub fn rms_error<K: StateKey>(estimates: &ValueTable<K>, true_values: &ValueTable<K>) -> f64 {
+    if true_values.is_empty() {
+        return 0.0;
+    }
+    let squared_error: f64 = true_values
+        .iter()
+        .map(|(state_id, value)| (estimates.get(state_id).unwrap_or(&0.0) - value).powi(2))
+        .sum();
+    (squared_error / true_values.len() as f64).sqrt()
+}
+
+// Solves the Bellman optimality equations as the linear program
+// min sum_s v(s) s.t. v(s) >= r(s, a) + discount * sum_s' p(s' | s, a) v(s')
+// for every action, whose solution is the optimal value function. With a
+// discount of 1 every policy has to reach a terminal state, otherwise the
+// program may have no solution.
+pub fn lp_values<K: StateKey + Ord>(
+    table: &TransitionTable<K>,
+    discount: f64,
+) -> Result<ValueTable<K>, LpError> {
+    let states = non_terminal_states(table);
+    let index: HashMap<&StateId<K>, usize> =
+        states.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let mut program = LinearProgram::minimize(vec![1.0; states.len()]);
+    for i in 0..states.len() {
+        program = program.with_free_variable(i);
+    }
+    for (i, state_id) in states.iter().enumerate() {
+        for action_id in actions(&table[*state_id]) {
+            let mut coefficients = vec![0.0; states.len()];
+            coefficients[i] = 1.0;
+            let mut reward = 0.0;
+            for transition in table[*state_id].iter().filter(|t| t.action_id == action_id) {
+                reward += transition.prob.0 * transition.reward.0;
+                if let Some(next) = index.get(&transition.new_state_id) {
+                    coefficients[*next] -= discount * transition.prob.0;
+                }
+            }
+            program.add_constraint(coefficients, Relation::GreaterEqual, reward);
+        }
+    }
+    let solution = program.solve()?;
+    let mut values: ValueTable<K> = table.keys().map(|id| (id.clone(), 0.0)).collect();
+    for (state_id, value) in states.into_iter().zip(solution.variables) {
+        values.insert(state_id.clone(), value);
+    }
+    Ok(values)
+}
+
+// The dual of `lp_values`: maximizes the expected return over the occupancy
+// measures x of all policies starting from the distribution `initial`,
+// which are characterized by the flow constraints
+// sum_a x(s', a) = initial(s') + discount * sum_s,a p(s' | s, a) x(s, a).
+pub fn lp_occupancy<K: StateKey + Ord>(
+    table: &TransitionTable<K>,
+    discount: f64,
+    initial: &HashMap<StateId<K>, f64>,
+) -> Result<OccupancyMeasure<K>, LpError> {
+    let (program, pairs) = occupancy_program(table, discount, initial);
+    let solution = program.solve()?;
+    Ok(pairs.into_iter().zip(solution.variables).collect())
+}
+
+// The occupancy LP without a solution yet, so that more constraints can be
+// added, with the state-action pair of every variable.
+pub(crate) fn occupancy_program<K: StateKey + Ord>(
+    table: &TransitionTable<K>,
+    discount: f64,
+    initial: &HashMap<StateId<K>, f64>,
+) -> (LinearProgram, Vec<(StateId<K>, ActionId)>) {
+    let states = non_terminal_states(table);
+    let index: HashMap<&StateId<K>, usize> =
+        states.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+    let pairs: Vec<(StateId<K>, ActionId)> = states
+        .iter()
+        .flat_map(|id| actions(&table[*id]).into_iter().map(|a| ((*id).clone(), a)))
+        .collect();
+    let rewards = pairs
+        .iter()
+        .map(|(state_id, action_id)| expected_reward(&table[state_id], *action_id))
+        .collect();
+    let mut flows = vec![vec![0.0; pairs.len()]; states.len()];
+    for (j, (state_id, action_id)) in pairs.iter().enumerate() {
+        flows[index[state_id]][j] += 1.0;
+        for transition in table[state_id].iter().filter(|t| t.action_id == *action_id) {
+            if let Some(next) = index.get(&transition.new_state_id) {
+                flows[*next][j] -= discount * transition.prob.0;
+            }
+        }
+    }
+    let mut program = LinearProgram::maximize(rewards);
+    for (state_id, coefficients) in states.iter().zip(flows) {
+        let start_prob = *initial.get(*state_id).unwrap_or(&0.0);
+        program.add_constraint(coefficients, Relation::Equal, start_prob);
+    }
+    (program, pairs)
+}
+
+// The expected return of the policy behind `occupancy`.
+pub fn occupancy_return<K: StateKey>(
+    table: &TransitionTable<K>,
+    occupancy: &OccupancyMeasure<K>,
+) -> f64 {
+    occupancy
+        .iter()
+        .map(|((state_id, action_id), x)| x * expected_reward(&table[state_id], *action_id))
+        .sum()
+}
+
+// The actions an occupancy measure takes most often in every non-terminal
+// state. Optimal occupancy measures of the LP are deterministic, states that
+// are never visited get the action appearing first.
+pub fn occupancy_policy<K: StateKey>(
+    table: &TransitionTable<K>,
+    occupancy: &OccupancyMeasure<K>,
+) -> Policy<K> {
+    table
+        .iter()
+        .filter(|(_, transitions)| !transitions.is_empty())
+        .map(|(state_id, transitions)| {
+            let action_values = actions(transitions)
+                .into_iter()
+                .map(|action_id| {
+                    let key = (state_id.clone(), action_id);
+                    (action_id, *occupancy.get(&key).unwrap_or(&0.0))
+                })
+                .collect();
+            (state_id.clone(), best_action(action_values).0)
+        })
+        .collect()
+}
+
+// The states with transitions in a fixed order, the variables and constraints
+// of the LPs follow it. In `HashMap` order the simplex could break degenerate
+// ties differently and end at another optimal vertex on every run.
+fn non_terminal_states<K: StateKey + Ord>(table: &TransitionTable<K>) -> Vec<&StateId<K>> {
+    let mut states: Vec<&StateId<K>> = table
+        .iter()
+        .filter(|(_, transitions)| !transitions.is_empty())
+        .map(|(state_id, _)| state_id)
+        .collect();
+    states.sort();
+    states
+}
+
+// The actions of a state in the order they first appear.
+fn actions<K>(transitions: &[StateTransition<K>]) -> Vec<ActionId> {
+    let mut actions = vec![];
+    for transition in transitions.iter() {
+        if !actions.contains(&transition.action_id) {
+            actions.push(transition.action_id);
+        }
+    }
+    actions
+}
+
+fn expected_reward<K>(transitions: &[StateTransition<K>], action_id: ActionId) -> f64 {
+    transitions
+        .iter()
+        .filter(|t| t.action_id == action_id)
+        .map(|t| t.prob.0 * t.reward.0)
+        .sum()
+}
+
+fn best_action(action_values: Vec<(ActionId, f64)>) -> (ActionId, f64) {
+    let mut best: Option<(ActionId, f64)> = None;
+    for (action_id, value) in action_values {
+        if best.is_none_or(|(_, best_value)| value > best_value) {
+            best = Some((action_id, value));
+        }
+    }
+    best.expect("a state with transitions has at least one action")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{ProbabilityT, RewardT};
+    use pretty_assertions::assert_eq;
+
+    fn transition(
+        action: usize,
+        new_state: &str,
+        reward: f64,
+        prob: f64,
+    ) -> StateTransition<String> {
+        StateTransition {
+            action_id: ActionId(action),
+            new_state_id: StateId(new_state.to_string()),
+            reward: RewardT(reward),
+            prob: ProbabilityT(prob),
+        }
+    }
+
+    // From "a" the agent can either walk to "b", which leads to a large reward,
+    // or gamble for a small reward right away.
+    fn make_table() -> TransitionTable<String> {
+        HashMap::from([
+            (
+                StateId("a".to_string()),
+                vec![
+                    transition(0, "b", 0.0, 1.0),
+                    transition(1, "end", 1.0, 0.5),
+                    transition(1, "a", 0.0, 0.5),
+                ],
+            ),
+            (
+                StateId("b".to_string()),
+                vec![transition(0, "end", 10.0, 1.0)],
+            ),
+            (StateId("end".to_string()), vec![]),
+        ])
+    }
+
+    fn config() -> DPConfig {
+        DPConfig {
+            discount: 0.9,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn learning_rate_decays_after_every_step() {
+        let mut learning_rate = LearningRate::new(0.5, 0.5);
+        assert_eq!(learning_rate.step(), 0.5);
+        assert_eq!(learning_rate.step(), 0.25);
+        assert_eq!(learning_rate.rate, 0.125);
+    }
+
+    #[test]
+    fn value_iteration_finds_optimal_policy() {
+        let table = make_table();
+        let values = value_iteration(&table, &config());
+        assert!((values[&StateId("a".to_string())] - 9.0).abs() < 1e-6);
+        assert!((values[&StateId("b".to_string())] - 10.0).abs() < 1e-6);
+        assert_eq!(values[&StateId("end".to_string())], 0.0);
+
+        let policy = greedy_policy(&table, &values, 0.9);
+        assert_eq!(policy.len(), 2);
+        assert_eq!(policy[&StateId("a".to_string())], ActionId(0));
+    }
+
+    #[test]
+    fn policy_evaluation_of_gambling_policy() {
+        let table = make_table();
+        let policy = HashMap::from([
+            (StateId("a".to_string()), ActionId(1)),
+            (StateId("b".to_string()), ActionId(0)),
+        ]);
+        let values = policy_evaluation(&table, &policy, &config());
+        // v(a) = 0.5 + 0.5 * 0.9 * v(a)
+        assert!((values[&StateId("a".to_string())] - 0.5 / 0.55).abs() < 1e-6);
+    }
+
+    #[test]
+    fn action_values_are_grouped_by_action() {
+        let table = make_table();
+        let values = HashMap::from([(StateId("a".to_string()), 2.0)]);
+        let action_values = action_values(&table[&StateId("a".to_string())], &values, 0.5);
+        assert_eq!(action_values, vec![(ActionId(0), 0.0), (ActionId(1), 1.0)]);
+    }
+
+    #[test]
+    fn linear_programs_agree_with_value_iteration() {
+        let table = make_table();
+        let values = value_iteration(&table, &config());
+        let lp_values = lp_values(&table, 0.9).unwrap();
+        assert!(rms_error(&lp_values, &values) < 1e-6);
+
+        let initial = HashMap::from([(StateId("a".to_string()), 1.0)]);
+        let occupancy = lp_occupancy(&table, 0.9, &initial).unwrap();
+        // Walking to "b" visits "a" and "b" once each, discounted once.
+        let a_to_b = (StateId("a".to_string()), ActionId(0));
+        assert!((occupancy[&a_to_b] - 1.0).abs() < 1e-9);
+        let b_to_end = (StateId("b".to_string()), ActionId(0));
+        assert!((occupancy[&b_to_end] - 0.9).abs() < 1e-9);
+        let expected_return = occupancy_return(&table, &occupancy);
+        assert!((expected_return - values[&StateId("a".to_string())]).abs() < 1e-6);
+        assert_eq!(
+            occupancy_policy(&table, &occupancy),
+            greedy_policy(&table, &values, 0.9)
+        );
+    }
+
+    #[test]
+    fn linear_programs_are_reproducible() {
+        // Every table gets its own hash order, the LP's columns must not.
+        let make_chain = || -> TransitionTable<String> {
+            let mut table: TransitionTable<String> = (0..8)
+                .map(|i| {
+                    let next = format!("s{}", i + 1);
+                    let transitions = vec![
+                        transition(0, &next, 1.0, 1.0),
+                        transition(1, &next, 1.0, 1.0),
+                    ];
+                    (StateId(format!("s{}", i)), transitions)
+                })
+                .collect();
+            table.insert(StateId("s8".to_string()), vec![]);
+            table
+        };
+        let initial = HashMap::from([(StateId("s0".to_string()), 1.0)]);
+        let (_, pairs) = occupancy_program(&make_chain(), 0.9, &initial);
+        assert_eq!(pairs[0], (StateId("s0".to_string()), ActionId(0)));
+        assert_eq!(pairs[15], (StateId("s7".to_string()), ActionId(1)));
+        let occupancy = lp_occupancy(&make_chain(), 0.9, &initial).unwrap();
+        for _ in 0..10 {
+            assert_eq!(occupancy_program(&make_chain(), 0.9, &initial).1, pairs);
+            assert_eq!(
+                lp_occupancy(&make_chain(), 0.9, &initial).unwrap(),
+                occupancy
+            );
+        }
+    }
+
+    #[test]
+    fn rms_error_counts_missing_estimates_as_zero() {
+        let true_values = HashMap::from([
+            (StateId("a".to_string()), 3.0),
+            (StateId("b".to_string()), 1.0),
+        ]);
+        let estimates = HashMap::from([
+            (StateId("a".to_string()), 2.0),
+            (StateId("end".to_string()), 5.0),
+        ]);
+        assert_eq!(rms_error(&estimates, &true_values), 1.0);
+    }
+}
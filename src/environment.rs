@@ -1,21 +1,61 @@
+use crate::space::BoxSpace;
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{Hash, Hasher};
 
 pub trait State {
     fn is_terminal(&self) -> bool;
 }
 
-#[derive(Debug)]
+// States of continuous environments, which can't be enumerated into
+// `StateId`s and are presented to function approximators as a vector.
+pub trait ObservableState: State {
+    fn observation(&self) -> Vec<f64>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct RewardT(pub f64);
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct ProbabilityT(pub f64);
 
-#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
-pub struct StateId(pub usize);
+// A cost of constrained environments, kept apart from rewards: instead of
+// being traded off against the reward, its expected total is bounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CostT(pub f64);
+
+// A reward with one entry per objective, for tasks whose objectives shouldn't
+// be traded off against each other upfront.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorRewardT(pub Vec<f64>);
+
+// Anything that can identify a state in a table: an index into an enumerable
+// state space, a wide integer, a hash or a full key such as a board.
+pub trait StateKey: Clone + Eq + Hash + Debug {}
+
+impl<T: Clone + Eq + Hash + Debug> StateKey for T {}
 
-#[derive(Debug)]
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy, PartialOrd, Ord)]
+pub struct StateId<K = usize>(pub K);
+
+impl StateId<u64> {
+    // Identifies a state by the hash of `value`, for state spaces that can't
+    // be enumerated. Distinct states may collide, albeit rarely.
+    pub fn hashed<T: Hash>(value: &T) -> StateId<u64> {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        StateId(hasher.finish())
+    }
+}
+
+#[derive(Debug, Hash, Eq, PartialEq, Clone, Copy, PartialOrd, Ord)]
 pub struct ActionId(pub usize);
 
+// An action of a continuous action space, e.g. a vector of torques.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContinuousAction(pub Vec<f64>);
+
 pub trait Environment {
     type State: State;
     type Action;
@@ -25,14 +65,115 @@ pub trait Environment {
     fn apply_action(&mut self, action: &Self::Action) -> RewardT;
 }
 
-#[derive(Debug)]
-pub struct StateTransition {
+// Environments whose actions are points of a box. Such actions can't be
+// enumerated, so `actions` only returns `low`, the center and `high` of the
+// box for non-terminal states. Actions outside of the box are clipped to it,
+// actions of the wrong dimension or with NaNs panic.
+pub trait ContinuousEnvironment: Environment<Action = ContinuousAction> {
+    fn action_space(&self) -> &BoxSpace;
+}
+
+// Environments where an action deterministically produces a post-decision
+// state (afterstate) before the environment or an opponent reacts. An
+// environment may identify afterstates with several key types.
+pub trait AfterstateEnvironment<K: StateKey = usize>: Environment {
+    fn afterstate_id(&self, action: &Self::Action) -> StateId<K>;
+}
+
+// `prob` is the probability of ending up in `new_state_id` with `reward` when
+// taking `action_id`, so the probabilities of one action sum up to one.
+#[derive(Debug, Clone)]
+pub struct StateTransition<K = usize> {
     pub action_id: ActionId,
-    pub new_state_id: StateId,
+    pub new_state_id: StateId<K>,
+    pub reward: RewardT,
+    pub prob: ProbabilityT,
+}
+
+// States without transitions are terminal.
+pub trait DPEnvironment<K: StateKey = usize>: Environment {
+    fn state_transitions(&self) -> HashMap<StateId<K>, Vec<StateTransition<K>>>;
+}
+
+// The joint-action form of `StateTransition` for games where both players
+// act at once. `action_ids` is indexed by `Player::index` and the reward is
+// the first player's, the probabilities of one joint action sum up to one.
+#[derive(Debug, Clone)]
+pub struct JointStateTransition<K = usize> {
+    pub action_ids: [ActionId; 2],
+    pub new_state_id: StateId<K>,
     pub reward: RewardT,
     pub prob: ProbabilityT,
 }
 
-pub trait DPEnvironment: Environment {
-    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>>;
+// Two-player zero-sum games with simultaneous moves, where `Self::Action` is
+// a joint action. States without transitions are terminal.
+pub trait MarkovGameEnvironment<K: StateKey = usize>: Environment {
+    fn joint_state_transitions(&self) -> HashMap<StateId<K>, Vec<JointStateTransition<K>>>;
+}
+
+// A `StateTransition` that also incurs a cost in every cost channel of the
+// environment.
+#[derive(Debug, Clone)]
+pub struct CostedStateTransition<K = usize> {
+    pub transition: StateTransition<K>,
+    pub costs: Vec<CostT>,
+}
+
+// Environments with one or more cost channels. All transitions have one cost
+// per channel, states without transitions are terminal.
+pub trait ConstrainedDPEnvironment<K: StateKey = usize>: Environment {
+    fn num_cost_channels(&self) -> usize;
+    fn costed_state_transitions(&self) -> HashMap<StateId<K>, Vec<CostedStateTransition<K>>>;
+}
+
+// The multi-objective form of `StateTransition`.
+#[derive(Debug, Clone)]
+pub struct VectorStateTransition<K = usize> {
+    pub action_id: ActionId,
+    pub new_state_id: StateId<K>,
+    pub reward: VectorRewardT,
+    pub prob: ProbabilityT,
+}
+
+// Environments with several objectives. All rewards have one entry per
+// objective, states without transitions are terminal.
+pub trait MultiObjectiveDPEnvironment<K: StateKey = usize>: Environment {
+    fn num_objectives(&self) -> usize;
+    fn vector_state_transitions(&self) -> HashMap<StateId<K>, Vec<VectorStateTransition<K>>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Player {
+    First,
+    Second,
+}
+
+impl Player {
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::First => Player::Second,
+            Player::Second => Player::First,
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        match self {
+            Player::First => 0,
+            Player::Second => 1,
+        }
+    }
+
+    // Rewards of two-player environments are given from the first player's
+    // perspective, the games are zero-sum so the second player gets the negation.
+    pub fn perspective(&self, reward: RewardT) -> RewardT {
+        match self {
+            Player::First => reward,
+            Player::Second => RewardT(-reward.0),
+        }
+    }
+}
+
+pub trait TwoPlayerEnvironment: Environment {
+    fn current_player(&self) -> Player;
 }
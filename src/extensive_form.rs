@@ -0,0 +1,339 @@
+// Two-player games with chance and hidden information, described as a game
+// tree. A history is the sequence of all actions so far, including the
+// outcomes of chance. Players don't observe full histories, only the
+// information set of the history they act in: histories a player can't tell
+// apart share an information set.
+use crate::environment::{
+    Environment, Player, ProbabilityT, RewardT, State, StateKey, TwoPlayerEnvironment,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+use std::fmt::Debug;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeKind {
+    Chance,
+    Decision(Player),
+    Terminal,
+}
+
+pub trait ExtensiveFormGame {
+    type History: State + StateKey;
+    type Action: Clone + PartialEq + Debug;
+    type InfoSet: StateKey;
+
+    // The empty history the game starts from.
+    fn root(&self) -> Self::History;
+    fn node_kind(&self, history: &Self::History) -> NodeKind;
+    // The outcomes of a chance node, their probabilities sum up to one.
+    fn chance_outcomes(&self, history: &Self::History) -> Vec<(Self::Action, ProbabilityT)>;
+    // The actions of the player to act in a decision node. Histories of the
+    // same information set have the same actions.
+    fn actions(&self, history: &Self::History) -> Vec<Self::Action>;
+    // The information set of the player to act in a decision node.
+    fn info_set(&self, history: &Self::History) -> Self::InfoSet;
+    fn apply(&self, history: &Self::History, action: &Self::Action) -> Self::History;
+    // The rewards of both players in a terminal history, indexed by
+    // `Player::index`.
+    fn rewards(&self, history: &Self::History) -> [RewardT; 2];
+}
+
+// All information sets of `game` with the player acting in them and their
+// actions, found by walking the whole game tree.
+pub fn info_sets<G: ExtensiveFormGame>(game: &G) -> HashMap<G::InfoSet, (Player, Vec<G::Action>)> {
+    let mut result = HashMap::new();
+    let mut stack = vec![game.root()];
+    while let Some(history) = stack.pop() {
+        match game.node_kind(&history) {
+            NodeKind::Terminal => {}
+            NodeKind::Chance => {
+                for (outcome, _) in game.chance_outcomes(&history) {
+                    stack.push(game.apply(&history, &outcome));
+                }
+            }
+            NodeKind::Decision(player) => {
+                let actions = game.actions(&history);
+                for action in actions.iter() {
+                    stack.push(game.apply(&history, action));
+                }
+                result
+                    .entry(game.info_set(&history))
+                    .or_insert((player, actions));
+            }
+        }
+    }
+    result
+}
+
+// The expected rewards of both players when they act according to
+// `strategy`, which gives the probabilities of the actions of an information
+// set in the order of `ExtensiveFormGame::actions`.
+pub fn expected_rewards<G, S>(game: &G, strategy: &S) -> [f64; 2]
+where
+    G: ExtensiveFormGame,
+    S: Fn(&G::InfoSet, &[G::Action]) -> Vec<f64>,
+{
+    fn visit<G, S>(game: &G, strategy: &S, history: &G::History) -> [f64; 2]
+    where
+        G: ExtensiveFormGame,
+        S: Fn(&G::InfoSet, &[G::Action]) -> Vec<f64>,
+    {
+        let branches: Vec<(G::Action, f64)> = match game.node_kind(history) {
+            NodeKind::Terminal => {
+                let rewards = game.rewards(history);
+                return [rewards[0].0, rewards[1].0];
+            }
+            NodeKind::Chance => game
+                .chance_outcomes(history)
+                .into_iter()
+                .map(|(outcome, prob)| (outcome, prob.0))
+                .collect(),
+            NodeKind::Decision(_) => {
+                let actions = game.actions(history);
+                let probs = strategy(&game.info_set(history), &actions);
+                actions.into_iter().zip(probs).collect()
+            }
+        };
+        let mut values = [0.0; 2];
+        for (action, prob) in branches {
+            if prob == 0.0 {
+                continue;
+            }
+            let child = visit(game, strategy, &game.apply(history, &action));
+            values[0] += prob * child[0];
+            values[1] += prob * child[1];
+        }
+        values
+    }
+    visit(game, strategy, &game.root())
+}
+
+// The expected reward `player` gets by best responding to the opponent's
+// part of `strategy`.
+pub fn best_response_value<G, S>(game: &G, strategy: &S, player: Player) -> f64
+where
+    G: ExtensiveFormGame,
+    S: Fn(&G::InfoSet, &[G::Action]) -> Vec<f64>,
+{
+    let mut best_response = BestResponse {
+        game,
+        strategy,
+        player,
+        histories: HashMap::new(),
+        best_actions: HashMap::new(),
+        values: HashMap::new(),
+    };
+    best_response.collect(game.root(), 1.0);
+    best_response.value(&game.root())
+}
+
+// How much the players gain on average by deviating from `strategy` to a
+// best response. It's zero exactly for equilibria of zero-sum games, which
+// CFR converges to.
+pub fn exploitability<G, S>(game: &G, strategy: &S) -> f64
+where
+    G: ExtensiveFormGame,
+    S: Fn(&G::InfoSet, &[G::Action]) -> Vec<f64>,
+{
+    let gains = best_response_value(game, strategy, Player::First)
+        + best_response_value(game, strategy, Player::Second);
+    gains / 2.0
+}
+
+struct BestResponse<'a, G: ExtensiveFormGame, S> {
+    game: &'a G,
+    strategy: &'a S,
+    player: Player,
+    // The histories of every information set of `player`, with the
+    // probability that chance and the opponent reach them.
+    histories: HashMap<G::InfoSet, Vec<(G::History, f64)>>,
+    best_actions: HashMap<G::InfoSet, G::Action>,
+    values: HashMap<G::History, f64>,
+}
+
+impl<G, S> BestResponse<'_, G, S>
+where
+    G: ExtensiveFormGame,
+    S: Fn(&G::InfoSet, &[G::Action]) -> Vec<f64>,
+{
+    fn collect(&mut self, history: G::History, reach: f64) {
+        match self.game.node_kind(&history) {
+            NodeKind::Terminal => {}
+            NodeKind::Chance => {
+                for (outcome, prob) in self.game.chance_outcomes(&history) {
+                    self.collect(self.game.apply(&history, &outcome), reach * prob.0);
+                }
+            }
+            NodeKind::Decision(player) if player == self.player => {
+                for action in self.game.actions(&history) {
+                    self.collect(self.game.apply(&history, &action), reach);
+                }
+                self.histories
+                    .entry(self.game.info_set(&history))
+                    .or_default()
+                    .push((history, reach));
+            }
+            NodeKind::Decision(_) => {
+                let actions = self.game.actions(&history);
+                let probs = (self.strategy)(&self.game.info_set(&history), &actions);
+                for (action, prob) in actions.iter().zip(probs) {
+                    self.collect(self.game.apply(&history, action), reach * prob);
+                }
+            }
+        }
+    }
+
+    fn value(&mut self, history: &G::History) -> f64 {
+        if let Some(value) = self.values.get(history) {
+            return *value;
+        }
+        let value = match self.game.node_kind(history) {
+            NodeKind::Terminal => self.game.rewards(history)[self.player.index()].0,
+            NodeKind::Chance => self
+                .game
+                .chance_outcomes(history)
+                .iter()
+                .map(|(outcome, prob)| prob.0 * self.value(&self.game.apply(history, outcome)))
+                .sum(),
+            NodeKind::Decision(player) if player == self.player => {
+                let action = self.best_action(self.game.info_set(history));
+                self.value(&self.game.apply(history, &action))
+            }
+            NodeKind::Decision(_) => {
+                let actions = self.game.actions(history);
+                let probs = (self.strategy)(&self.game.info_set(history), &actions);
+                let mut value = 0.0;
+                for (action, prob) in actions.iter().zip(probs) {
+                    if prob > 0.0 {
+                        value += prob * self.value(&self.game.apply(history, action));
+                    }
+                }
+                value
+            }
+        };
+        self.values.insert(history.clone(), value);
+        value
+    }
+
+    // The action maximizing the reach-weighted value over all histories of
+    // `info_set`, which is what the player can condition on.
+    fn best_action(&mut self, info_set: G::InfoSet) -> G::Action {
+        if let Some(action) = self.best_actions.get(&info_set) {
+            return action.clone();
+        }
+        let histories = self.histories[&info_set].clone();
+        let actions = self.game.actions(&histories[0].0);
+        let mut best = (f64::NEG_INFINITY, 0);
+        for (i, action) in actions.iter().enumerate() {
+            let value: f64 = histories
+                .iter()
+                .map(|(history, reach)| reach * self.value(&self.game.apply(history, action)))
+                .sum();
+            if value > best.0 {
+                best = (value, i);
+            }
+        }
+        let action = actions[best.1].clone();
+        self.best_actions.insert(info_set, action.clone());
+        action
+    }
+}
+
+// Plays an extensive-form game through the `Environment` interface. Chance
+// nodes are resolved by sampling right away, so the state is always a
+// decision node or terminal. Like other two-player environments, rewards are
+// given from the first player's perspective, `rewards` has both of them.
+#[derive(Debug)]
+pub struct ExtensiveFormEnvironment<G: ExtensiveFormGame> {
+    game: G,
+    history: G::History,
+    rng: StdRng,
+}
+
+impl<G: ExtensiveFormGame> ExtensiveFormEnvironment<G> {
+    pub fn new(game: G, seed: u64) -> Self {
+        let history = game.root();
+        let mut env = ExtensiveFormEnvironment {
+            game,
+            history,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.resolve_chance();
+        env
+    }
+
+    pub fn game(&self) -> &G {
+        &self.game
+    }
+
+    pub fn reset(&mut self) {
+        self.history = self.game.root();
+        self.resolve_chance();
+    }
+
+    // What the player to act knows about the state.
+    pub fn info_set(&self) -> G::InfoSet {
+        if self.history.is_terminal() {
+            panic!("terminal states have no information set");
+        }
+        self.game.info_set(&self.history)
+    }
+
+    // The rewards of both players once the game is over, zero until then.
+    pub fn rewards(&self) -> [RewardT; 2] {
+        if self.history.is_terminal() {
+            self.game.rewards(&self.history)
+        } else {
+            [RewardT(0.0); 2]
+        }
+    }
+
+    fn resolve_chance(&mut self) {
+        while self.game.node_kind(&self.history) == NodeKind::Chance {
+            let outcomes = self.game.chance_outcomes(&self.history);
+            let mut sample: f64 = self.rng.gen();
+            let mut chosen = &outcomes[outcomes.len() - 1].0;
+            for (outcome, prob) in outcomes.iter() {
+                if sample < prob.0 {
+                    chosen = outcome;
+                    break;
+                }
+                sample -= prob.0;
+            }
+            self.history = self.game.apply(&self.history, chosen);
+        }
+    }
+}
+
+impl<G: ExtensiveFormGame> Environment for ExtensiveFormEnvironment<G> {
+    type Action = G::Action;
+    type State = G::History;
+
+    fn state(&self) -> &G::History {
+        &self.history
+    }
+    fn actions(&self) -> Vec<G::Action> {
+        match self.game.node_kind(&self.history) {
+            NodeKind::Decision(_) => self.game.actions(&self.history),
+            _ => vec![],
+        }
+    }
+    fn apply_action(&mut self, action: &G::Action) -> RewardT {
+        if !self.actions().contains(action) {
+            panic!("tried applying an invalid action {:?}", action);
+        }
+        self.history = self.game.apply(&self.history, action);
+        self.resolve_chance();
+        self.rewards()[Player::First.index()]
+    }
+}
+
+impl<G: ExtensiveFormGame> TwoPlayerEnvironment for ExtensiveFormEnvironment<G> {
+    fn current_player(&self) -> Player {
+        match self.game.node_kind(&self.history) {
+            NodeKind::Decision(player) => player,
+            _ => panic!("only decision nodes have a player to act"),
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use crate::environment::ActionId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GridAction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl GridAction {
+    pub fn all() -> [GridAction; 4] {
+        [
+            GridAction::Up,
+            GridAction::Down,
+            GridAction::Left,
+            GridAction::Right,
+        ]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            GridAction::Up => ActionId(0),
+            GridAction::Down => ActionId(1),
+            GridAction::Left => ActionId(2),
+            GridAction::Right => ActionId(3),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> GridAction {
+        GridAction::all()[action_id.0]
+    }
+
+    // Row and column offsets, rows grow downwards.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            GridAction::Up => (-1, 0),
+            GridAction::Down => (1, 0),
+            GridAction::Left => (0, -1),
+            GridAction::Right => (0, 1),
+        }
+    }
+
+    // The two directions an agent can slip into when trying this action.
+    pub fn perpendicular(&self) -> [GridAction; 2] {
+        match self {
+            GridAction::Up | GridAction::Down => [GridAction::Left, GridAction::Right],
+            GridAction::Left | GridAction::Right => [GridAction::Up, GridAction::Down],
+        }
+    }
+}
@@ -0,0 +1,222 @@
+use crate::environment::{
+    DPEnvironment, Environment, ProbabilityT, RewardT, State, StateId, StateTransition,
+};
+use crate::gridworld::action::GridAction;
+use crate::gridworld::tile::Tile;
+use crate::gridworld::world::{GridWorld, Position};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GridWorldState {
+    position: Position,
+    terminal: bool,
+}
+
+impl GridWorldState {
+    pub fn position(&self) -> Position {
+        self.position
+    }
+}
+
+impl State for GridWorldState {
+    fn is_terminal(&self) -> bool {
+        self.terminal
+    }
+}
+
+// Walks an agent through a `GridWorld`, sampling the outcome of every step
+// from the world's dynamics.
+#[derive(Debug)]
+pub struct GridWorldEnvironment {
+    world: GridWorld,
+    state: GridWorldState,
+    rng: StdRng,
+}
+
+impl GridWorldEnvironment {
+    pub fn new(world: GridWorld, seed: u64) -> Self {
+        let mut env = GridWorldEnvironment {
+            state: GridWorldState {
+                position: (0, 0),
+                terminal: false,
+            },
+            world,
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.reset();
+        env
+    }
+
+    pub fn world(&self) -> &GridWorld {
+        &self.world
+    }
+
+    // Puts the agent back on the start tile, or on a random non-terminal cell
+    // if the world has no start.
+    pub fn reset(&mut self) {
+        let starts = self.world.start_positions();
+        let position = starts[self.rng.gen_range(0..starts.len())];
+        self.state = self.state_at(position);
+    }
+
+    pub fn state_id(&self) -> StateId {
+        self.world.state_id(self.state.position)
+    }
+
+    fn state_at(&self, position: Position) -> GridWorldState {
+        GridWorldState {
+            position,
+            terminal: self.world.tile(position).is_terminal(),
+        }
+    }
+}
+
+impl Environment for GridWorldEnvironment {
+    type Action = GridAction;
+    type State = GridWorldState;
+
+    fn state(&self) -> &GridWorldState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<GridAction> {
+        if self.state.terminal {
+            return vec![];
+        }
+        GridAction::all().to_vec()
+    }
+    fn apply_action(&mut self, action: &GridAction) -> RewardT {
+        if self.state.terminal {
+            panic!("tried applying an action to a terminal state");
+        }
+        let outcomes = self.world.outcomes(self.state.position, *action);
+        let mut sample: f64 = self.rng.gen();
+        let outcome = outcomes
+            .iter()
+            .find(|outcome| {
+                sample -= outcome.prob;
+                sample < 0.0
+            })
+            // Rounding may leave a tiny bit of probability mass at the end.
+            .unwrap_or_else(|| outcomes.last().unwrap());
+        self.state = self.state_at(outcome.position);
+        RewardT(outcome.reward)
+    }
+}
+
+// The table covers every cell but the walls, regardless of the current state.
+impl DPEnvironment for GridWorldEnvironment {
+    fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
+        let mut transition_table = HashMap::new();
+        for position in self.world.positions() {
+            let tile = self.world.tile(position);
+            if tile == Tile::Wall {
+                continue;
+            }
+            let mut transitions = vec![];
+            if !tile.is_terminal() {
+                for action in GridAction::all() {
+                    for outcome in self.world.outcomes(position, action) {
+                        transitions.push(StateTransition {
+                            action_id: action.id(),
+                            new_state_id: self.world.state_id(outcome.position),
+                            reward: RewardT(outcome.reward),
+                            prob: ProbabilityT(outcome.prob),
+                        });
+                    }
+                }
+            }
+            transition_table.insert(self.world.state_id(position), transitions);
+        }
+        transition_table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{greedy_policy, value_iteration, DPConfig};
+    use crate::gridworld::presets;
+    use pretty_assertions::assert_eq;
+
+    fn solve(world: GridWorld, discount: f64) -> (GridWorldEnvironment, HashMap<StateId, f64>) {
+        let env = GridWorldEnvironment::new(world, 0);
+        let config = DPConfig {
+            discount,
+            ..Default::default()
+        };
+        let values = value_iteration(&env.state_transitions(), &config);
+        (env, values)
+    }
+
+    #[test]
+    fn transition_probabilities_sum_to_one() {
+        let env = GridWorldEnvironment::new(presets::frozen_lake_8x8(), 0);
+        let table = env.state_transitions();
+        assert_eq!(table.len(), 64);
+        for transitions in table.values() {
+            for action in GridAction::all() {
+                let total: f64 = transitions
+                    .iter()
+                    .filter(|t| t.action_id == action.id())
+                    .map(|t| t.prob.0)
+                    .sum();
+                assert!(transitions.is_empty() || (total - 1.0).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn sutton_4x4_optimal_values() {
+        let (env, values) = solve(presets::sutton_4x4(), 1.0);
+        let world = env.world();
+        // The optimal value is minus the number of steps to the closest corner.
+        assert_eq!(values[&world.state_id((0, 1))], -1.0);
+        assert_eq!(values[&world.state_id((1, 1))], -2.0);
+        assert_eq!(values[&world.state_id((3, 0))], -3.0);
+        assert_eq!(values[&world.state_id((3, 3))], 0.0);
+    }
+
+    #[test]
+    fn cliff_walking_optimal_path() {
+        let (env, values) = solve(presets::cliff_walking(), 1.0);
+        let world = env.world();
+        // Up, eleven steps along the cliff and down again.
+        assert_eq!(values[&env.state_id()], -13.0);
+
+        let policy = greedy_policy(&env.state_transitions(), &values, 1.0);
+        assert_eq!(policy[&world.state_id((3, 0))], GridAction::Up.id());
+        assert_eq!(policy[&world.state_id((2, 5))], GridAction::Right.id());
+        assert_eq!(policy[&world.state_id((2, 11))], GridAction::Down.id());
+    }
+
+    #[test]
+    fn windy_gridworld_shortest_path() {
+        let (env, values) = solve(presets::windy_gridworld(), 1.0);
+        // The wind makes the shortest path 15 steps long instead of 7.
+        assert_eq!(values[&env.state_id()], -15.0);
+    }
+
+    #[test]
+    fn frozen_lake_is_stochastic() {
+        let (env, values) = solve(presets::frozen_lake_4x4(), 0.99);
+        let start_value = values[&env.state_id()];
+        assert!(start_value > 0.5 && start_value < 0.6, "{}", start_value);
+
+        // Episodes end in a hole or on the goal.
+        let mut env = env;
+        let mut rewards = vec![];
+        for _ in 0..20 {
+            env.reset();
+            let mut reward = RewardT(0.0);
+            while !env.state().is_terminal() {
+                reward = env.apply_action(&GridAction::Right);
+            }
+            let tile = env.world().tile(env.state().position());
+            assert!(tile == Tile::Goal || tile == Tile::Hole);
+            rewards.push(reward.0);
+        }
+        assert!(rewards.contains(&0.0));
+    }
+}
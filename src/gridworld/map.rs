@@ -0,0 +1,376 @@
+// Plain-text gridworld maps. A map starts with an optional header of
+// `key = value` lines, separated from the grid by a `---` line:
+//
+//   step_reward = -1
+//   slip = 0.1
+//   ---
+//   S..#....
+//   .#.#.##.
+//   ...H...G
+//   00112210
+//
+// Grid rows use the characters of `Tile`. A last row made of digits gives
+// the upward wind of every column.
+use crate::gridworld::tile::Tile;
+use crate::gridworld::world::GridWorld;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+const SEPARATOR: &str = "---";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapErrorKind {
+    UnknownTile(char),
+    UnknownKey(String),
+    DuplicateKey(String),
+    MissingValue(String),
+    InvalidValue(String),
+    RowLength { expected: usize, got: usize },
+    MultipleStarts,
+    CliffWithoutStart,
+    NoStartPosition,
+    EmptyMap,
+}
+
+// Lines and columns start at 1, like in text editors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapParseError {
+    pub line: usize,
+    pub column: usize,
+    pub kind: MapErrorKind,
+}
+
+impl fmt::Display for MapParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            MapErrorKind::UnknownTile(c) => write!(f, "unknown tile {:?}", c),
+            MapErrorKind::UnknownKey(key) => write!(f, "unknown header key {:?}", key),
+            MapErrorKind::DuplicateKey(key) => write!(f, "header key {:?} is set twice", key),
+            MapErrorKind::MissingValue(key) => write!(f, "header key {:?} has no value", key),
+            MapErrorKind::InvalidValue(value) => write!(f, "invalid value {:?}", value),
+            MapErrorKind::RowLength { expected, got } => {
+                write!(f, "expected {} tiles, got {}", expected, got)
+            }
+            MapErrorKind::MultipleStarts => f.write_str("a map can have at most one start"),
+            MapErrorKind::CliffWithoutStart => f.write_str("cliffs need a start to return to"),
+            MapErrorKind::NoStartPosition => {
+                f.write_str("a map without a start needs a cell to start on")
+            }
+            MapErrorKind::EmptyMap => f.write_str("the map has no tiles"),
+        }
+    }
+}
+
+impl std::error::Error for MapParseError {}
+
+#[derive(Debug)]
+pub enum MapReadError {
+    Io(std::io::Error),
+    Parse(MapParseError),
+}
+
+impl fmt::Display for MapReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MapReadError::Io(e) => write!(f, "couldn't read map: {}", e),
+            MapReadError::Parse(e) => write!(f, "couldn't parse map: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for MapReadError {}
+
+fn error(line: usize, column: usize, kind: MapErrorKind) -> MapParseError {
+    MapParseError { line, column, kind }
+}
+
+// Setters of the header keys, a value is validated before it's applied.
+type HeaderSetter = fn(GridWorld, f64) -> GridWorld;
+
+fn header_setter(key: &str) -> Option<HeaderSetter> {
+    match key {
+        "step_reward" => Some(GridWorld::with_step_reward),
+        "goal_reward" => Some(GridWorld::with_goal_reward),
+        "hole_reward" => Some(GridWorld::with_hole_reward),
+        "cliff_reward" => Some(GridWorld::with_cliff_reward),
+        "slip" => Some(GridWorld::with_slip_probability),
+        _ => None,
+    }
+}
+
+fn is_wind_row(row: &str) -> bool {
+    !row.is_empty() && row.chars().all(|c| c.is_ascii_digit())
+}
+
+impl FromStr for GridWorld {
+    type Err = MapParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines: Vec<&str> = s.lines().map(|line| line.trim_end()).collect();
+        let separator = lines.iter().position(|&line| line == SEPARATOR);
+        let (header, grid_start) = match separator {
+            Some(index) => (&lines[..index], index + 1),
+            None => (&lines[..0], 0),
+        };
+
+        let mut settings: Vec<(&str, HeaderSetter, f64)> = vec![];
+        for (index, line) in header.iter().enumerate() {
+            let line_number = index + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value),
+                None => {
+                    let key = line.trim().to_string();
+                    return Err(error(line_number, 1, MapErrorKind::MissingValue(key)));
+                }
+            };
+            let key_column = line.len() - line.trim_start().len() + 1;
+            let setter = header_setter(key).ok_or_else(|| {
+                error(
+                    line_number,
+                    key_column,
+                    MapErrorKind::UnknownKey(key.to_string()),
+                )
+            })?;
+            if settings.iter().any(|(k, _, _)| *k == key) {
+                return Err(error(
+                    line_number,
+                    key_column,
+                    MapErrorKind::DuplicateKey(key.to_string()),
+                ));
+            }
+            let value_column = line.len() - value.trim_start().len() + 1;
+            let value = value.trim();
+            if value.is_empty() {
+                return Err(error(
+                    line_number,
+                    value_column,
+                    MapErrorKind::MissingValue(key.to_string()),
+                ));
+            }
+            let parsed = value
+                .parse::<f64>()
+                .ok()
+                .filter(|v| v.is_finite())
+                .filter(|v| key != "slip" || (0.0..=1.0).contains(v))
+                .ok_or_else(|| {
+                    error(
+                        line_number,
+                        value_column,
+                        MapErrorKind::InvalidValue(value.to_string()),
+                    )
+                })?;
+            settings.push((key, setter, parsed));
+        }
+
+        let mut rows: Vec<(usize, &str)> = lines
+            .iter()
+            .enumerate()
+            .skip(grid_start)
+            .map(|(index, &line)| (index + 1, line))
+            .filter(|(_, line)| !line.is_empty())
+            .collect();
+        let wind_row = match rows.last() {
+            Some(&(_, row)) if rows.len() > 1 && is_wind_row(row) => rows.pop(),
+            _ => None,
+        };
+        let Some(&(_, first_row)) = rows.first() else {
+            return Err(error(grid_start + 1, 1, MapErrorKind::EmptyMap));
+        };
+
+        let cols = first_row.chars().count();
+        let mut tiles = vec![];
+        let mut start: Option<(usize, usize)> = None;
+        let mut cliff: Option<(usize, usize)> = None;
+        for &(line_number, row) in rows.iter().chain(wind_row.iter()) {
+            let got = row.chars().count();
+            if got != cols {
+                return Err(error(
+                    line_number,
+                    got.min(cols) + 1,
+                    MapErrorKind::RowLength {
+                        expected: cols,
+                        got,
+                    },
+                ));
+            }
+        }
+        for &(line_number, row) in rows.iter() {
+            let mut tile_row = vec![];
+            for (index, c) in row.chars().enumerate() {
+                let tile = Tile::try_from(c)
+                    .map_err(|e| error(line_number, index + 1, MapErrorKind::UnknownTile(e.0)))?;
+                match tile {
+                    Tile::Start if start.is_some() => {
+                        return Err(error(line_number, index + 1, MapErrorKind::MultipleStarts))
+                    }
+                    Tile::Start => start = Some((line_number, index + 1)),
+                    Tile::Cliff if cliff.is_none() => cliff = Some((line_number, index + 1)),
+                    _ => {}
+                }
+                tile_row.push(tile);
+            }
+            tiles.push(tile_row);
+        }
+        if let (None, Some((line, column))) = (start, cliff) {
+            return Err(error(line, column, MapErrorKind::CliffWithoutStart));
+        }
+        if start.is_none() && !tiles.iter().flatten().any(|t| t.can_start()) {
+            let (line_number, _) = rows[0];
+            return Err(error(line_number, 1, MapErrorKind::NoStartPosition));
+        }
+
+        let mut world = GridWorld::new(tiles);
+        if let Some((_, row)) = wind_row {
+            let wind = row
+                .chars()
+                .map(|c| c.to_digit(10).unwrap() as usize)
+                .collect();
+            world = world.with_wind(wind);
+        }
+        for (_, setter, value) in settings {
+            world = setter(world, value);
+        }
+        Ok(world)
+    }
+}
+
+impl fmt::Display for GridWorld {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "step_reward = {}", self.step_reward())?;
+        writeln!(f, "goal_reward = {}", self.goal_reward())?;
+        writeln!(f, "hole_reward = {}", self.hole_reward())?;
+        writeln!(f, "cliff_reward = {}", self.cliff_reward())?;
+        writeln!(f, "slip = {}", self.slip_probability())?;
+        writeln!(f, "{}", SEPARATOR)?;
+        for row in 0..self.rows() {
+            let row: String = (0..self.cols())
+                .map(|col| char::from(self.tile((row, col))))
+                .collect();
+            writeln!(f, "{}", row)?;
+        }
+        if self.wind().iter().any(|&w| w != 0) {
+            let wind: String = self
+                .wind()
+                .iter()
+                .map(|&w| {
+                    char::from_digit(w as u32, 10)
+                        .unwrap_or_else(|| panic!("wind {} doesn't fit into a map", w))
+                })
+                .collect();
+            writeln!(f, "{}", wind)?;
+        }
+        Ok(())
+    }
+}
+
+impl GridWorld {
+    pub fn read_map(path: impl AsRef<Path>) -> Result<GridWorld, MapReadError> {
+        let text = std::fs::read_to_string(path).map_err(MapReadError::Io)?;
+        text.parse().map_err(MapReadError::Parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gridworld::presets;
+    use pretty_assertions::assert_eq;
+
+    fn parse_error(map: &str) -> MapParseError {
+        map.parse::<GridWorld>().unwrap_err()
+    }
+
+    #[test]
+    fn parse_map_with_header_and_wind() {
+        let world: GridWorld = "\
+step_reward = -0.5
+slip = 0.2
+
+---
+S.#
+.HG
+012
+"
+        .parse()
+        .unwrap();
+        assert_eq!(world.rows(), 2);
+        assert_eq!(world.tile((0, 2)), Tile::Wall);
+        assert_eq!(world.tile((1, 1)), Tile::Hole);
+        assert_eq!(world.wind(), &[0, 1, 2]);
+        assert_eq!(world.step_reward(), -0.5);
+        assert_eq!(world.slip_probability(), 0.2);
+        // Keys missing from the header keep their defaults.
+        assert_eq!(world.goal_reward(), 0.0);
+    }
+
+    #[test]
+    fn map_without_header() {
+        let world: GridWorld = "S..\n..G\n".parse().unwrap();
+        assert_eq!(world.cols(), 3);
+        assert_eq!(world.wind(), &[0, 0, 0]);
+    }
+
+    #[test]
+    fn presets_round_trip() {
+        for world in [
+            presets::sutton_4x4(),
+            presets::cliff_walking(),
+            presets::windy_gridworld(),
+            presets::frozen_lake_8x8(),
+        ] {
+            assert_eq!(world.to_string().parse::<GridWorld>().unwrap(), world);
+        }
+    }
+
+    #[test]
+    fn errors_report_position() {
+        assert_eq!(
+            parse_error("slip = 0.1\n---\nS..\n.x.\n"),
+            error(4, 2, MapErrorKind::UnknownTile('x'))
+        );
+        assert_eq!(
+            parse_error("slip = 0.1\n  speed = 2\n---\nS\n"),
+            error(2, 3, MapErrorKind::UnknownKey("speed".to_string()))
+        );
+        assert_eq!(
+            parse_error("slip =  1.5\n---\nS\n"),
+            error(1, 9, MapErrorKind::InvalidValue("1.5".to_string()))
+        );
+        assert_eq!(
+            parse_error("S...\n..\n"),
+            error(
+                2,
+                3,
+                MapErrorKind::RowLength {
+                    expected: 4,
+                    got: 2
+                }
+            )
+        );
+        assert_eq!(
+            parse_error("S..\n.S.\n"),
+            error(2, 2, MapErrorKind::MultipleStarts)
+        );
+        assert_eq!(
+            parse_error("slip = 0\n---\n"),
+            error(3, 1, MapErrorKind::EmptyMap)
+        );
+        assert_eq!(
+            parse_error("slip = 0\n---\n#G\nH#\n"),
+            error(3, 1, MapErrorKind::NoStartPosition)
+        );
+    }
+
+    #[test]
+    fn error_message() {
+        assert_eq!(
+            parse_error("..\n.?\n").to_string(),
+            "line 2, column 2: unknown tile '?'"
+        );
+    }
+}
@@ -0,0 +1,339 @@
+use crate::gridworld::tile::Tile;
+use crate::gridworld::world::{GridWorld, Position};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::VecDeque;
+
+// Generates gridworlds from a seed, so that agents can be trained on some
+// seeds and evaluated on held-out ones. Every step costs -1, including the one
+// onto the goal, so the optimal return is minus the length of the shortest
+// path.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MazeGenerator {
+    // Perfect mazes with long winding corridors.
+    RecursiveBacktracker,
+    // Perfect mazes with many short dead ends.
+    Prim,
+    // Open grids where every cell off a random path from the start to the
+    // goal is a wall with the given probability. Pockets the start can't
+    // reach are walled up.
+    RandomObstacles { wall_density: f64 },
+}
+
+impl MazeGenerator {
+    // Perfect mazes put their passages on odd rows and columns, so they need
+    // odd dimensions. The start is in the top left corner and the goal in the
+    // bottom right one.
+    pub fn generate(&self, rows: usize, cols: usize, seed: u64) -> GridWorld {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let tiles = match *self {
+            MazeGenerator::RecursiveBacktracker => {
+                let mut maze = PerfectMaze::new(rows, cols);
+                maze.carve_backtracker(&mut rng);
+                maze.into_tiles()
+            }
+            MazeGenerator::Prim => {
+                let mut maze = PerfectMaze::new(rows, cols);
+                maze.carve_prim(&mut rng);
+                maze.into_tiles()
+            }
+            MazeGenerator::RandomObstacles { wall_density } => {
+                random_obstacles(rows, cols, wall_density, &mut rng)
+            }
+        };
+        GridWorld::new(tiles)
+            .with_step_reward(-1.0)
+            .with_goal_reward(-1.0)
+    }
+}
+
+// Number of steps from the start to the closest goal, ignoring wind and
+// slipping. `None` if no goal can be reached.
+pub fn shortest_path_length(world: &GridWorld) -> Option<usize> {
+    shortest_path_length_in(&world_tiles(world))
+}
+
+fn world_tiles(world: &GridWorld) -> Vec<Vec<Tile>> {
+    (0..world.rows())
+        .map(|row| {
+            (0..world.cols())
+                .map(|col| world.tile((row, col)))
+                .collect()
+        })
+        .collect()
+}
+
+fn neighbours(position: Position, rows: usize, cols: usize, distance: usize) -> Vec<Position> {
+    let (row, col) = position;
+    let mut neighbours = vec![];
+    if row >= distance {
+        neighbours.push((row - distance, col));
+    }
+    if row + distance < rows {
+        neighbours.push((row + distance, col));
+    }
+    if col >= distance {
+        neighbours.push((row, col - distance));
+    }
+    if col + distance < cols {
+        neighbours.push((row, col + distance));
+    }
+    neighbours
+}
+
+// Steps from the start to every cell that can be reached from it, the search
+// doesn't continue past terminal tiles.
+fn distances_from_start(tiles: &[Vec<Tile>]) -> Vec<Vec<Option<usize>>> {
+    let (rows, cols) = (tiles.len(), tiles[0].len());
+    let mut distances = vec![vec![None; cols]; rows];
+    let Some(start) = (0..rows)
+        .flat_map(|row| (0..cols).map(move |col| (row, col)))
+        .find(|&(row, col)| tiles[row][col] == Tile::Start)
+    else {
+        return distances;
+    };
+    distances[start.0][start.1] = Some(0);
+    let mut queue = VecDeque::from([start]);
+    while let Some((row, col)) = queue.pop_front() {
+        if tiles[row][col].is_terminal() {
+            continue;
+        }
+        let distance = distances[row][col].unwrap();
+        for (r, c) in neighbours((row, col), rows, cols, 1) {
+            if tiles[r][c] != Tile::Wall && distances[r][c].is_none() {
+                distances[r][c] = Some(distance + 1);
+                queue.push_back((r, c));
+            }
+        }
+    }
+    distances
+}
+
+fn shortest_path_length_in(tiles: &[Vec<Tile>]) -> Option<usize> {
+    let distances = distances_from_start(tiles);
+    tiles
+        .iter()
+        .flatten()
+        .zip(distances.iter().flatten())
+        .filter(|(&tile, _)| tile == Tile::Goal)
+        .filter_map(|(_, &distance)| distance)
+        .min()
+}
+
+// A grid of walls with cells on odd rows and columns, passages are carved by
+// removing the wall between two neighbouring cells.
+struct PerfectMaze {
+    tiles: Vec<Vec<Tile>>,
+}
+
+impl PerfectMaze {
+    fn new(rows: usize, cols: usize) -> PerfectMaze {
+        if rows < 3 || cols < 3 || rows.is_multiple_of(2) || cols.is_multiple_of(2) {
+            panic!(
+                "mazes need odd dimensions of at least 3, got {}x{}",
+                rows, cols
+            );
+        }
+        let mut tiles = vec![vec![Tile::Wall; cols]; rows];
+        tiles[1][1] = Tile::Start;
+        PerfectMaze { tiles }
+    }
+
+    fn rows(&self) -> usize {
+        self.tiles.len()
+    }
+
+    fn cols(&self) -> usize {
+        self.tiles[0].len()
+    }
+
+    fn is_carved(&self, cell: Position) -> bool {
+        self.tiles[cell.0][cell.1] != Tile::Wall
+    }
+
+    fn uncarved_neighbours(&self, cell: Position) -> Vec<Position> {
+        neighbours(cell, self.rows(), self.cols(), 2)
+            .into_iter()
+            .filter(|&neighbour| !self.is_carved(neighbour))
+            .collect()
+    }
+
+    // Opens `to` and the wall between it and `from`.
+    fn carve(&mut self, from: Position, to: Position) {
+        let wall = ((from.0 + to.0) / 2, (from.1 + to.1) / 2);
+        self.tiles[wall.0][wall.1] = Tile::Empty;
+        self.tiles[to.0][to.1] = Tile::Empty;
+    }
+
+    fn into_tiles(mut self) -> Vec<Vec<Tile>> {
+        let (rows, cols) = (self.rows(), self.cols());
+        self.tiles[rows - 2][cols - 2] = Tile::Goal;
+        self.tiles
+    }
+
+    fn carve_backtracker(&mut self, rng: &mut StdRng) {
+        let mut stack = vec![(1, 1)];
+        while let Some(&cell) = stack.last() {
+            match self.uncarved_neighbours(cell).choose(rng) {
+                Some(&next) => {
+                    self.carve(cell, next);
+                    stack.push(next);
+                }
+                None => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    fn carve_prim(&mut self, rng: &mut StdRng) {
+        let mut frontier: Vec<(Position, Position)> = self
+            .uncarved_neighbours((1, 1))
+            .into_iter()
+            .map(|next| ((1, 1), next))
+            .collect();
+        while !frontier.is_empty() {
+            let (cell, next) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            if self.is_carved(next) {
+                continue;
+            }
+            self.carve(cell, next);
+            frontier.extend(
+                self.uncarved_neighbours(next)
+                    .into_iter()
+                    .map(|neighbour| (next, neighbour)),
+            );
+        }
+    }
+}
+
+fn random_obstacles(
+    rows: usize,
+    cols: usize,
+    wall_density: f64,
+    rng: &mut StdRng,
+) -> Vec<Vec<Tile>> {
+    if rows * cols < 2 {
+        panic!(
+            "random grids need room for a start and a goal, got {}x{}",
+            rows, cols
+        );
+    }
+    if !(0.0..=1.0).contains(&wall_density) {
+        panic!("wall density {} is not in [0, 1]", wall_density);
+    }
+    // A random staircase of steps down and right is kept free, so the goal
+    // can always be reached however dense the walls are.
+    let mut steps: Vec<bool> = [vec![true; rows - 1], vec![false; cols - 1]].concat();
+    steps.shuffle(rng);
+    let mut on_path = vec![vec![false; cols]; rows];
+    let (mut row, mut col) = (0, 0);
+    on_path[row][col] = true;
+    for down in steps {
+        if down {
+            row += 1;
+        } else {
+            col += 1;
+        }
+        on_path[row][col] = true;
+    }
+
+    let mut tiles: Vec<Vec<Tile>> = on_path
+        .iter()
+        .map(|path_row| {
+            path_row
+                .iter()
+                .map(|&on_path| {
+                    if !on_path && rng.gen_bool(wall_density) {
+                        Tile::Wall
+                    } else {
+                        Tile::Empty
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    tiles[0][0] = Tile::Start;
+    tiles[rows - 1][cols - 1] = Tile::Goal;
+    // Walling up the pockets keeps DP from having to deal with states that
+    // never reach the goal.
+    let distances = distances_from_start(&tiles);
+    for (tile_row, distance_row) in tiles.iter_mut().zip(distances.iter()) {
+        for (tile, distance) in tile_row.iter_mut().zip(distance_row.iter()) {
+            if distance.is_none() {
+                *tile = Tile::Wall;
+            }
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dp::{value_iteration, DPConfig};
+    use crate::environment::DPEnvironment;
+    use crate::gridworld::environment::GridWorldEnvironment;
+    use pretty_assertions::assert_eq;
+
+    const GENERATORS: [MazeGenerator; 3] = [
+        MazeGenerator::RecursiveBacktracker,
+        MazeGenerator::Prim,
+        MazeGenerator::RandomObstacles { wall_density: 0.4 },
+    ];
+
+    #[test]
+    fn generation_is_seeded() {
+        for generator in GENERATORS {
+            let maze = generator.generate(9, 11, 3);
+            assert_eq!(generator.generate(9, 11, 3), maze);
+            assert_ne!(generator.generate(9, 11, 4), maze);
+        }
+    }
+
+    #[test]
+    fn perfect_mazes_are_trees() {
+        for generator in [MazeGenerator::RecursiveBacktracker, MazeGenerator::Prim] {
+            let maze = generator.generate(11, 15, 0);
+            // 5x7 cells connected by 34 passages.
+            let open = maze
+                .positions()
+                .filter(|&p| maze.tile(p) != Tile::Wall)
+                .count();
+            assert_eq!(open, 35 + 34, "{}", maze);
+            // The search stops at the goal, so it's opened up to reach the
+            // cells behind it.
+            let mut tiles = world_tiles(&maze);
+            tiles[9][13] = Tile::Empty;
+            let reachable = distances_from_start(&tiles)
+                .iter()
+                .flatten()
+                .filter(|distance| distance.is_some())
+                .count();
+            assert_eq!(reachable, open);
+        }
+    }
+
+    #[test]
+    fn dense_obstacles_leave_a_path() {
+        let generator = MazeGenerator::RandomObstacles { wall_density: 1.0 };
+        for seed in 0..5 {
+            let maze = generator.generate(9, 11, seed);
+            assert_eq!(shortest_path_length(&maze), Some(8 + 10));
+        }
+    }
+
+    #[test]
+    fn optimal_return_is_shortest_path() {
+        for generator in GENERATORS {
+            for seed in 0..5 {
+                let maze = generator.generate(9, 9, seed);
+                let path_length = shortest_path_length(&maze).unwrap();
+                let env = GridWorldEnvironment::new(maze, 0);
+                let values = value_iteration(&env.state_transitions(), &DPConfig::default());
+                assert_eq!(values[&env.state_id()], -(path_length as f64));
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+pub mod action;
+pub mod environment;
+pub mod map;
+pub mod maze;
+pub mod presets;
+pub mod tile;
+pub mod world;
@@ -0,0 +1,79 @@
+use crate::gridworld::tile::Tile;
+use crate::gridworld::world::GridWorld;
+
+fn tiles(rows: &[&str]) -> Vec<Vec<Tile>> {
+    rows.iter()
+        .map(|row| {
+            row.chars()
+                .map(|c| Tile::try_from(c).unwrap_or_else(|e| panic!("bad tile {:?}", e)))
+                .collect()
+        })
+        .collect()
+}
+
+// Example 4.1 of Sutton & Barto: two terminal corners and a reward of -1 for
+// every step, including the last one.
+pub fn sutton_4x4() -> GridWorld {
+    GridWorld::new(tiles(&["G...", "....", "....", "...G"]))
+        .with_step_reward(-1.0)
+        .with_goal_reward(-1.0)
+}
+
+// Example 6.6 of Sutton & Barto: walking into the cliff along the bottom row
+// costs -100 and sends the agent back to the start.
+pub fn cliff_walking() -> GridWorld {
+    GridWorld::new(tiles(&[
+        "............",
+        "............",
+        "............",
+        "SCCCCCCCCCCG",
+    ]))
+    .with_step_reward(-1.0)
+    .with_goal_reward(-1.0)
+    .with_cliff_reward(-100.0)
+}
+
+// Example 6.5 of Sutton & Barto: a crosswind pushes the agent upwards in the
+// middle columns.
+pub fn windy_gridworld() -> GridWorld {
+    GridWorld::new(tiles(&[
+        "..........",
+        "..........",
+        "..........",
+        "S......G..",
+        "..........",
+        "..........",
+        "..........",
+    ]))
+    .with_wind(vec![0, 0, 0, 1, 1, 1, 2, 2, 1, 0])
+    .with_step_reward(-1.0)
+    .with_goal_reward(-1.0)
+}
+
+// FrozenLake: the ice is slippery, so the agent only moves in the chosen
+// direction a third of the time. Only reaching the goal is rewarded.
+pub fn frozen_lake_4x4() -> GridWorld {
+    frozen_lake(&["S...", ".H.H", "...H", "H..G"])
+}
+
+#[rustfmt::skip]
+pub fn frozen_lake_8x8() -> GridWorld {
+    frozen_lake(&[
+        "S.......",
+        "........",
+        "...H....",
+        ".....H..",
+        "...H....",
+        ".HH...H.",
+        ".H..H.H.",
+        "...H...G",
+    ])
+}
+
+fn frozen_lake(rows: &[&str]) -> GridWorld {
+    GridWorld::new(tiles(rows))
+        .with_step_reward(0.0)
+        .with_goal_reward(1.0)
+        .with_hole_reward(0.0)
+        .with_slip_probability(2.0 / 3.0)
+}
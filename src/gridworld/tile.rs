@@ -0,0 +1,88 @@
+use std::char;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Tile {
+    Empty,
+    Wall,
+    Start,
+    // Ends the episode with the goal reward.
+    Goal,
+    // Ends the episode with the hole reward, like in FrozenLake.
+    Hole,
+    // Gives the cliff reward and sends the agent back to the start.
+    Cliff,
+}
+
+impl Tile {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Tile::Goal | Tile::Hole)
+    }
+
+    // Whether an episode can start here in a world without a start tile.
+    pub fn can_start(&self) -> bool {
+        *self != Tile::Wall && !self.is_terminal()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TileConversionError(pub char);
+
+impl TryFrom<char> for Tile {
+    type Error = TileConversionError;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        match c {
+            '.' => Ok(Tile::Empty),
+            '#' => Ok(Tile::Wall),
+            'S' => Ok(Tile::Start),
+            'G' => Ok(Tile::Goal),
+            'H' => Ok(Tile::Hole),
+            'C' => Ok(Tile::Cliff),
+            _ => Err(TileConversionError(c)),
+        }
+    }
+}
+
+impl From<Tile> for char {
+    fn from(tile: Tile) -> char {
+        match tile {
+            Tile::Empty => '.',
+            Tile::Wall => '#',
+            Tile::Start => 'S',
+            Tile::Goal => 'G',
+            Tile::Hole => 'H',
+            Tile::Cliff => 'C',
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn conversion_from_char() {
+        let chars = ['.', '#', 'S', 'G', 'H', 'C'];
+        let tiles: Vec<Tile> = chars.map(|c| Tile::try_from(c).unwrap()).to_vec();
+        assert_eq!(
+            tiles,
+            [
+                Tile::Empty,
+                Tile::Wall,
+                Tile::Start,
+                Tile::Goal,
+                Tile::Hole,
+                Tile::Cliff
+            ]
+        );
+
+        let serialized_chars: Vec<char> = tiles.iter().map(|t| char::from(*t)).collect();
+        assert_eq!(serialized_chars, chars);
+    }
+
+    #[test]
+    fn incorrect_conversion_from_char() {
+        assert!(Tile::try_from('x').is_err());
+    }
+}
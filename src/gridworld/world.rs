@@ -0,0 +1,295 @@
+use crate::environment::StateId;
+use crate::gridworld::action::GridAction;
+use crate::gridworld::tile::Tile;
+
+pub type Position = (usize, usize);
+
+// Where a step can take the agent and with what probability.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Outcome {
+    pub position: Position,
+    pub reward: f64,
+    pub prob: f64,
+}
+
+// The layout and dynamics of a gridworld. Every step yields the step reward,
+// unless it ends on a goal, hole or cliff tile which give their own reward.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridWorld {
+    // Rows from top to bottom, columns from left to right.
+    tiles: Vec<Vec<Tile>>,
+    // Upward push of every column, applied after the move in the column the
+    // agent started from.
+    wind: Vec<usize>,
+    step_reward: f64,
+    goal_reward: f64,
+    hole_reward: f64,
+    cliff_reward: f64,
+    // Probability of moving perpendicular to the chosen direction instead,
+    // split evenly between both sides.
+    slip_prob: f64,
+}
+
+impl GridWorld {
+    pub fn new(tiles: Vec<Vec<Tile>>) -> GridWorld {
+        if tiles.is_empty() || tiles[0].is_empty() {
+            panic!("a gridworld needs at least one cell");
+        }
+        if tiles.iter().any(|row| row.len() != tiles[0].len()) {
+            panic!("all rows of a gridworld need to have the same length");
+        }
+        let num_starts = tiles
+            .iter()
+            .flatten()
+            .filter(|&&t| t == Tile::Start)
+            .count();
+        if num_starts > 1 {
+            panic!("a gridworld can have at most one start, got {}", num_starts);
+        }
+        if num_starts == 0 && tiles.iter().flatten().any(|&t| t == Tile::Cliff) {
+            panic!("cliffs send the agent back to the start, which is missing");
+        }
+        if num_starts == 0 && !tiles.iter().flatten().any(|&t| t.can_start()) {
+            panic!("a gridworld without a start needs a cell to start on");
+        }
+        let cols = tiles[0].len();
+        GridWorld {
+            tiles,
+            wind: vec![0; cols],
+            step_reward: -1.0,
+            goal_reward: 0.0,
+            hole_reward: 0.0,
+            cliff_reward: -100.0,
+            slip_prob: 0.0,
+        }
+    }
+
+    pub fn with_wind(mut self, wind: Vec<usize>) -> Self {
+        if wind.len() != self.cols() {
+            panic!(
+                "expected wind for {} columns, got {}",
+                self.cols(),
+                wind.len()
+            );
+        }
+        self.wind = wind;
+        self
+    }
+
+    pub fn with_step_reward(mut self, reward: f64) -> Self {
+        self.step_reward = reward;
+        self
+    }
+
+    pub fn with_goal_reward(mut self, reward: f64) -> Self {
+        self.goal_reward = reward;
+        self
+    }
+
+    pub fn with_hole_reward(mut self, reward: f64) -> Self {
+        self.hole_reward = reward;
+        self
+    }
+
+    pub fn with_cliff_reward(mut self, reward: f64) -> Self {
+        self.cliff_reward = reward;
+        self
+    }
+
+    pub fn with_slip_probability(mut self, prob: f64) -> Self {
+        if !(0.0..=1.0).contains(&prob) {
+            panic!("slip probability {} is not a probability", prob);
+        }
+        self.slip_prob = prob;
+        self
+    }
+
+    pub fn rows(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn cols(&self) -> usize {
+        self.tiles[0].len()
+    }
+
+    pub fn tile(&self, position: Position) -> Tile {
+        self.tiles[position.0][position.1]
+    }
+
+    pub fn wind(&self) -> &[usize] {
+        &self.wind
+    }
+
+    pub fn step_reward(&self) -> f64 {
+        self.step_reward
+    }
+
+    pub fn goal_reward(&self) -> f64 {
+        self.goal_reward
+    }
+
+    pub fn hole_reward(&self) -> f64 {
+        self.hole_reward
+    }
+
+    pub fn cliff_reward(&self) -> f64 {
+        self.cliff_reward
+    }
+
+    pub fn slip_probability(&self) -> f64 {
+        self.slip_prob
+    }
+
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.rows()).flat_map(move |row| (0..self.cols()).map(move |col| (row, col)))
+    }
+
+    // The start tile, or every cell an agent can stand on without the episode
+    // being over if the world has no start tile.
+    pub fn start_positions(&self) -> Vec<Position> {
+        let starts: Vec<Position> = self
+            .positions()
+            .filter(|&p| self.tile(p) == Tile::Start)
+            .collect();
+        if !starts.is_empty() {
+            return starts;
+        }
+        self.positions()
+            .filter(|&p| self.tile(p).can_start())
+            .collect()
+    }
+
+    pub fn state_id(&self, position: Position) -> StateId {
+        StateId(position.0 * self.cols() + position.1)
+    }
+
+    pub fn position_with_id(&self, state_id: StateId) -> Position {
+        (state_id.0 / self.cols(), state_id.0 % self.cols())
+    }
+
+    // Moves by `offset` unless that leaves the grid or runs into a wall.
+    fn step(&self, position: Position, offset: (isize, isize)) -> Position {
+        let row = position.0 as isize + offset.0;
+        let col = position.1 as isize + offset.1;
+        if row < 0 || col < 0 || row >= self.rows() as isize || col >= self.cols() as isize {
+            return position;
+        }
+        let new_position = (row as usize, col as usize);
+        if self.tile(new_position) == Tile::Wall {
+            return position;
+        }
+        new_position
+    }
+
+    fn deterministic_move(&self, position: Position, action: GridAction) -> Outcome {
+        let mut new_position = self.step(position, action.offset());
+        for _ in 0..self.wind[position.1] {
+            new_position = self.step(new_position, (-1, 0));
+        }
+        let (new_position, reward) = match self.tile(new_position) {
+            Tile::Goal => (new_position, self.goal_reward),
+            Tile::Hole => (new_position, self.hole_reward),
+            Tile::Cliff => (self.start_positions()[0], self.cliff_reward),
+            _ => (new_position, self.step_reward),
+        };
+        Outcome {
+            position: new_position,
+            reward,
+            prob: 1.0,
+        }
+    }
+
+    // All possible results of taking `action` at `position`, outcomes with
+    // the same position and reward are merged.
+    pub fn outcomes(&self, position: Position, action: GridAction) -> Vec<Outcome> {
+        let mut moves = vec![(action, 1.0 - self.slip_prob)];
+        for slip_action in action.perpendicular() {
+            moves.push((slip_action, self.slip_prob / 2.0));
+        }
+
+        let mut outcomes: Vec<Outcome> = vec![];
+        for (action, prob) in moves {
+            if prob == 0.0 {
+                continue;
+            }
+            let outcome = self.deterministic_move(position, action);
+            match outcomes
+                .iter_mut()
+                .find(|o| o.position == outcome.position && o.reward == outcome.reward)
+            {
+                Some(existing) => existing.prob += prob,
+                None => outcomes.push(Outcome { prob, ..outcome }),
+            }
+        }
+        outcomes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gridworld::presets;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn walls_and_edges_block_moves() {
+        let world = presets::frozen_lake_4x4().with_slip_probability(0.0);
+        let outcomes = world.outcomes((0, 0), GridAction::Up);
+        assert_eq!(
+            outcomes,
+            vec![Outcome {
+                position: (0, 0),
+                reward: 0.0,
+                prob: 1.0
+            }]
+        );
+    }
+
+    #[test]
+    fn slipping_is_split_between_sides() {
+        let world = presets::frozen_lake_4x4();
+        let outcomes = world.outcomes((0, 1), GridAction::Down);
+        let positions: Vec<Position> = outcomes.iter().map(|o| o.position).collect();
+        assert_eq!(positions, vec![(1, 1), (0, 0), (0, 2)]);
+        for outcome in outcomes {
+            assert!((outcome.prob - 1.0 / 3.0).abs() < 1e-12);
+        }
+        // The hole at (1, 1) gives its own reward.
+        let world = world.with_hole_reward(-1.0);
+        assert_eq!(world.outcomes((0, 1), GridAction::Down)[0].reward, -1.0);
+    }
+
+    #[test]
+    fn wind_pushes_upwards() {
+        let world = presets::windy_gridworld();
+        // Column 6 has a wind of 2.
+        let outcomes = world.outcomes((3, 6), GridAction::Right);
+        assert_eq!(outcomes[0].position, (1, 7));
+        // The wind stops at the top edge.
+        let outcomes = world.outcomes((0, 6), GridAction::Left);
+        assert_eq!(outcomes[0].position, (0, 5));
+    }
+
+    #[test]
+    fn cliff_sends_back_to_start() {
+        let world = presets::cliff_walking();
+        let outcomes = world.outcomes((2, 5), GridAction::Down);
+        assert_eq!(
+            outcomes,
+            vec![Outcome {
+                position: (3, 0),
+                reward: -100.0,
+                prob: 1.0
+            }]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "needs a cell to start on")]
+    fn worlds_need_somewhere_to_start() {
+        GridWorld::new(vec![
+            vec![Tile::Wall, Tile::Goal],
+            vec![Tile::Hole, Tile::Wall],
+        ]);
+    }
+}
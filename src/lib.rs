@@ -0,0 +1,24 @@
+pub mod afterstate;
+pub mod agent;
+pub mod average_reward;
+pub mod bandit;
+pub mod benchmarks;
+pub mod blackjack;
+pub mod cfr;
+pub mod connect4;
+pub mod constrained;
+pub mod control;
+pub mod distributions;
+pub mod dp;
+pub mod environment;
+pub mod extensive_form;
+pub mod gridworld;
+pub mod lp;
+pub mod markov_game;
+pub mod matrix_game;
+pub mod multi_objective;
+pub mod poker;
+pub mod selfplay;
+pub mod soft;
+pub mod space;
+pub mod tictactoe;
@@ -0,0 +1,465 @@
+// A small dense linear program solver: the two-phase simplex method on a
+// full tableau. It's meant for the games and MDPs of this crate, with up to
+// a few thousand variables, not as a general LP library.
+use std::fmt;
+
+const EPSILON: f64 = 1e-9;
+// Entries this much smaller than the largest one of their column are
+// treated as rounding errors by the ratio test.
+const PIVOT_TOLERANCE: f64 = 1e-7;
+// Degenerate pivots in a row before switching to Bland's rule. Occupancy
+// measures have a zero right-hand side for all but the start states, so
+// long degenerate stretches are normal, and Bland's rule can take ages to
+// get through them.
+const MAX_STALLED_PIVOTS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    LessEqual,
+    Equal,
+    GreaterEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LpError {
+    Infeasible,
+    Unbounded,
+}
+
+impl fmt::Display for LpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LpError::Infeasible => f.write_str("the linear program has no feasible solution"),
+            LpError::Unbounded => f.write_str("the linear program is unbounded"),
+        }
+    }
+}
+
+impl std::error::Error for LpError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LpSolution {
+    pub value: f64,
+    pub variables: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    coefficients: Vec<f64>,
+    relation: Relation,
+    rhs: f64,
+}
+
+// Optimizes a linear objective subject to linear constraints. Variables are
+// non-negative unless they are marked as free.
+#[derive(Debug, Clone)]
+pub struct LinearProgram {
+    objective: Vec<f64>,
+    maximize: bool,
+    constraints: Vec<Constraint>,
+    free: Vec<bool>,
+}
+
+impl LinearProgram {
+    pub fn maximize(objective: Vec<f64>) -> Self {
+        LinearProgram::new(objective, true)
+    }
+
+    pub fn minimize(objective: Vec<f64>) -> Self {
+        LinearProgram::new(objective, false)
+    }
+
+    fn new(objective: Vec<f64>, maximize: bool) -> Self {
+        if objective.is_empty() {
+            panic!("a linear program needs at least one variable");
+        }
+        let num_variables = objective.len();
+        LinearProgram {
+            objective,
+            maximize,
+            constraints: vec![],
+            free: vec![false; num_variables],
+        }
+    }
+
+    pub fn num_variables(&self) -> usize {
+        self.objective.len()
+    }
+
+    pub fn with_constraint(mut self, coefficients: Vec<f64>, relation: Relation, rhs: f64) -> Self {
+        self.add_constraint(coefficients, relation, rhs);
+        self
+    }
+
+    pub fn add_constraint(&mut self, coefficients: Vec<f64>, relation: Relation, rhs: f64) {
+        if coefficients.len() != self.num_variables() {
+            panic!(
+                "expected {} coefficients, got {}",
+                self.num_variables(),
+                coefficients.len()
+            );
+        }
+        self.constraints.push(Constraint {
+            coefficients,
+            relation,
+            rhs,
+        });
+    }
+
+    // Lets variable `index` take negative values too.
+    pub fn with_free_variable(mut self, index: usize) -> Self {
+        self.free[index] = true;
+        self
+    }
+
+    pub fn solve(&self) -> Result<LpSolution, LpError> {
+        // Free variables are split into a positive and a negative part.
+        let mut columns = vec![];
+        for (i, free) in self.free.iter().enumerate() {
+            columns.push((i, 1.0));
+            if *free {
+                columns.push((i, -1.0));
+            }
+        }
+        let num_structural = columns.len();
+        let num_slacks = self
+            .constraints
+            .iter()
+            .filter(|c| c.relation != Relation::Equal)
+            .count();
+        let num_artificials = self
+            .constraints
+            .iter()
+            .filter(|c| c.relation != Relation::LessEqual || c.rhs < 0.0)
+            .count();
+        let first_artificial = num_structural + num_slacks;
+        let width = first_artificial + num_artificials;
+
+        let mut tableau = Tableau {
+            rows: vec![],
+            basis: vec![],
+        };
+        let (mut slack, mut artificial) = (num_structural, first_artificial);
+        for constraint in self.constraints.iter() {
+            let mut row = vec![0.0; width + 1];
+            for (j, (variable, sign)) in columns.iter().enumerate() {
+                row[j] = sign * constraint.coefficients[*variable];
+            }
+            match constraint.relation {
+                Relation::LessEqual => row[slack] = 1.0,
+                Relation::GreaterEqual => row[slack] = -1.0,
+                Relation::Equal => {}
+            }
+            row[width] = constraint.rhs;
+            // Right-hand sides have to be non-negative for the initial basis.
+            if constraint.rhs < 0.0 {
+                row.iter_mut().for_each(|value| *value = -*value);
+            }
+            let basic = if constraint.relation == Relation::LessEqual && constraint.rhs >= 0.0 {
+                slack
+            } else {
+                row[artificial] = 1.0;
+                artificial += 1;
+                artificial - 1
+            };
+            if constraint.relation != Relation::Equal {
+                slack += 1;
+            }
+            tableau.rows.push(row);
+            tableau.basis.push(basic);
+        }
+
+        // Phase one minimizes the artificial variables to find a feasible
+        // basis.
+        let mut phase_one = vec![0.0; width];
+        for cost in phase_one[first_artificial..].iter_mut() {
+            *cost = -1.0;
+        }
+        tableau.optimize(&phase_one, width)?;
+        if tableau.objective_value(&phase_one) < -EPSILON * (1.0 + self.scale()) {
+            return Err(LpError::Infeasible);
+        }
+        tableau.drive_out_artificials(first_artificial);
+
+        let sign = if self.maximize { 1.0 } else { -1.0 };
+        let mut objective = vec![0.0; width];
+        for (j, (variable, column_sign)) in columns.iter().enumerate() {
+            objective[j] = sign * column_sign * self.objective[*variable];
+        }
+        tableau.optimize(&objective, first_artificial)?;
+
+        let mut variables = vec![0.0; self.num_variables()];
+        for (row, basic) in tableau.basis.iter().enumerate() {
+            if *basic < num_structural {
+                let (variable, column_sign) = columns[*basic];
+                variables[variable] += column_sign * tableau.rows[row][width];
+            }
+        }
+        let value = self
+            .objective
+            .iter()
+            .zip(&variables)
+            .map(|(c, x)| c * x)
+            .sum();
+        Ok(LpSolution { value, variables })
+    }
+
+    fn scale(&self) -> f64 {
+        self.constraints
+            .iter()
+            .map(|c| c.rhs.abs())
+            .fold(0.0, f64::max)
+    }
+}
+
+// Every row is a constraint with its right-hand side in the last column,
+// `basis` holds the basic variable of every row.
+struct Tableau {
+    rows: Vec<Vec<f64>>,
+    basis: Vec<usize>,
+}
+
+impl Tableau {
+    fn width(&self) -> usize {
+        self.rows.first().map_or(0, |row| row.len() - 1)
+    }
+
+    fn objective_value(&self, objective: &[f64]) -> f64 {
+        self.basis
+            .iter()
+            .enumerate()
+            .map(|(row, basic)| objective[*basic] * self.rows[row][self.width()])
+            .sum()
+    }
+
+    // Maximizes `objective`, only variables before `allowed` may enter the
+    // basis. The entering variable has the largest reduced cost, which
+    // takes few pivots, until the objective stalls on degenerate pivots.
+    // From then on Bland's rule picks the first improving variable and the
+    // leaving row with the lowest basic variable, which can't cycle.
+    fn optimize(&mut self, objective: &[f64], allowed: usize) -> Result<(), LpError> {
+        self.optimize_with_patience(objective, allowed, MAX_STALLED_PIVOTS)
+    }
+
+    // `optimize` that falls back to Bland's rule after `patience` degenerate
+    // pivots in a row.
+    fn optimize_with_patience(
+        &mut self,
+        objective: &[f64],
+        allowed: usize,
+        patience: usize,
+    ) -> Result<(), LpError> {
+        let width = self.width();
+        // The reduced costs with the negated objective value in the last
+        // column, updated by every pivot like a row.
+        let mut reduced = objective.to_vec();
+        reduced.push(0.0);
+        for (row, basic) in self.basis.iter().enumerate() {
+            let cost = objective[*basic];
+            if cost != 0.0 {
+                for (value, row_value) in reduced.iter_mut().zip(&self.rows[row]) {
+                    *value -= cost * row_value;
+                }
+            }
+        }
+        let mut stalled = 0;
+        loop {
+            let improving = (0..allowed).filter(|&j| reduced[j] > EPSILON);
+            let entering = if stalled >= patience {
+                improving.min()
+            } else {
+                improving.max_by(|&a, &b| reduced[a].total_cmp(&reduced[b]).then(b.cmp(&a)))
+            };
+            let Some(entering) = entering else {
+                return Ok(());
+            };
+            let bland = stalled >= patience;
+            let Some(leaving) = self.leaving_row(entering, bland) else {
+                return Err(LpError::Unbounded);
+            };
+            if self.rows[leaving][width] > EPSILON {
+                stalled = 0;
+            } else {
+                stalled += 1;
+            }
+            self.pivot(leaving, entering);
+            let factor = reduced[entering];
+            for (value, pivot_value) in reduced.iter_mut().zip(&self.rows[leaving]) {
+                *value -= factor * pivot_value;
+            }
+        }
+    }
+
+    // The ratio test: the row that limits the entering variable first. Of
+    // rows that limit it about equally, the one with the largest pivot is
+    // numerically safest, while Bland's rule takes the lowest basic variable.
+    // Pivoting on tiny entries left over from rounding would blow up the
+    // rest of the tableau, so they don't limit the entering variable.
+    fn leaving_row(&self, entering: usize, bland: bool) -> Option<usize> {
+        let width = self.width();
+        let largest = self
+            .rows
+            .iter()
+            .map(|row| row[entering])
+            .fold(0.0, f64::max);
+        let min_pivot = EPSILON.max(PIVOT_TOLERANCE * largest);
+        let candidates: Vec<usize> = (0..self.rows.len())
+            .filter(|&row| self.rows[row][entering] > min_pivot)
+            .collect();
+        let ratio = |row: usize| self.rows[row][width].max(0.0) / self.rows[row][entering];
+        let min_ratio = candidates
+            .iter()
+            .map(|&row| ratio(row))
+            .fold(f64::INFINITY, f64::min);
+        let tied = candidates
+            .into_iter()
+            .filter(|&row| ratio(row) <= min_ratio + EPSILON);
+        if bland {
+            return tied.min_by_key(|&row| self.basis[row]);
+        }
+        tied.max_by(|&a, &b| {
+            let pivot_a = self.rows[a][entering];
+            let pivot_b = self.rows[b][entering];
+            pivot_a
+                .total_cmp(&pivot_b)
+                .then(self.basis[b].cmp(&self.basis[a]))
+        })
+    }
+
+    fn pivot(&mut self, pivot_row: usize, column: usize) {
+        let pivot = self.rows[pivot_row][column];
+        self.rows[pivot_row].iter_mut().for_each(|v| *v /= pivot);
+        let pivot_values = std::mem::take(&mut self.rows[pivot_row]);
+        for values in self.rows.iter_mut() {
+            let factor = values.get(column).copied().unwrap_or(0.0);
+            if factor == 0.0 {
+                continue;
+            }
+            for (value, pivot_value) in values.iter_mut().zip(&pivot_values) {
+                *value -= factor * pivot_value;
+            }
+        }
+        self.rows[pivot_row] = pivot_values;
+        self.basis[pivot_row] = column;
+    }
+
+    // Artificial variables left in the basis at zero are swapped for other
+    // variables. Rows where that's impossible are redundant and dropped.
+    fn drive_out_artificials(&mut self, first_artificial: usize) {
+        let mut row = 0;
+        while row < self.rows.len() {
+            if self.basis[row] >= first_artificial {
+                let column = (0..first_artificial).find(|&j| self.rows[row][j].abs() > EPSILON);
+                match column {
+                    Some(column) => self.pivot(row, column),
+                    None => {
+                        self.rows.remove(row);
+                        self.basis.remove(row);
+                        continue;
+                    }
+                }
+            }
+            row += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn assert_close(actual: &[f64], expected: &[f64]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-9, "{:?} {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn maximization() {
+        // max 3x + 5y s.t. x <= 4, 2y <= 12, 3x + 2y <= 18.
+        let solution = LinearProgram::maximize(vec![3.0, 5.0])
+            .with_constraint(vec![1.0, 0.0], Relation::LessEqual, 4.0)
+            .with_constraint(vec![0.0, 2.0], Relation::LessEqual, 12.0)
+            .with_constraint(vec![3.0, 2.0], Relation::LessEqual, 18.0)
+            .solve()
+            .unwrap();
+        assert!((solution.value - 36.0).abs() < 1e-9);
+        assert_close(&solution.variables, &[2.0, 6.0]);
+    }
+
+    #[test]
+    fn minimization_with_equalities() {
+        // min x + 2y + 3z s.t. x + y + z = 10, y >= 2, z - x >= 1.
+        let solution = LinearProgram::minimize(vec![1.0, 2.0, 3.0])
+            .with_constraint(vec![1.0, 1.0, 1.0], Relation::Equal, 10.0)
+            .with_constraint(vec![0.0, 1.0, 0.0], Relation::GreaterEqual, 2.0)
+            .with_constraint(vec![-1.0, 0.0, 1.0], Relation::GreaterEqual, 1.0)
+            .solve()
+            .unwrap();
+        assert_close(&solution.variables, &[3.5, 2.0, 4.5]);
+        assert!((solution.value - 21.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn free_variables() {
+        // min x s.t. x >= -3, with x free.
+        let solution = LinearProgram::minimize(vec![1.0])
+            .with_constraint(vec![1.0], Relation::GreaterEqual, -3.0)
+            .with_free_variable(0)
+            .solve()
+            .unwrap();
+        assert_close(&solution.variables, &[-3.0]);
+    }
+
+    #[test]
+    fn redundant_constraints() {
+        let solution = LinearProgram::maximize(vec![1.0, 1.0])
+            .with_constraint(vec![1.0, 1.0], Relation::Equal, 1.0)
+            .with_constraint(vec![2.0, 2.0], Relation::Equal, 2.0)
+            .solve()
+            .unwrap();
+        assert!((solution.value - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn degenerate_programs_terminate() {
+        // Beale's example, which cycles with the textbook pivoting rules.
+        let solution = LinearProgram::maximize(vec![0.75, -20.0, 0.5, -6.0])
+            .with_constraint(vec![0.25, -8.0, -1.0, 9.0], Relation::LessEqual, 0.0)
+            .with_constraint(vec![0.5, -12.0, -0.5, 3.0], Relation::LessEqual, 0.0)
+            .with_constraint(vec![0.0, 0.0, 1.0, 0.0], Relation::LessEqual, 1.0)
+            .solve()
+            .unwrap();
+        assert!((solution.value - 1.25).abs() < 1e-9);
+        assert!((solution.variables[0] - 1.0).abs() < 1e-9);
+        assert!((solution.variables[2] - 1.0).abs() < 1e-9);
+
+        // Bland's rule from the first pivot on, with the slacks as basis.
+        let mut tableau = Tableau {
+            rows: vec![
+                vec![0.25, -8.0, -1.0, 9.0, 1.0, 0.0, 0.0, 0.0],
+                vec![0.5, -12.0, -0.5, 3.0, 0.0, 1.0, 0.0, 0.0],
+                vec![0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 1.0],
+            ],
+            basis: vec![4, 5, 6],
+        };
+        let objective = [0.75, -20.0, 0.5, -6.0, 0.0, 0.0, 0.0];
+        tableau.optimize_with_patience(&objective, 7, 0).unwrap();
+        assert!((tableau.objective_value(&objective) - 1.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn errors() {
+        let infeasible = LinearProgram::maximize(vec![1.0])
+            .with_constraint(vec![1.0], Relation::LessEqual, 1.0)
+            .with_constraint(vec![1.0], Relation::GreaterEqual, 2.0)
+            .solve();
+        assert_eq!(infeasible, Err(LpError::Infeasible));
+        let unbounded = LinearProgram::maximize(vec![1.0, -1.0])
+            .with_constraint(vec![-1.0, 1.0], Relation::LessEqual, 1.0)
+            .solve();
+        assert_eq!(unbounded, Err(LpError::Unbounded));
+    }
+}
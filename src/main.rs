@@ -1,9 +1,13 @@
-pub mod environment;
-pub mod tictactoe;
-
-use crate::environment::{DPEnvironment, Environment, State, StateId};
-use crate::tictactoe::environment::TicTacToeEnvironment;
 use rand::seq::SliceRandom;
+use rustrl::afterstate::AfterstateAgent;
+use rustrl::agent::RandomAgent;
+use rustrl::dp::{greedy_policy, value_iteration, DPConfig};
+use rustrl::environment::{DPEnvironment, Environment, State, StateId};
+use rustrl::gridworld::action::GridAction;
+use rustrl::gridworld::environment::GridWorldEnvironment;
+use rustrl::gridworld::world::GridWorld;
+use rustrl::selfplay::{SelfPlay, SelfPlayConfig};
+use rustrl::tictactoe::environment::TicTacToeEnvironment;
 use std::env;
 
 fn main() {
@@ -28,6 +32,40 @@ fn main() {
     } else if args[1] == "dp" {
         let transition_table = ttt.state_transitions();
         println!("{:#?}", transition_table[&StateId(0)]);
+    } else if args[1] == "selfplay" {
+        let mut self_play: SelfPlay<TicTacToeEnvironment, AfterstateAgent> =
+            SelfPlay::new(AfterstateAgent::new(0.2, 0.1, 0), SelfPlayConfig::default());
+        self_play.add_opponent("random", Box::new(RandomAgent::new(0)));
+        for evaluation in self_play.train() {
+            println!(
+                "episode {}: {:?} against {}",
+                evaluation.episode, evaluation.result, evaluation.opponent
+            );
+        }
+    } else if args[1] == "gridworld" {
+        let path = args.get(2).expect("please provide the path of a map");
+        let world = GridWorld::read_map(path).unwrap_or_else(|e| panic!("{e}"));
+        let env = GridWorldEnvironment::new(world, 0);
+        let table = env.state_transitions();
+        let config = DPConfig::default();
+        let values = value_iteration(&table, &config);
+        let policy = greedy_policy(&table, &values, config.discount);
+        let world = env.world();
+        for row in 0..world.rows() {
+            let line: String = (0..world.cols())
+                .map(|col| match policy.get(&world.state_id((row, col))) {
+                    Some(&action_id) => match GridAction::action_with_id(action_id) {
+                        GridAction::Up => '^',
+                        GridAction::Down => 'v',
+                        GridAction::Left => '<',
+                        GridAction::Right => '>',
+                    },
+                    None => char::from(world.tile((row, col))),
+                })
+                .collect();
+            println!("{line}");
+        }
+        println!("start value: {:?}", values[&env.state_id()]);
     } else {
         panic!("unexpected arguments provided: {args:?}")
     }
@@ -0,0 +1,351 @@
+// Solution methods for two-player zero-sum Markov games with simultaneous
+// moves. Every state is a matrix game whose payoffs are the joint action
+// values, so where MDP methods maximize over actions these solve that matrix
+// game for the maximin mixed strategy and its value.
+use crate::dp::{DPConfig, LearningRate, ValueTable};
+use crate::environment::{ActionId, JointStateTransition, Player, RewardT, StateId, StateKey};
+use crate::matrix_game::game::maximin;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+pub type JointTransitionTable<K = usize> = HashMap<StateId<K>, Vec<JointStateTransition<K>>>;
+// The probabilities of the actions of every state, indexed by `ActionId`.
+pub type MixedPolicy<K = usize> = HashMap<StateId<K>, Vec<f64>>;
+
+// The expected returns of all joint actions from the first player's
+// perspective, indexed by the first and then the second player's action.
+// Joint actions without transitions are worth zero. States missing from
+// `values` are worth zero.
+pub fn joint_action_values<K: StateKey>(
+    transitions: &[JointStateTransition<K>],
+    values: &ValueTable<K>,
+    discount: f64,
+) -> Vec<Vec<f64>> {
+    let rows = transitions
+        .iter()
+        .map(|t| t.action_ids[0].0 + 1)
+        .max()
+        .unwrap_or(0);
+    let cols = transitions
+        .iter()
+        .map(|t| t.action_ids[1].0 + 1)
+        .max()
+        .unwrap_or(0);
+    let mut action_values = vec![vec![0.0; cols]; rows];
+    for transition in transitions.iter() {
+        let next_value = values.get(&transition.new_state_id).unwrap_or(&0.0);
+        let [first, second] = transition.action_ids;
+        action_values[first.0][second.0] +=
+            transition.prob.0 * (transition.reward.0 + discount * next_value);
+    }
+    action_values
+}
+
+// Shapley's value iteration for zero-sum Markov games: every sweep replaces
+// the value of a state with the value of its matrix game. The values are the
+// first player's.
+pub fn minimax_value_iteration<K: StateKey>(
+    table: &JointTransitionTable<K>,
+    config: &DPConfig,
+) -> ValueTable<K> {
+    let mut values: ValueTable<K> = table.keys().map(|id| (id.clone(), 0.0)).collect();
+    for _ in 0..config.max_sweeps {
+        let mut max_delta: f64 = 0.0;
+        for (state_id, transitions) in table.iter() {
+            if transitions.is_empty() {
+                continue;
+            }
+            let (_, new_value) =
+                maximin(&joint_action_values(transitions, &values, config.discount));
+            let old_value = values.insert(state_id.clone(), new_value).unwrap_or(0.0);
+            max_delta = max_delta.max((new_value - old_value).abs());
+        }
+        if max_delta <= config.theta {
+            break;
+        }
+    }
+    values
+}
+
+// The maximin strategies of `player` for the matrix games of all
+// non-terminal states.
+pub fn minimax_policy<K: StateKey>(
+    table: &JointTransitionTable<K>,
+    values: &ValueTable<K>,
+    discount: f64,
+    player: Player,
+) -> MixedPolicy<K> {
+    table
+        .iter()
+        .filter(|(_, transitions)| !transitions.is_empty())
+        .map(|(state_id, transitions)| {
+            let action_values = joint_action_values(transitions, values, discount);
+            let payoffs = match player {
+                Player::First => action_values,
+                Player::Second => transpose_negated(&action_values),
+            };
+            (state_id.clone(), maximin(&payoffs).0)
+        })
+        .collect()
+}
+
+fn transpose_negated(matrix: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let cols = matrix.first().map_or(0, |row| row.len());
+    (0..cols)
+        .map(|col| matrix.iter().map(|row| -row[col]).collect())
+        .collect()
+}
+
+// Minimax-Q (Littman, 1994): Q-learning over the joint actions of both
+// players, where the value of the next state is the value of its matrix game
+// instead of the best action value. It learns the maximin strategy of its
+// player regardless of how the opponent plays, as long as all joint actions
+// keep being tried. Every state has `num_actions` actions for both players,
+// indexed by `Player::index`.
+#[derive(Debug)]
+pub struct MinimaxQ<K: StateKey = usize> {
+    player: Player,
+    num_actions: [usize; 2],
+    discount: f64,
+    learning_rate: LearningRate,
+    exploration: f64,
+    // The value of all joint actions and states before they are learned.
+    initial_value: f64,
+    // Indexed by the player's own action and then the opponent's, from the
+    // player's perspective.
+    q_values: HashMap<StateId<K>, Vec<Vec<f64>>>,
+    policies: MixedPolicy<K>,
+    values: ValueTable<K>,
+    rng: StdRng,
+}
+
+impl<K: StateKey> MinimaxQ<K> {
+    pub fn new(player: Player, num_actions: [usize; 2], discount: f64, seed: u64) -> Self {
+        if num_actions.contains(&0) {
+            panic!("both players need at least one action");
+        }
+        MinimaxQ {
+            player,
+            num_actions,
+            discount,
+            learning_rate: LearningRate::new(1.0, 0.9999),
+            exploration: 0.2,
+            initial_value: 1.0,
+            q_values: HashMap::new(),
+            policies: HashMap::new(),
+            values: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn with_learning_rate(mut self, learning_rate: f64, decay: f64) -> Self {
+        self.learning_rate = LearningRate::new(learning_rate, decay);
+        self
+    }
+
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    // Optimistic initial values, like the default of 1 from the paper for
+    // rewards of +-1, keep the player trying out actions it knows little
+    // about.
+    pub fn with_initial_value(mut self, initial_value: f64) -> Self {
+        self.initial_value = initial_value;
+        self
+    }
+
+    pub fn player(&self) -> Player {
+        self.player
+    }
+
+    // The current maximin strategy in `state_id`, uniform for unseen states.
+    pub fn policy(&self, state_id: &StateId<K>) -> Vec<f64> {
+        let own = self.num_actions[self.player.index()];
+        self.policies
+            .get(state_id)
+            .cloned()
+            .unwrap_or_else(|| vec![1.0 / own as f64; own])
+    }
+
+    // The value of `state_id` from the player's perspective.
+    pub fn value(&self, state_id: &StateId<K>) -> f64 {
+        *self.values.get(state_id).unwrap_or(&self.initial_value)
+    }
+
+    // Explores uniformly with probability `exploration`, otherwise samples
+    // from the policy.
+    pub fn select_action(&mut self, state_id: &StateId<K>) -> ActionId {
+        let own = self.num_actions[self.player.index()];
+        if self.rng.gen::<f64>() < self.exploration {
+            return ActionId(self.rng.gen_range(0..own));
+        }
+        let policy = self.policy(state_id);
+        let mut sample: f64 = self.rng.gen();
+        for (action, prob) in policy.iter().enumerate() {
+            if sample < *prob {
+                return ActionId(action);
+            }
+            sample -= prob;
+        }
+        ActionId(own - 1)
+    }
+
+    // Learns from a step with the joint action `action_ids`, indexed by
+    // `Player::index`. `reward` is the first player's, like in the transition
+    // tables. `next_state_id` is `None` once the game is over.
+    pub fn learn(
+        &mut self,
+        state_id: &StateId<K>,
+        action_ids: [ActionId; 2],
+        reward: RewardT,
+        next_state_id: Option<&StateId<K>>,
+    ) {
+        let own = action_ids[self.player.index()].0;
+        let other = action_ids[self.player.opponent().index()].0;
+        let next_value = next_state_id.map_or(0.0, |id| self.value(id));
+        let target = self.player.perspective(reward).0 + self.discount * next_value;
+        let learning_rate = self.learning_rate.step();
+        let (rows, cols) = (
+            self.num_actions[self.player.index()],
+            self.num_actions[self.player.opponent().index()],
+        );
+        let q_values = self
+            .q_values
+            .entry(state_id.clone())
+            .or_insert_with(|| vec![vec![self.initial_value; cols]; rows]);
+        q_values[own][other] += learning_rate * (target - q_values[own][other]);
+        let (policy, value) = maximin(q_values);
+        self.policies.insert(state_id.clone(), policy);
+        self.values.insert(state_id.clone(), value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{Environment, MarkovGameEnvironment, ProbabilityT, State};
+    use crate::markov_game::soccer::{SoccerAction, SoccerEnvironment};
+    use pretty_assertions::assert_eq;
+
+    // A single round of a matrix game whose first player's equilibrium is
+    // (3/7, 4/7) with a value of 1/7, the second player's is (2/7, 5/7).
+    fn one_shot_table() -> JointTransitionTable<&'static str> {
+        let payoffs = [[3.0, -1.0], [-2.0, 1.0]];
+        let mut transitions = vec![];
+        for (first, row) in payoffs.iter().enumerate() {
+            for (second, payoff) in row.iter().enumerate() {
+                transitions.push(JointStateTransition {
+                    action_ids: [ActionId(first), ActionId(second)],
+                    new_state_id: StateId("end"),
+                    reward: RewardT(*payoff),
+                    prob: ProbabilityT(1.0),
+                });
+            }
+        }
+        HashMap::from([(StateId("start"), transitions), (StateId("end"), vec![])])
+    }
+
+    #[test]
+    fn one_shot_game() {
+        let table = one_shot_table();
+        let values = minimax_value_iteration(&table, &DPConfig::default());
+        assert!((values[&StateId("start")] - 1.0 / 7.0).abs() < 1e-9);
+        assert_eq!(values[&StateId("end")], 0.0);
+        let first = minimax_policy(&table, &values, 1.0, Player::First);
+        assert!((first[&StateId("start")][0] - 3.0 / 7.0).abs() < 1e-9);
+        let second = minimax_policy(&table, &values, 1.0, Player::Second);
+        assert!((second[&StateId("start")][0] - 2.0 / 7.0).abs() < 1e-9);
+        assert!(!first.contains_key(&StateId("end")));
+    }
+
+    #[test]
+    fn minimax_q_learns_the_equilibrium_against_a_random_opponent() {
+        let table = one_shot_table();
+        let mut agent = MinimaxQ::new(Player::First, [2, 2], 1.0, 0).with_learning_rate(0.5, 0.999);
+        let mut rng = StdRng::seed_from_u64(1);
+        let start = StateId("start");
+        for _ in 0..3000 {
+            let action_ids = [agent.select_action(&start), ActionId(rng.gen_range(0..2))];
+            let reward = table[&start]
+                .iter()
+                .find(|t| t.action_ids == action_ids)
+                .unwrap()
+                .reward;
+            agent.learn(&start, action_ids, reward, None);
+        }
+        assert!((agent.policy(&start)[0] - 3.0 / 7.0).abs() < 0.02);
+        assert!((agent.value(&start) - 1.0 / 7.0).abs() < 0.02);
+    }
+
+    #[test]
+    fn soccer_values_are_antisymmetric() {
+        let env = SoccerEnvironment::with_size(2, 4, 0);
+        let table = env.joint_state_transitions();
+        let config = DPConfig {
+            discount: 0.9,
+            theta: 1e-6,
+            ..Default::default()
+        };
+        let values = minimax_value_iteration(&table, &config);
+        // Mirroring the field and swapping the players negates the value.
+        let value = |first, second, ball| {
+            let env = SoccerEnvironment::with_size(2, 4, 0).with_state(first, second, ball);
+            values[&env.state().id()]
+        };
+        for (first, second) in [((0, 2), (1, 1)), ((1, 0), (0, 3)), ((0, 1), (0, 2))] {
+            let mirror = |(row, col): (usize, usize)| (row, 3 - col);
+            for ball in [Player::First, Player::Second] {
+                let mirrored = value(mirror(second), mirror(first), ball.opponent());
+                assert!((value(first, second, ball) + mirrored).abs() < 1e-5);
+            }
+        }
+        // Right in front of the goal with the ball, the first player scores
+        // unless the second player is in the way.
+        assert!((value((0, 0), (1, 3), Player::First) - 1.0).abs() < 1e-5);
+        assert!(value((0, 2), (1, 1), Player::First) > 0.0);
+    }
+
+    #[test]
+    fn minimax_q_beats_a_random_opponent_at_soccer() {
+        let mut env = SoccerEnvironment::with_size(2, 4, 0);
+        let mut agent = MinimaxQ::new(Player::First, [5, 5], 0.9, 1);
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut play = |env: &mut SoccerEnvironment, agent: &mut MinimaxQ, learning: bool| {
+            env.reset();
+            // Games that take too long are cut off.
+            for _ in 0..100 {
+                let state_id = env.state().id();
+                let action_ids = [
+                    agent.select_action(&state_id),
+                    ActionId(rng.gen_range(0..5)),
+                ];
+                let reward = env.apply_action(&action_ids.map(SoccerAction::action_with_id));
+                let (next_state_id, terminal) = (env.state().id(), env.state().is_terminal());
+                if learning {
+                    let next_state_id = (!terminal).then_some(&next_state_id);
+                    agent.learn(&state_id, action_ids, reward, next_state_id);
+                }
+                if terminal {
+                    break;
+                }
+            }
+        };
+        for _ in 0..3000 {
+            play(&mut env, &mut agent, true);
+        }
+
+        let mut agent = agent.with_exploration(0.0);
+        let mut wins = 0;
+        for _ in 0..200 {
+            play(&mut env, &mut agent, false);
+            if env.state().scorer() == Some(Player::First) {
+                wins += 1;
+            }
+        }
+        // The exact minimax policy wins about 95% of the games.
+        assert!(wins > 170, "{}", wins);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod minimax;
+pub mod soccer;
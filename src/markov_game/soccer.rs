@@ -0,0 +1,352 @@
+use crate::environment::{
+    ActionId, Environment, JointStateTransition, MarkovGameEnvironment, Player, ProbabilityT,
+    RewardT, State, StateId,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SoccerAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Stand,
+}
+
+impl SoccerAction {
+    pub fn all() -> [SoccerAction; 5] {
+        [
+            SoccerAction::Up,
+            SoccerAction::Down,
+            SoccerAction::Left,
+            SoccerAction::Right,
+            SoccerAction::Stand,
+        ]
+    }
+
+    pub fn id(&self) -> ActionId {
+        match self {
+            SoccerAction::Up => ActionId(0),
+            SoccerAction::Down => ActionId(1),
+            SoccerAction::Left => ActionId(2),
+            SoccerAction::Right => ActionId(3),
+            SoccerAction::Stand => ActionId(4),
+        }
+    }
+
+    pub fn action_with_id(action_id: ActionId) -> SoccerAction {
+        SoccerAction::all()[action_id.0]
+    }
+
+    // Row and column offsets, rows grow downwards.
+    pub fn offset(&self) -> (isize, isize) {
+        match self {
+            SoccerAction::Up => (-1, 0),
+            SoccerAction::Down => (1, 0),
+            SoccerAction::Left => (0, -1),
+            SoccerAction::Right => (0, 1),
+            SoccerAction::Stand => (0, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SoccerState {
+    // Rows and columns, indexed by `Player::index`.
+    positions: [(usize, usize); 2],
+    ball: Player,
+    scorer: Option<Player>,
+    rows: usize,
+    cols: usize,
+}
+
+impl SoccerState {
+    pub fn position(&self, player: Player) -> (usize, usize) {
+        self.positions[player.index()]
+    }
+
+    pub fn ball(&self) -> Player {
+        self.ball
+    }
+
+    // The player who scored once the game is over.
+    pub fn scorer(&self) -> Option<Player> {
+        self.scorer
+    }
+
+    pub fn id(&self) -> StateId {
+        let cells = self.rows * self.cols;
+        if let Some(scorer) = self.scorer {
+            return StateId(cells * cells * 2 + scorer.index());
+        }
+        let [first, second] = self.positions.map(|(row, col)| row * self.cols + col);
+        StateId((first * cells + second) * 2 + self.ball.index())
+    }
+
+    // The goals span the middle row, or the middle two rows of grids with an
+    // even number of rows.
+    fn is_goal_row(&self, row: usize) -> bool {
+        2 * row + 2 >= self.rows && 2 * row <= self.rows
+    }
+
+    // Moves `player`, returns whether that scored a goal.
+    fn apply_move(&mut self, player: Player, action: SoccerAction) -> bool {
+        let (row, col) = self.positions[player.index()];
+        let (row_offset, col_offset) = action.offset();
+        let (new_row, new_col) = (row as isize + row_offset, col as isize + col_offset);
+        let has_ball = self.ball == player;
+        if has_ball && self.is_goal_row(row) && (new_col < 0 || new_col >= self.cols as isize) {
+            // The first player attacks the left goal, the second the right.
+            self.scorer = Some(if new_col < 0 {
+                Player::First
+            } else {
+                Player::Second
+            });
+            return true;
+        }
+        let inside = (0..self.rows as isize).contains(&new_row)
+            && (0..self.cols as isize).contains(&new_col);
+        if !inside {
+            return false;
+        }
+        let target = (new_row as usize, new_col as usize);
+        if target == self.positions[player.opponent().index()] {
+            // Running into the other player hands them the ball.
+            self.ball = player.opponent();
+            return false;
+        }
+        self.positions[player.index()] = target;
+        false
+    }
+
+    // Applies the moves of both players in the given order.
+    fn after_moves(&self, actions: [SoccerAction; 2], order: [Player; 2]) -> SoccerState {
+        let mut state = *self;
+        for player in order {
+            if state.apply_move(player, actions[player.index()]) {
+                break;
+            }
+        }
+        state
+    }
+
+    fn reward(&self) -> RewardT {
+        match self.scorer {
+            Some(Player::First) => RewardT(1.0),
+            Some(Player::Second) => RewardT(-1.0),
+            None => RewardT(0.0),
+        }
+    }
+}
+
+impl State for SoccerState {
+    fn is_terminal(&self) -> bool {
+        self.scorer.is_some()
+    }
+}
+
+// The soccer game of Littman (1994): two players on a grid, the one with the
+// ball scores by carrying it into the goal of its side. Both pick a move at
+// once and the moves are executed in a random order. A player running into
+// the other doesn't move and the ball goes to the other player. The first
+// player scores on the left, the second on the right, and rewards are +1 and
+// -1 from the first player's perspective. Who starts with the ball is random.
+#[derive(Debug)]
+pub struct SoccerEnvironment {
+    state: SoccerState,
+    rng: StdRng,
+}
+
+impl SoccerEnvironment {
+    // The 4x5 grid of the paper.
+    pub fn new(seed: u64) -> Self {
+        SoccerEnvironment::with_size(4, 5, seed)
+    }
+
+    pub fn with_size(rows: usize, cols: usize, seed: u64) -> Self {
+        if rows < 2 || cols < 4 {
+            panic!("the grid has to be at least 2x4, got {}x{}", rows, cols);
+        }
+        let mut env = SoccerEnvironment {
+            state: SoccerState {
+                positions: [(0, 0); 2],
+                ball: Player::First,
+                scorer: None,
+                rows,
+                cols,
+            },
+            rng: StdRng::seed_from_u64(seed),
+        };
+        env.reset();
+        env
+    }
+
+    // Puts both players next to their own goal, facing each other.
+    pub fn reset(&mut self) {
+        let (rows, cols) = (self.state.rows, self.state.cols);
+        self.state.positions = [((rows - 1) / 2, cols - 2), (rows / 2, 1)];
+        self.state.ball = if self.rng.gen_bool(0.5) {
+            Player::First
+        } else {
+            Player::Second
+        };
+        self.state.scorer = None;
+    }
+
+    pub fn with_state(
+        mut self,
+        first: (usize, usize),
+        second: (usize, usize),
+        ball: Player,
+    ) -> Self {
+        for (row, col) in [first, second] {
+            if row >= self.state.rows || col >= self.state.cols {
+                panic!("({}, {}) is outside of the grid", row, col);
+            }
+        }
+        if first == second {
+            panic!("both players can't be at {:?}", first);
+        }
+        self.state.positions = [first, second];
+        self.state.ball = ball;
+        self.state.scorer = None;
+        self
+    }
+
+    fn all_states(&self) -> Vec<SoccerState> {
+        let cells: Vec<(usize, usize)> = (0..self.state.rows)
+            .flat_map(|row| (0..self.state.cols).map(move |col| (row, col)))
+            .collect();
+        let mut states = vec![];
+        for first in cells.iter() {
+            for second in cells.iter().filter(|cell| *cell != first) {
+                for ball in [Player::First, Player::Second] {
+                    states.push(SoccerState {
+                        positions: [*first, *second],
+                        ball,
+                        ..self.state
+                    });
+                }
+            }
+        }
+        for scorer in [Player::First, Player::Second] {
+            states.push(SoccerState {
+                scorer: Some(scorer),
+                ..self.state
+            });
+        }
+        states
+    }
+}
+
+impl Environment for SoccerEnvironment {
+    type Action = [SoccerAction; 2];
+    type State = SoccerState;
+
+    fn state(&self) -> &SoccerState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<[SoccerAction; 2]> {
+        if self.state.is_terminal() {
+            return vec![];
+        }
+        SoccerAction::all()
+            .into_iter()
+            .flat_map(|first| SoccerAction::all().map(|second| [first, second]))
+            .collect()
+    }
+    fn apply_action(&mut self, action: &[SoccerAction; 2]) -> RewardT {
+        if self.state.is_terminal() {
+            panic!("can't move after the game is over");
+        }
+        let order = if self.rng.gen_bool(0.5) {
+            [Player::First, Player::Second]
+        } else {
+            [Player::Second, Player::First]
+        };
+        self.state = self.state.after_moves(*action, order);
+        self.state.reward()
+    }
+}
+
+impl MarkovGameEnvironment for SoccerEnvironment {
+    fn joint_state_transitions(&self) -> HashMap<StateId, Vec<JointStateTransition>> {
+        self.all_states()
+            .into_iter()
+            .map(|state| {
+                let mut transitions = vec![];
+                if !state.is_terminal() {
+                    for first in SoccerAction::all() {
+                        for second in SoccerAction::all() {
+                            for order in [
+                                [Player::First, Player::Second],
+                                [Player::Second, Player::First],
+                            ] {
+                                let next = state.after_moves([first, second], order);
+                                transitions.push(JointStateTransition {
+                                    action_ids: [first.id(), second.id()],
+                                    new_state_id: next.id(),
+                                    reward: next.reward(),
+                                    prob: ProbabilityT(0.5),
+                                });
+                            }
+                        }
+                    }
+                }
+                (state.id(), transitions)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn scoring() {
+        let mut env = SoccerEnvironment::new(0).with_state((1, 0), (2, 3), Player::First);
+        let reward = env.apply_action(&[SoccerAction::Left, SoccerAction::Stand]);
+        assert_eq!(reward, RewardT(1.0));
+        assert_eq!(env.state().scorer(), Some(Player::First));
+        assert!(env.actions().is_empty());
+
+        // Only the middle rows have goals, elsewhere the edge is a wall.
+        let mut env = SoccerEnvironment::new(0).with_state((0, 0), (2, 3), Player::First);
+        let reward = env.apply_action(&[SoccerAction::Left, SoccerAction::Stand]);
+        assert_eq!(reward, RewardT(0.0));
+        assert_eq!(env.state().position(Player::First), (0, 0));
+
+        // Carrying the ball into the own goal scores for the opponent.
+        let mut env = SoccerEnvironment::new(0).with_state((2, 4), (0, 0), Player::First);
+        let reward = env.apply_action(&[SoccerAction::Right, SoccerAction::Up]);
+        assert_eq!(reward, RewardT(-1.0));
+    }
+
+    #[test]
+    fn collisions_hand_over_the_ball() {
+        for seed in 0..10 {
+            let mut env = SoccerEnvironment::new(seed).with_state((1, 1), (1, 2), Player::Second);
+            env.apply_action(&[SoccerAction::Stand, SoccerAction::Left]);
+            let state = env.state();
+            assert_eq!(state.ball(), Player::First);
+            assert_eq!(state.position(Player::Second), (1, 2));
+        }
+    }
+
+    #[test]
+    fn joint_state_transitions() {
+        let env = SoccerEnvironment::with_size(3, 4, 0);
+        let table = env.joint_state_transitions();
+        assert_eq!(table.len(), 12 * 11 * 2 + 2);
+        assert!(table.contains_key(&env.state().id()));
+        for transitions in table.values().filter(|t| !t.is_empty()) {
+            assert_eq!(transitions.len(), 50);
+            let total: f64 = transitions.iter().map(|t| t.prob.0).sum();
+            assert!((total - 25.0).abs() < 1e-9);
+        }
+    }
+}
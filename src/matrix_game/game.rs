@@ -0,0 +1,248 @@
+use crate::environment::Player;
+use crate::lp::{LinearProgram, Relation};
+
+// A two-player normal-form game: the first player picks a row, the second
+// player a column, and both get the payoff of that cell in their matrix.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixGame {
+    // Indexed by `Player::index`, then by row and column.
+    payoffs: [Vec<Vec<f64>>; 2],
+}
+
+impl MatrixGame {
+    pub fn new(first: Vec<Vec<f64>>, second: Vec<Vec<f64>>) -> Self {
+        let rows = first.len();
+        let cols = first.first().map_or(0, |row| row.len());
+        if rows == 0 || cols == 0 {
+            panic!("both players need at least one action");
+        }
+        for matrix in [&first, &second] {
+            if matrix.len() != rows || matrix.iter().any(|row| row.len() != cols) {
+                panic!("payoff matrices have to be {}x{}", rows, cols);
+            }
+        }
+        MatrixGame {
+            payoffs: [first, second],
+        }
+    }
+
+    // A zero-sum game, `payoffs` are the first player's.
+    pub fn zero_sum(payoffs: Vec<Vec<f64>>) -> Self {
+        let negated = payoffs
+            .iter()
+            .map(|row| row.iter().map(|p| -p).collect())
+            .collect();
+        MatrixGame::new(payoffs, negated)
+    }
+
+    // Rock, paper, scissors in this order. The equilibrium is uniform.
+    pub fn rock_paper_scissors() -> Self {
+        MatrixGame::zero_sum(vec![
+            vec![0.0, -1.0, 1.0],
+            vec![1.0, 0.0, -1.0],
+            vec![-1.0, 1.0, 0.0],
+        ])
+    }
+
+    // The first player wins if both coins show the same side.
+    pub fn matching_pennies() -> Self {
+        MatrixGame::zero_sum(vec![vec![1.0, -1.0], vec![-1.0, 1.0]])
+    }
+
+    // Cooperating and defecting in this order, defecting is dominant although
+    // mutual cooperation pays more.
+    pub fn prisoners_dilemma() -> Self {
+        let first = vec![vec![-1.0, -3.0], vec![0.0, -2.0]];
+        let second = vec![vec![-1.0, 0.0], vec![-3.0, -2.0]];
+        MatrixGame::new(first, second)
+    }
+
+    pub fn num_actions(&self, player: Player) -> usize {
+        match player {
+            Player::First => self.payoffs[0].len(),
+            Player::Second => self.payoffs[0][0].len(),
+        }
+    }
+
+    pub fn payoff(&self, player: Player, row: usize, col: usize) -> f64 {
+        self.payoffs[player.index()][row][col]
+    }
+
+    pub fn is_zero_sum(&self) -> bool {
+        self.payoffs[0]
+            .iter()
+            .flatten()
+            .zip(self.payoffs[1].iter().flatten())
+            .all(|(a, b)| a + b == 0.0)
+    }
+
+    // The expected payoff of every action of `player` against the mixed
+    // strategy `opponent`.
+    pub fn action_values(&self, player: Player, opponent: &[f64]) -> Vec<f64> {
+        let matrix = &self.payoffs[player.index()];
+        (0..self.num_actions(player))
+            .map(|action| {
+                opponent
+                    .iter()
+                    .enumerate()
+                    .map(|(other, prob)| {
+                        let payoff = match player {
+                            Player::First => matrix[action][other],
+                            Player::Second => matrix[other][action],
+                        };
+                        prob * payoff
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    // The expected payoffs of both players, indexed by `Player::index`.
+    pub fn expected_payoffs(&self, first: &[f64], second: &[f64]) -> [f64; 2] {
+        let value = |player: Player, own: &[f64], opponent: &[f64]| -> f64 {
+            self.action_values(player, opponent)
+                .iter()
+                .zip(own)
+                .map(|(v, p)| v * p)
+                .sum()
+        };
+        [
+            value(Player::First, first, second),
+            value(Player::Second, second, first),
+        ]
+    }
+
+    // How much the players gain on average by deviating to a best response,
+    // zero exactly for Nash equilibria. Like the exploitability of
+    // extensive-form games, it's the game value gap for zero-sum games.
+    pub fn exploitability(&self, first: &[f64], second: &[f64]) -> f64 {
+        let payoffs = self.expected_payoffs(first, second);
+        let best = |player: Player, opponent: &[f64]| {
+            self.action_values(player, opponent)
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max)
+        };
+        let gains =
+            best(Player::First, second) - payoffs[0] + best(Player::Second, first) - payoffs[1];
+        gains / 2.0
+    }
+
+    // The equilibrium strategies of a zero-sum game, indexed by
+    // `Player::index`, and the first player's value.
+    pub fn zero_sum_equilibrium(&self) -> ([Vec<f64>; 2], f64) {
+        if !self.is_zero_sum() {
+            panic!("only zero-sum games have a single value");
+        }
+        let (first, value) = maximin(&self.payoffs[0]);
+        let transposed = (0..self.num_actions(Player::Second))
+            .map(|col| self.payoffs[1].iter().map(|row| row[col]).collect())
+            .collect::<Vec<Vec<f64>>>();
+        let (second, _) = maximin(&transposed);
+        ([first, second], value)
+    }
+}
+
+// The mixed strategy over the rows of `payoffs` maximizing the worst-case
+// expected payoff over all columns, and that payoff. With the payoffs mapped
+// to b_ij in [1, 2] so that a bigger payoff is worse for the column, it's the
+// linear program max sum_i w_i s.t. sum_i b_ij w_i <= 1 for every column j,
+// where w is the strategy scaled by the inverse of the mapped value. It
+// starts from a feasible basis, which keeps the solver clear of degenerate
+// pivots on tiny payoffs.
+pub fn maximin(payoffs: &[Vec<f64>]) -> (Vec<f64>, f64) {
+    let rows = payoffs.len();
+    let cols = payoffs.first().map_or(0, |row| row.len());
+    if rows == 0 || cols == 0 {
+        panic!("both players need at least one action");
+    }
+    let flat = payoffs.iter().flatten();
+    let max = flat.clone().copied().fold(f64::NEG_INFINITY, f64::max);
+    let min = flat.copied().fold(f64::INFINITY, f64::min);
+    let range = max - min;
+    // Differences this small are rounding noise, and they'd blow up in the
+    // mapping.
+    if range <= 1e-12 * max.abs().max(min.abs()).max(1e-300) {
+        return (vec![1.0 / rows as f64; rows], min);
+    }
+    let mut program = LinearProgram::maximize(vec![1.0; rows]);
+    for col in 0..cols {
+        let coefficients = payoffs
+            .iter()
+            .map(|row| 1.0 + (max - row[col]) / range)
+            .collect();
+        program.add_constraint(coefficients, Relation::LessEqual, 1.0);
+    }
+    let solution = program
+        .solve()
+        .expect("a matrix game always has a bounded value");
+    let strategy = solution
+        .variables
+        .iter()
+        .map(|w| w.max(0.0) / solution.value)
+        .collect();
+    let mapped_value = 1.0 / solution.value;
+    (strategy, max - (mapped_value - 1.0) * range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn action_values() {
+        let game = MatrixGame::rock_paper_scissors();
+        assert!(game.is_zero_sum());
+        assert_eq!(
+            game.action_values(Player::First, &[1.0, 0.0, 0.0]),
+            vec![0.0, 1.0, -1.0]
+        );
+        assert_eq!(
+            game.action_values(Player::Second, &[1.0, 0.0, 0.0]),
+            vec![0.0, 1.0, -1.0]
+        );
+        let uniform = [1.0 / 3.0; 3];
+        assert_eq!(game.exploitability(&uniform, &uniform), 0.0);
+        // Always playing rock loses a point to paper.
+        assert_eq!(game.exploitability(&[1.0, 0.0, 0.0], &uniform), 0.5);
+    }
+
+    #[test]
+    fn prisoners_dilemma() {
+        let game = MatrixGame::prisoners_dilemma();
+        assert!(!game.is_zero_sum());
+        assert_eq!(game.exploitability(&[0.0, 1.0], &[0.0, 1.0]), 0.0);
+        // Both players gain one by defecting from mutual cooperation.
+        assert_eq!(game.exploitability(&[1.0, 0.0], &[1.0, 0.0]), 1.0);
+        assert_eq!(game.expected_payoffs(&[1.0, 0.0], &[0.0, 1.0]), [-3.0, 0.0]);
+    }
+
+    #[test]
+    fn zero_sum_equilibrium() {
+        let game = MatrixGame::zero_sum(vec![vec![3.0, -1.0], vec![-2.0, 1.0]]);
+        let ([first, second], value) = game.zero_sum_equilibrium();
+        assert!((value - 1.0 / 7.0).abs() < 1e-9);
+        assert!((first[0] - 3.0 / 7.0).abs() < 1e-9, "{:?}", first);
+        assert!((second[0] - 2.0 / 7.0).abs() < 1e-9, "{:?}", second);
+        assert!(game.exploitability(&first, &second) < 1e-9);
+
+        let ([first, second], value) = MatrixGame::rock_paper_scissors().zero_sum_equilibrium();
+        assert!(value.abs() < 1e-9);
+        for p in first.iter().chain(&second) {
+            assert!((p - 1.0 / 3.0).abs() < 1e-9);
+        }
+        // A saddle point needs no mixing.
+        let (strategy, value) = maximin(&[vec![2.0, 1.0], vec![0.0, -1.0]]);
+        assert!((strategy[0] - 1.0).abs() < 1e-9, "{:?}", strategy);
+        assert!((value - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic(expected = "payoff matrices have to be 2x2")]
+    fn ragged_payoffs() {
+        MatrixGame::new(
+            vec![vec![0.0, 1.0], vec![1.0, 0.0]],
+            vec![vec![0.0, 1.0], vec![1.0]],
+        );
+    }
+}
@@ -0,0 +1,316 @@
+// Learning dynamics for repeated matrix games. Both players pick a mixed
+// strategy every round and learn from the expected payoffs of all their
+// actions against the opponent's strategy.
+use crate::environment::Player;
+use crate::matrix_game::game::MatrixGame;
+use itertools::Itertools;
+
+pub trait MatrixGameLearner {
+    fn strategy(&self) -> Vec<f64>;
+    // `action_values` are the expected payoffs of every action against the
+    // opponent's strategy of this round.
+    fn update(&mut self, action_values: &[f64]);
+    // The average of all strategies played so far, which is what converges
+    // to an equilibrium for regret minimizers in zero-sum games.
+    fn average_strategy(&self) -> Vec<f64>;
+}
+
+// Tracks how far the played strategies are from an equilibrium over time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Convergence {
+    average: Vec<f64>,
+    current: Vec<f64>,
+}
+
+impl Convergence {
+    pub fn record(&mut self, average_exploitability: f64, current_exploitability: f64) {
+        self.average.push(average_exploitability);
+        self.current.push(current_exploitability);
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.average.len()
+    }
+
+    // The exploitability of the average strategies after every round.
+    pub fn average(&self) -> &[f64] {
+        &self.average
+    }
+
+    // The exploitability of the strategies played in every round.
+    pub fn current(&self) -> &[f64] {
+        &self.current
+    }
+}
+
+// Plays `rounds` rounds of `game` with both players learning simultaneously.
+pub fn run(
+    game: &MatrixGame,
+    first: &mut dyn MatrixGameLearner,
+    second: &mut dyn MatrixGameLearner,
+    rounds: usize,
+) -> Convergence {
+    let mut convergence = Convergence::default();
+    for _ in 0..rounds {
+        let strategies = [first.strategy(), second.strategy()];
+        let current = game.exploitability(&strategies[0], &strategies[1]);
+        first.update(&game.action_values(Player::First, &strategies[1]));
+        second.update(&game.action_values(Player::Second, &strategies[0]));
+        let average = game.exploitability(&first.average_strategy(), &second.average_strategy());
+        convergence.record(average, current);
+    }
+    convergence
+}
+
+#[derive(Debug, Clone)]
+struct StrategySum {
+    sum: Vec<f64>,
+    rounds: usize,
+}
+
+impl StrategySum {
+    fn new(num_actions: usize) -> Self {
+        StrategySum {
+            sum: vec![0.0; num_actions],
+            rounds: 0,
+        }
+    }
+
+    fn add(&mut self, strategy: &[f64]) {
+        for (sum, prob) in self.sum.iter_mut().zip(strategy) {
+            *sum += prob;
+        }
+        self.rounds += 1;
+    }
+
+    fn average(&self) -> Vec<f64> {
+        if self.rounds == 0 {
+            return vec![1.0 / self.sum.len() as f64; self.sum.len()];
+        }
+        self.sum.iter().map(|s| s / self.rounds as f64).collect()
+    }
+}
+
+// Brown (1951): every round a best response to the opponent's empirical
+// average, i.e. to the sum of all action values seen so far. The first round
+// is uniform.
+#[derive(Debug, Clone)]
+pub struct FictitiousPlay {
+    value_sums: Vec<f64>,
+    played: StrategySum,
+}
+
+impl FictitiousPlay {
+    pub fn new(num_actions: usize) -> Self {
+        FictitiousPlay {
+            value_sums: vec![0.0; num_actions],
+            played: StrategySum::new(num_actions),
+        }
+    }
+}
+
+impl MatrixGameLearner for FictitiousPlay {
+    fn strategy(&self) -> Vec<f64> {
+        let n = self.value_sums.len();
+        if self.played.rounds == 0 {
+            return vec![1.0 / n as f64; n];
+        }
+        // Ties go to the first best action.
+        let best = self
+            .value_sums
+            .iter()
+            .position_max_by(|a, b| a.total_cmp(b).then(std::cmp::Ordering::Greater))
+            .unwrap();
+        let mut strategy = vec![0.0; n];
+        strategy[best] = 1.0;
+        strategy
+    }
+    fn update(&mut self, action_values: &[f64]) {
+        self.played.add(&self.strategy());
+        for (sum, value) in self.value_sums.iter_mut().zip(action_values) {
+            *sum += value;
+        }
+    }
+    fn average_strategy(&self) -> Vec<f64> {
+        self.played.average()
+    }
+}
+
+// Hart and Mas-Colell (2000): actions are played in proportion to their
+// positive cumulative regret.
+#[derive(Debug, Clone)]
+pub struct RegretMatching {
+    regrets: Vec<f64>,
+    played: StrategySum,
+}
+
+impl RegretMatching {
+    pub fn new(num_actions: usize) -> Self {
+        RegretMatching {
+            regrets: vec![0.0; num_actions],
+            played: StrategySum::new(num_actions),
+        }
+    }
+}
+
+impl MatrixGameLearner for RegretMatching {
+    fn strategy(&self) -> Vec<f64> {
+        let positive: Vec<f64> = self.regrets.iter().map(|r| r.max(0.0)).collect();
+        let total: f64 = positive.iter().sum();
+        if total > 0.0 {
+            positive.iter().map(|r| r / total).collect()
+        } else {
+            vec![1.0 / self.regrets.len() as f64; self.regrets.len()]
+        }
+    }
+    fn update(&mut self, action_values: &[f64]) {
+        let strategy = self.strategy();
+        let value: f64 = strategy.iter().zip(action_values).map(|(p, v)| p * v).sum();
+        for (regret, action_value) in self.regrets.iter_mut().zip(action_values) {
+            *regret += action_value - value;
+        }
+        self.played.add(&strategy);
+    }
+    fn average_strategy(&self) -> Vec<f64> {
+        self.played.average()
+    }
+}
+
+// Multiplicative weights (Hedge): the weight of every action grows
+// exponentially in its cumulative value, scaled by the `learning_rate`.
+#[derive(Debug, Clone)]
+pub struct MultiplicativeWeights {
+    learning_rate: f64,
+    value_sums: Vec<f64>,
+    played: StrategySum,
+}
+
+impl MultiplicativeWeights {
+    pub fn new(num_actions: usize, learning_rate: f64) -> Self {
+        if learning_rate <= 0.0 {
+            panic!(
+                "the learning rate has to be positive, got {}",
+                learning_rate
+            );
+        }
+        MultiplicativeWeights {
+            learning_rate,
+            value_sums: vec![0.0; num_actions],
+            played: StrategySum::new(num_actions),
+        }
+    }
+}
+
+impl MatrixGameLearner for MultiplicativeWeights {
+    fn strategy(&self) -> Vec<f64> {
+        // Shifting by the maximum keeps the exponentials from overflowing.
+        let max = self
+            .value_sums
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let weights: Vec<f64> = self
+            .value_sums
+            .iter()
+            .map(|v| (self.learning_rate * (v - max)).exp())
+            .collect();
+        let total: f64 = weights.iter().sum();
+        weights.iter().map(|w| w / total).collect()
+    }
+    fn update(&mut self, action_values: &[f64]) {
+        self.played.add(&self.strategy());
+        for (sum, value) in self.value_sums.iter_mut().zip(action_values) {
+            *sum += value;
+        }
+    }
+    fn average_strategy(&self) -> Vec<f64> {
+        self.played.average()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn learners(game: &MatrixGame) -> Vec<(&'static str, [Box<dyn MatrixGameLearner>; 2])> {
+        let n = [
+            game.num_actions(Player::First),
+            game.num_actions(Player::Second),
+        ];
+        vec![
+            (
+                "fictitious play",
+                [
+                    Box::new(FictitiousPlay::new(n[0])),
+                    Box::new(FictitiousPlay::new(n[1])),
+                ],
+            ),
+            (
+                "regret matching",
+                [
+                    Box::new(RegretMatching::new(n[0])),
+                    Box::new(RegretMatching::new(n[1])),
+                ],
+            ),
+            (
+                "multiplicative weights",
+                [
+                    Box::new(MultiplicativeWeights::new(n[0], 0.05)),
+                    Box::new(MultiplicativeWeights::new(n[1], 0.05)),
+                ],
+            ),
+        ]
+    }
+
+    #[test]
+    fn zero_sum_averages_converge() {
+        // The first player's equilibrium is (3/7, 4/7) and the value 1/7.
+        let skewed = MatrixGame::zero_sum(vec![vec![3.0, -1.0], vec![-2.0, 1.0]]);
+        for game in [
+            MatrixGame::rock_paper_scissors(),
+            MatrixGame::matching_pennies(),
+            skewed.clone(),
+        ] {
+            for (name, [mut first, mut second]) in learners(&game) {
+                let convergence = run(&game, first.as_mut(), second.as_mut(), 5000);
+                let exploitability = *convergence.average().last().unwrap();
+                assert!(exploitability < 0.02, "{} {}", name, exploitability);
+            }
+        }
+
+        let [mut first, mut second] = learners(&skewed).remove(1).1;
+        run(&skewed, first.as_mut(), second.as_mut(), 5000);
+        let average = first.average_strategy();
+        assert!((average[0] - 3.0 / 7.0).abs() < 0.01, "{:?}", average);
+        let value = skewed.expected_payoffs(&average, &second.average_strategy())[0];
+        assert!((value - 1.0 / 7.0).abs() < 0.01, "{}", value);
+    }
+
+    #[test]
+    fn regret_matching_cycles_in_rock_paper_scissors() {
+        // Starting away from the equilibrium, the played strategies keep
+        // cycling around it while their average converges.
+        let game = MatrixGame::rock_paper_scissors();
+        let mut first = RegretMatching::new(3);
+        first.regrets = vec![1.0, 0.0, 0.0];
+        let mut second = RegretMatching::new(3);
+        let convergence = run(&game, &mut first, &mut second, 5000);
+        assert_eq!(convergence.rounds(), 5000);
+        let late_current = convergence.current()[4000..]
+            .iter()
+            .fold(0.0_f64, |a, b| a.max(*b));
+        assert!(late_current > 0.1, "{}", late_current);
+        assert!(*convergence.average().last().unwrap() < 0.02);
+    }
+
+    #[test]
+    fn dominant_strategies() {
+        let game = MatrixGame::prisoners_dilemma();
+        for (name, [mut first, mut second]) in learners(&game) {
+            let convergence = run(&game, first.as_mut(), second.as_mut(), 500);
+            assert!(*convergence.current().last().unwrap() < 1e-3, "{}", name);
+            assert!(first.strategy()[1] > 0.999, "{}", name);
+            assert!(second.strategy()[1] > 0.999, "{}", name);
+        }
+    }
+}
@@ -0,0 +1,317 @@
+// Multi-objective MDPs: rewards are vectors and instead of a single optimal
+// value every state has a Pareto front of value vectors, none of which is
+// better than another one in all objectives.
+use crate::dp::{
+    greedy_policy, policy_evaluation, value_iteration, DPConfig, Policy, TransitionTable,
+};
+use crate::environment::{
+    ActionId, RewardT, StateId, StateKey, StateTransition, VectorStateTransition,
+};
+use std::collections::HashMap;
+
+pub type VectorTransitionTable<K = usize> = HashMap<StateId<K>, Vec<VectorStateTransition<K>>>;
+pub type VectorValueTable<K = usize> = HashMap<StateId<K>, Vec<f64>>;
+// The nondominated value vectors of every state.
+pub type ParetoFrontTable<K = usize> = HashMap<StateId<K>, Vec<Vec<f64>>>;
+
+// Whether `a` is at least as good as `b` in every objective and better in
+// one of them.
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(x, y)| x >= y) && a.iter().zip(b).any(|(x, y)| x > y)
+}
+
+// The points that no other point dominates, in lexicographic order. Points
+// within `tolerance` of each other in every objective count as one.
+pub fn pareto_front(mut points: Vec<Vec<f64>>, tolerance: f64) -> Vec<Vec<f64>> {
+    // A point that dominates another one has a larger sum, so it's kept
+    // before the other one is looked at.
+    let sum = |point: &Vec<f64>| point.iter().sum::<f64>();
+    points.sort_by(|a, b| sum(b).total_cmp(&sum(a)));
+    let mut front: Vec<Vec<f64>> = vec![];
+    for point in points {
+        let covered = front
+            .iter()
+            .any(|kept| kept.iter().zip(&point).all(|(k, p)| *k >= p - tolerance));
+        if !covered {
+            front.push(point);
+        }
+    }
+    front.sort_by(|a, b| {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| x.total_cmp(y))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    front
+}
+
+// Value iteration on sets of value vectors (White, 1982). An action can
+// reach the expected reward plus any combination of front points of its
+// successor states, and the front of a state is the nondominated part of
+// what its actions reach. With stochastic transitions the fronts can grow
+// exponentially, so this is meant for small or mostly deterministic tasks.
+// Points closer than `config.theta` are merged.
+pub fn pareto_value_iteration<K: StateKey>(
+    table: &VectorTransitionTable<K>,
+    config: &DPConfig,
+) -> ParetoFrontTable<K> {
+    let zero = vec![0.0; num_objectives(table)];
+    let mut fronts: ParetoFrontTable<K> = table
+        .keys()
+        .map(|id| (id.clone(), vec![zero.clone()]))
+        .collect();
+    for _ in 0..config.max_sweeps {
+        let mut max_delta: f64 = 0.0;
+        for (state_id, transitions) in table.iter() {
+            if transitions.is_empty() {
+                continue;
+            }
+            let new_front = pareto_backup(transitions, &fronts, config);
+            max_delta = max_delta.max(front_distance(&fronts[state_id], &new_front));
+            fronts.insert(state_id.clone(), new_front);
+        }
+        if max_delta <= config.theta {
+            break;
+        }
+    }
+    fronts
+}
+
+// The table with the rewards of `objective` only.
+pub fn objective_table<K: StateKey>(
+    table: &VectorTransitionTable<K>,
+    objective: usize,
+) -> TransitionTable<K> {
+    map_rewards(table, |reward| reward[objective])
+}
+
+// Collapses the reward vectors into their weighted sums.
+pub fn scalarize<K: StateKey>(
+    table: &VectorTransitionTable<K>,
+    weights: &[f64],
+) -> TransitionTable<K> {
+    if weights.len() != num_objectives(table) {
+        panic!(
+            "expected {} weights, got {}",
+            num_objectives(table),
+            weights.len()
+        );
+    }
+    map_rewards(table, |reward| {
+        reward.iter().zip(weights).map(|(r, w)| r * w).sum()
+    })
+}
+
+// The values of a deterministic policy in every objective.
+pub fn evaluate_policy<K: StateKey>(
+    table: &VectorTransitionTable<K>,
+    policy: &Policy<K>,
+    config: &DPConfig,
+) -> VectorValueTable<K> {
+    let mut values: VectorValueTable<K> = table.keys().map(|id| (id.clone(), vec![])).collect();
+    for objective in 0..num_objectives(table) {
+        let objective_values =
+            policy_evaluation(&objective_table(table, objective), policy, config);
+        for (state_id, value) in values.iter_mut() {
+            value.push(objective_values[state_id]);
+        }
+    }
+    values
+}
+
+// Linear scalarisation: the optimal policy for the weighted sum of the
+// objectives, with its values in every objective. Only the points on the
+// convex hull of a Pareto front are optimal for some weights, the points in
+// its dents are never found.
+pub fn linear_scalarization<K: StateKey>(
+    table: &VectorTransitionTable<K>,
+    weights: &[f64],
+    config: &DPConfig,
+) -> (Policy<K>, VectorValueTable<K>) {
+    let scalarized = scalarize(table, weights);
+    let values = value_iteration(&scalarized, config);
+    let policy = greedy_policy(&scalarized, &values, config.discount);
+    let vector_values = evaluate_policy(table, &policy, config);
+    (policy, vector_values)
+}
+
+fn num_objectives<K>(table: &VectorTransitionTable<K>) -> usize {
+    table
+        .values()
+        .flat_map(|transitions| transitions.first())
+        .map(|t| t.reward.0.len())
+        .next()
+        .unwrap_or(0)
+}
+
+fn map_rewards<K: StateKey, F: Fn(&[f64]) -> f64>(
+    table: &VectorTransitionTable<K>,
+    reward: F,
+) -> TransitionTable<K> {
+    table
+        .iter()
+        .map(|(state_id, transitions)| {
+            let transitions = transitions
+                .iter()
+                .map(|t| StateTransition {
+                    action_id: t.action_id,
+                    new_state_id: t.new_state_id.clone(),
+                    reward: RewardT(reward(&t.reward.0)),
+                    prob: t.prob,
+                })
+                .collect();
+            (state_id.clone(), transitions)
+        })
+        .collect()
+}
+
+// The front of a state given the fronts of all states. States missing from
+// `fronts` are worth zero.
+fn pareto_backup<K: StateKey>(
+    transitions: &[VectorStateTransition<K>],
+    fronts: &ParetoFrontTable<K>,
+    config: &DPConfig,
+) -> Vec<Vec<f64>> {
+    let mut action_ids: Vec<ActionId> = vec![];
+    for t in transitions.iter() {
+        if !action_ids.contains(&t.action_id) {
+            action_ids.push(t.action_id);
+        }
+    }
+    let mut points = vec![];
+    for action_id in action_ids {
+        let mut reward = vec![0.0; transitions[0].reward.0.len()];
+        let mut successors: Vec<(&StateId<K>, f64)> = vec![];
+        for t in transitions.iter().filter(|t| t.action_id == action_id) {
+            for (sum, r) in reward.iter_mut().zip(&t.reward.0) {
+                *sum += t.prob.0 * r;
+            }
+            match successors.iter_mut().find(|(id, _)| **id == t.new_state_id) {
+                Some((_, prob)) => *prob += t.prob.0,
+                None => successors.push((&t.new_state_id, t.prob.0)),
+            }
+        }
+        // Every successor state may go on with a different front point, and
+        // sums of dominated points are dominated, so pruning along the way
+        // loses nothing.
+        let mut sums = vec![reward];
+        for (state_id, prob) in successors {
+            let Some(front) = fronts.get(state_id) else {
+                continue;
+            };
+            let scale = config.discount * prob;
+            let combined = sums
+                .iter()
+                .flat_map(|sum| {
+                    front.iter().map(move |point| {
+                        sum.iter().zip(point).map(|(s, p)| s + scale * p).collect()
+                    })
+                })
+                .collect();
+            sums = pareto_front(combined, config.theta);
+        }
+        points.extend(sums);
+    }
+    pareto_front(points, config.theta)
+}
+
+// The Hausdorff distance between two fronts under the max norm.
+fn front_distance(a: &[Vec<f64>], b: &[Vec<f64>]) -> f64 {
+    let one_way = |from: &[Vec<f64>], to: &[Vec<f64>]| {
+        from.iter()
+            .map(|p| {
+                to.iter()
+                    .map(|q| {
+                        p.iter()
+                            .zip(q)
+                            .map(|(x, y)| (x - y).abs())
+                            .fold(0.0, f64::max)
+                    })
+                    .fold(f64::INFINITY, f64::min)
+            })
+            .fold(0.0, f64::max)
+    };
+    one_way(a, b).max(one_way(b, a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::{ProbabilityT, VectorRewardT};
+    use pretty_assertions::assert_eq;
+
+    fn transition(
+        action: usize,
+        new_state: usize,
+        reward: [f64; 2],
+        prob: f64,
+    ) -> VectorStateTransition {
+        VectorStateTransition {
+            action_id: ActionId(action),
+            new_state_id: StateId(new_state),
+            reward: VectorRewardT(reward.to_vec()),
+            prob: ProbabilityT(prob),
+        }
+    }
+
+    #[test]
+    fn dominance() {
+        assert!(dominates(&[1.0, 2.0], &[1.0, 1.0]));
+        assert!(!dominates(&[1.0, 1.0], &[1.0, 1.0]));
+        assert!(!dominates(&[2.0, 0.0], &[1.0, 1.0]));
+
+        let points = vec![
+            vec![1.0, 1.0],
+            vec![0.0, 3.0],
+            vec![2.0, 0.0],
+            vec![1.0, 0.5],
+            vec![1.0 + 1e-12, 1.0],
+        ];
+        assert_eq!(
+            pareto_front(points, 1e-9),
+            vec![vec![0.0, 3.0], vec![1.0 + 1e-12, 1.0], vec![2.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn stochastic_transitions_combine_the_successor_fronts() {
+        // The only action of state 0 leads to states 1 and 2 with equal
+        // probability, both of which pick one of the two objectives.
+        let table: VectorTransitionTable = HashMap::from([
+            (
+                StateId(0),
+                vec![
+                    transition(0, 1, [0.0, 0.0], 0.5),
+                    transition(0, 2, [0.0, 0.0], 0.5),
+                ],
+            ),
+            (
+                StateId(1),
+                vec![
+                    transition(0, 3, [1.0, 0.0], 1.0),
+                    transition(1, 3, [0.0, 1.0], 1.0),
+                ],
+            ),
+            (
+                StateId(2),
+                vec![
+                    transition(0, 3, [1.0, 0.0], 1.0),
+                    transition(1, 3, [0.0, 1.0], 1.0),
+                ],
+            ),
+            (StateId(3), vec![]),
+        ]);
+        let config = DPConfig::default();
+        let fronts = pareto_value_iteration(&table, &config);
+        assert_eq!(
+            fronts[&StateId(0)],
+            vec![vec![0.0, 1.0], vec![0.5, 0.5], vec![1.0, 0.0]]
+        );
+        assert_eq!(fronts[&StateId(3)], vec![vec![0.0, 0.0]]);
+
+        let (policy, values) = linear_scalarization(&table, &[0.75, 0.25], &config);
+        assert_eq!(policy[&StateId(1)], ActionId(0));
+        assert_eq!(values[&StateId(0)], vec![1.0, 0.0]);
+    }
+}
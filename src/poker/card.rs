@@ -0,0 +1,27 @@
+use std::fmt;
+
+// Kuhn poker and Leduc hold'em only use jacks, queens and kings, suits never
+// matter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Rank {
+    Jack,
+    Queen,
+    King,
+}
+
+impl Rank {
+    pub fn all() -> [Rank; 3] {
+        [Rank::Jack, Rank::Queen, Rank::King]
+    }
+}
+
+impl fmt::Display for Rank {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let c = match self {
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+        };
+        write!(f, "{}", c)
+    }
+}
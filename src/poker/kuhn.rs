@@ -0,0 +1,231 @@
+use crate::environment::{Player, ProbabilityT, RewardT, State};
+use crate::extensive_form::{ExtensiveFormGame, NodeKind};
+use crate::poker::card::Rank;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum KuhnAction {
+    // Chance deals a card to the next player without a card.
+    Deal(Rank),
+    // Checks, or folds when facing a bet.
+    Pass,
+    // Bets, or calls when facing a bet.
+    Bet,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct KuhnHistory {
+    cards: Vec<Rank>,
+    bets: Vec<KuhnAction>,
+}
+
+impl KuhnHistory {
+    pub fn cards(&self) -> &[Rank] {
+        &self.cards
+    }
+
+    pub fn bets(&self) -> &[KuhnAction] {
+        &self.bets
+    }
+}
+
+// The betting ends with two passes, a pass after a bet or two bets.
+impl State for KuhnHistory {
+    fn is_terminal(&self) -> bool {
+        use KuhnAction::*;
+        matches!(
+            self.bets.as_slice(),
+            [Pass, Pass] | [Bet, Pass] | [Bet, Bet] | [Pass, Bet, Pass] | [Pass, Bet, Bet]
+        )
+    }
+}
+
+// The card of the player to act and the betting so far.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct KuhnInfoSet {
+    pub card: Rank,
+    pub bets: Vec<KuhnAction>,
+}
+
+// Kuhn (1950): both players ante one chip and get one of three cards. The
+// first player checks or bets one chip, the second player can bet after a
+// check or call a bet, and a bet has to be called or folded to. The game
+// value is -1/18 for the first player.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KuhnPoker;
+
+impl KuhnPoker {
+    pub fn new() -> Self {
+        KuhnPoker
+    }
+}
+
+impl ExtensiveFormGame for KuhnPoker {
+    type History = KuhnHistory;
+    type Action = KuhnAction;
+    type InfoSet = KuhnInfoSet;
+
+    fn root(&self) -> KuhnHistory {
+        KuhnHistory::default()
+    }
+    fn node_kind(&self, history: &KuhnHistory) -> NodeKind {
+        if history.cards.len() < 2 {
+            NodeKind::Chance
+        } else if history.is_terminal() {
+            NodeKind::Terminal
+        } else if history.bets.len().is_multiple_of(2) {
+            NodeKind::Decision(Player::First)
+        } else {
+            NodeKind::Decision(Player::Second)
+        }
+    }
+    fn chance_outcomes(&self, history: &KuhnHistory) -> Vec<(KuhnAction, ProbabilityT)> {
+        let remaining: Vec<Rank> = Rank::all()
+            .into_iter()
+            .filter(|rank| !history.cards.contains(rank))
+            .collect();
+        let prob = ProbabilityT(1.0 / remaining.len() as f64);
+        remaining
+            .into_iter()
+            .map(|rank| (KuhnAction::Deal(rank), prob))
+            .collect()
+    }
+    fn actions(&self, _history: &KuhnHistory) -> Vec<KuhnAction> {
+        vec![KuhnAction::Pass, KuhnAction::Bet]
+    }
+    fn info_set(&self, history: &KuhnHistory) -> KuhnInfoSet {
+        KuhnInfoSet {
+            card: history.cards[history.bets.len() % 2],
+            bets: history.bets.clone(),
+        }
+    }
+    fn apply(&self, history: &KuhnHistory, action: &KuhnAction) -> KuhnHistory {
+        let mut next = history.clone();
+        match action {
+            KuhnAction::Deal(rank) => {
+                if history.cards.len() == 2 || history.cards.contains(rank) {
+                    panic!("can't deal {:?} in {:?}", rank, history);
+                }
+                next.cards.push(*rank);
+            }
+            _ => {
+                if self.node_kind(history) == NodeKind::Chance || history.is_terminal() {
+                    panic!("can't bet in {:?}", history);
+                }
+                next.bets.push(*action);
+            }
+        }
+        next
+    }
+    fn rewards(&self, history: &KuhnHistory) -> [RewardT; 2] {
+        use KuhnAction::*;
+        let showdown = if history.cards[0] > history.cards[1] {
+            1.0
+        } else {
+            -1.0
+        };
+        let first = match history.bets.as_slice() {
+            [Pass, Pass] => showdown,
+            [Bet, Pass] => 1.0,
+            [Pass, Bet, Pass] => -1.0,
+            [Bet, Bet] | [Pass, Bet, Bet] => 2.0 * showdown,
+            _ => panic!("{:?} isn't terminal", history),
+        };
+        [RewardT(first), RewardT(-first)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::RandomAgent;
+    use crate::extensive_form::{
+        expected_rewards, exploitability, info_sets, ExtensiveFormEnvironment,
+    };
+    use crate::selfplay::play_game;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn twelve_info_sets() {
+        let info_sets = info_sets(&KuhnPoker::new());
+        assert_eq!(info_sets.len(), 12);
+        let first = info_sets
+            .values()
+            .filter(|(player, _)| *player == Player::First)
+            .count();
+        assert_eq!(first, 6);
+    }
+
+    #[test]
+    fn equilibrium_value() {
+        // The equilibrium of Kuhn (1950) with α = 0: the first player always
+        // checks, calls with a queen a third of the time and with a king
+        // always. The second player bets a jack a third of the time after a
+        // check, calls with a queen a third of the time and always bets or
+        // calls with a king.
+        let strategy = |info_set: &KuhnInfoSet, _: &[KuhnAction]| {
+            use KuhnAction::*;
+            let bet = match (info_set.card, info_set.bets.as_slice()) {
+                (Rank::Jack, [] | [Pass, Bet] | [Bet]) => 0.0,
+                (Rank::Jack, [Pass]) => 1.0 / 3.0,
+                (Rank::Queen, [] | [Pass]) => 0.0,
+                (Rank::Queen, [Pass, Bet] | [Bet]) => 1.0 / 3.0,
+                (Rank::King, []) => 0.0,
+                (Rank::King, _) => 1.0,
+                _ => unreachable!(),
+            };
+            vec![1.0 - bet, bet]
+        };
+        let values = expected_rewards(&KuhnPoker::new(), &strategy);
+        assert!((values[0] + 1.0 / 18.0).abs() < 1e-12, "{:?}", values);
+        assert_eq!(values[0], -values[1]);
+        assert!(exploitability(&KuhnPoker::new(), &strategy).abs() < 1e-12);
+
+        // Always betting a jack gives the second player something to exploit.
+        let bluffing = |info_set: &KuhnInfoSet, actions: &[KuhnAction]| match info_set.card {
+            Rank::Jack => vec![0.0, 1.0],
+            _ => strategy(info_set, actions),
+        };
+        assert!(exploitability(&KuhnPoker::new(), &bluffing) > 0.01);
+    }
+
+    #[test]
+    fn info_sets_hide_the_opponent_card() {
+        let game = KuhnPoker::new();
+        let mut history = game.root();
+        for action in [
+            KuhnAction::Deal(Rank::King),
+            KuhnAction::Deal(Rank::Jack),
+            KuhnAction::Pass,
+        ] {
+            history = game.apply(&history, &action);
+        }
+        assert_eq!(game.node_kind(&history), NodeKind::Decision(Player::Second));
+        assert_eq!(
+            game.info_set(&history),
+            KuhnInfoSet {
+                card: Rank::Jack,
+                bets: vec![KuhnAction::Pass]
+            }
+        );
+        history = game.apply(&history, &KuhnAction::Bet);
+        history = game.apply(&history, &KuhnAction::Bet);
+        assert_eq!(game.rewards(&history), [RewardT(2.0), RewardT(-2.0)]);
+    }
+
+    #[test]
+    fn random_play() {
+        let uniform = |_: &KuhnInfoSet, actions: &[KuhnAction]| vec![0.5; actions.len()];
+        let expected = expected_rewards(&KuhnPoker::new(), &uniform);
+        let games = 2000;
+        let mut total = 0.0;
+        for seed in 0..games {
+            let env = ExtensiveFormEnvironment::new(KuhnPoker::new(), seed);
+            let mut agent = RandomAgent::new(10000 + seed);
+            let reward = play_game(env, &mut [&mut agent], [0, 0], [false, false]);
+            assert!([-2.0, -1.0, 1.0, 2.0].contains(&reward));
+            total += reward;
+        }
+        let mean = total / games as f64;
+        assert!((mean - expected[0]).abs() < 0.1, "{} {}", mean, expected[0]);
+    }
+}
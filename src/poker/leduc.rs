@@ -0,0 +1,324 @@
+use crate::environment::{Player, ProbabilityT, RewardT, State};
+use crate::extensive_form::{ExtensiveFormGame, NodeKind};
+use crate::poker::card::Rank;
+
+// Every rank is in the deck twice.
+const COPIES: usize = 2;
+const MAX_RAISES: usize = 2;
+// The fixed bet size of the first and the second betting round.
+const BET_SIZES: [f64; 2] = [2.0, 4.0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LeducAction {
+    // Chance deals a private card to the players, then the public card.
+    Deal(Rank),
+    Fold,
+    // Checks, or calls when facing a raise.
+    Call,
+    // Bets, or raises when facing a bet.
+    Raise,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct LeducHistory {
+    // The private cards of both players followed by the public card.
+    cards: Vec<Rank>,
+    rounds: [Vec<LeducAction>; 2],
+}
+
+impl LeducHistory {
+    pub fn cards(&self) -> &[Rank] {
+        &self.cards
+    }
+
+    pub fn public_card(&self) -> Option<Rank> {
+        self.cards.get(2).copied()
+    }
+
+    pub fn round(&self) -> usize {
+        if self.public_card().is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
+    // A round is over once a player calls after the other one acted.
+    fn round_over(&self, round: usize) -> bool {
+        let actions = &self.rounds[round];
+        actions.len() >= 2 && actions.last() == Some(&LeducAction::Call)
+    }
+
+    fn folded(&self) -> Option<Player> {
+        for actions in self.rounds.iter() {
+            if let Some(i) = actions.iter().position(|a| *a == LeducAction::Fold) {
+                return Some(player_at(i));
+            }
+        }
+        None
+    }
+
+    // The chips every player has put into the pot, including the antes.
+    pub fn contributions(&self) -> [f64; 2] {
+        let mut contributions = [1.0, 1.0];
+        for (round, actions) in self.rounds.iter().enumerate() {
+            for (i, action) in actions.iter().enumerate() {
+                let player = player_at(i).index();
+                let other = 1 - player;
+                match action {
+                    LeducAction::Call => contributions[player] = contributions[other],
+                    LeducAction::Raise => {
+                        contributions[player] = contributions[other] + BET_SIZES[round]
+                    }
+                    _ => {}
+                }
+            }
+        }
+        contributions
+    }
+}
+
+// The first player acts first in both rounds.
+fn player_at(index: usize) -> Player {
+    if index.is_multiple_of(2) {
+        Player::First
+    } else {
+        Player::Second
+    }
+}
+
+impl State for LeducHistory {
+    fn is_terminal(&self) -> bool {
+        self.folded().is_some() || self.round_over(1)
+    }
+}
+
+// The private card of the player to act, the public card once it's dealt and
+// the betting so far.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct LeducInfoSet {
+    pub card: Rank,
+    pub public_card: Option<Rank>,
+    pub rounds: [Vec<LeducAction>; 2],
+}
+
+// Leduc hold'em of Southey et al. (2005): a deck of two jacks, queens and
+// kings, both players ante one chip and get a private card. After a betting
+// round a public card is dealt and a second betting round follows. Bets are
+// two chips in the first round and four in the second, with at most two
+// raises per round. At the showdown pairing the public card wins, otherwise
+// the higher card wins and equal cards split the pot.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LeducPoker;
+
+impl LeducPoker {
+    pub fn new() -> Self {
+        LeducPoker
+    }
+}
+
+impl ExtensiveFormGame for LeducPoker {
+    type History = LeducHistory;
+    type Action = LeducAction;
+    type InfoSet = LeducInfoSet;
+
+    fn root(&self) -> LeducHistory {
+        LeducHistory::default()
+    }
+    fn node_kind(&self, history: &LeducHistory) -> NodeKind {
+        if history.cards.len() < 2 {
+            NodeKind::Chance
+        } else if history.is_terminal() {
+            NodeKind::Terminal
+        } else if history.round() == 0 && history.round_over(0) {
+            NodeKind::Chance
+        } else {
+            NodeKind::Decision(player_at(history.rounds[history.round()].len()))
+        }
+    }
+    fn chance_outcomes(&self, history: &LeducHistory) -> Vec<(LeducAction, ProbabilityT)> {
+        let remaining = (COPIES * Rank::all().len() - history.cards.len()) as f64;
+        Rank::all()
+            .into_iter()
+            .filter_map(|rank| {
+                let dealt = history.cards.iter().filter(|card| **card == rank).count();
+                let left = COPIES - dealt;
+                (left > 0).then(|| {
+                    (
+                        LeducAction::Deal(rank),
+                        ProbabilityT(left as f64 / remaining),
+                    )
+                })
+            })
+            .collect()
+    }
+    fn actions(&self, history: &LeducHistory) -> Vec<LeducAction> {
+        let actions = &history.rounds[history.round()];
+        let mut result = vec![];
+        if actions.last() == Some(&LeducAction::Raise) {
+            result.push(LeducAction::Fold);
+        }
+        result.push(LeducAction::Call);
+        let raises = actions.iter().filter(|a| **a == LeducAction::Raise).count();
+        if raises < MAX_RAISES {
+            result.push(LeducAction::Raise);
+        }
+        result
+    }
+    fn info_set(&self, history: &LeducHistory) -> LeducInfoSet {
+        let player = player_at(history.rounds[history.round()].len());
+        LeducInfoSet {
+            card: history.cards[player.index()],
+            public_card: history.public_card(),
+            rounds: history.rounds.clone(),
+        }
+    }
+    fn apply(&self, history: &LeducHistory, action: &LeducAction) -> LeducHistory {
+        let kind = self.node_kind(history);
+        let mut next = history.clone();
+        match action {
+            LeducAction::Deal(rank) => {
+                let valid = kind == NodeKind::Chance
+                    && self
+                        .chance_outcomes(history)
+                        .iter()
+                        .any(|(outcome, _)| outcome == action);
+                if !valid {
+                    panic!("can't deal {:?} in {:?}", rank, history);
+                }
+                next.cards.push(*rank);
+            }
+            _ => {
+                if !matches!(kind, NodeKind::Decision(_)) || !self.actions(history).contains(action)
+                {
+                    panic!("can't play {:?} in {:?}", action, history);
+                }
+                next.rounds[history.round()].push(*action);
+            }
+        }
+        next
+    }
+    fn rewards(&self, history: &LeducHistory) -> [RewardT; 2] {
+        if !history.is_terminal() {
+            panic!("{:?} isn't terminal", history);
+        }
+        let contributions = history.contributions();
+        let first = match history.folded() {
+            Some(Player::First) => -contributions[0],
+            Some(Player::Second) => contributions[1],
+            None => {
+                let public = history.cards[2];
+                let strength = |card: Rank| (card == public, card);
+                let (first, second) = (strength(history.cards[0]), strength(history.cards[1]));
+                if first > second {
+                    contributions[1]
+                } else if first < second {
+                    -contributions[0]
+                } else {
+                    0.0
+                }
+            }
+        };
+        [RewardT(first), RewardT(-first)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extensive_form::{expected_rewards, info_sets};
+    use pretty_assertions::assert_eq;
+
+    fn play(game: &LeducPoker, actions: &[LeducAction]) -> LeducHistory {
+        actions
+            .iter()
+            .fold(game.root(), |history, action| game.apply(&history, action))
+    }
+
+    #[test]
+    fn info_set_count() {
+        // Six decision points per betting round, five ways for the first
+        // round to end, three private and three public cards.
+        let info_sets = info_sets(&LeducPoker::new());
+        assert_eq!(info_sets.len(), 3 * 6 + 3 * 3 * 5 * 6);
+    }
+
+    #[test]
+    fn betting() {
+        use LeducAction::*;
+        let game = LeducPoker::new();
+        let history = play(
+            &game,
+            &[Deal(Rank::Queen), Deal(Rank::King), Raise, Raise, Call],
+        );
+        assert_eq!(history.contributions(), [5.0, 5.0]);
+        assert_eq!(game.node_kind(&history), NodeKind::Chance);
+        assert_eq!(
+            game.chance_outcomes(&history),
+            vec![
+                (Deal(Rank::Jack), ProbabilityT(0.5)),
+                (Deal(Rank::Queen), ProbabilityT(0.25)),
+                (Deal(Rank::King), ProbabilityT(0.25)),
+            ]
+        );
+
+        // The queen pairs the public card and beats the king.
+        let history = play(
+            &game,
+            &[
+                Deal(Rank::Queen),
+                Deal(Rank::King),
+                Raise,
+                Raise,
+                Call,
+                Deal(Rank::Queen),
+                Call,
+                Raise,
+            ],
+        );
+        assert_eq!(game.actions(&history), vec![Fold, Call, Raise]);
+        assert_eq!(
+            game.info_set(&history),
+            LeducInfoSet {
+                card: Rank::Queen,
+                public_card: Some(Rank::Queen),
+                rounds: [vec![Raise, Raise, Call], vec![Call, Raise]],
+            }
+        );
+        let called = game.apply(&history, &Call);
+        assert_eq!(game.rewards(&called), [RewardT(9.0), RewardT(-9.0)]);
+        let folded = game.apply(&history, &Fold);
+        assert_eq!(game.rewards(&folded), [RewardT(-5.0), RewardT(5.0)]);
+    }
+
+    #[test]
+    fn ties_split_the_pot() {
+        use LeducAction::*;
+        let game = LeducPoker::new();
+        let history = play(
+            &game,
+            &[
+                Deal(Rank::Jack),
+                Deal(Rank::Jack),
+                Call,
+                Call,
+                Deal(Rank::King),
+                Call,
+                Call,
+            ],
+        );
+        assert_eq!(game.rewards(&history), [RewardT(0.0), RewardT(0.0)]);
+    }
+
+    #[test]
+    fn always_calling_is_fair() {
+        let strategy = |_: &LeducInfoSet, actions: &[LeducAction]| {
+            actions
+                .iter()
+                .map(|a| if *a == LeducAction::Call { 1.0 } else { 0.0 })
+                .collect()
+        };
+        let values = expected_rewards(&LeducPoker::new(), &strategy);
+        assert!(values[0].abs() < 1e-12, "{:?}", values);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::agent::GameAgent;
+use crate::environment::{Player, RewardT, State, TwoPlayerEnvironment};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone)]
+pub struct SelfPlayConfig {
+    pub episodes: usize,
+    // Every `snapshot_interval` episodes a frozen copy of the learner is added
+    // to the pool of past selves. `None` disables snapshots.
+    pub snapshot_interval: Option<usize>,
+    // The oldest snapshots are dropped once the pool grows beyond this size.
+    pub max_snapshots: usize,
+    // Probability of training against a past self instead of the current one.
+    pub snapshot_probability: f64,
+    // Every `eval_interval` episodes the learner plays `eval_games` games
+    // against each fixed opponent. Zero disables evaluation.
+    pub eval_interval: usize,
+    pub eval_games: usize,
+    pub seed: u64,
+}
+
+impl Default for SelfPlayConfig {
+    fn default() -> Self {
+        SelfPlayConfig {
+            episodes: 10000,
+            snapshot_interval: None,
+            max_snapshots: 10,
+            snapshot_probability: 0.5,
+            eval_interval: 1000,
+            eval_games: 100,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MatchResult {
+    pub wins: usize,
+    pub draws: usize,
+    pub losses: usize,
+}
+
+impl MatchResult {
+    pub fn games(&self) -> usize {
+        self.wins + self.draws + self.losses
+    }
+
+    // Wins count as one point and draws as half a point.
+    pub fn score(&self) -> f64 {
+        if self.games() == 0 {
+            return 0.0;
+        }
+        (self.wins as f64 + 0.5 * self.draws as f64) / self.games() as f64
+    }
+
+    fn record(&mut self, outcome: f64) {
+        if outcome > 0.0 {
+            self.wins += 1;
+        } else if outcome < 0.0 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Evaluation {
+    pub episode: usize,
+    pub opponent: String,
+    pub result: MatchResult,
+}
+
+// Plays a single game starting from `env`. `agents` holds every
+// distinct participant and `seats` maps each player to its index there, so the
+// same agent can occupy both seats. Only seats marked in `learning` get
+// `observe`/`end_episode` calls. Returns the total reward of the game from the
+// first player's perspective.
+pub fn play_game<E: TwoPlayerEnvironment>(
+    mut env: E,
+    agents: &mut [&mut dyn GameAgent<E>],
+    seats: [usize; 2],
+    learning: [bool; 2],
+) -> f64 {
+    let mut total_reward = 0.0;
+    while !env.state().is_terminal() {
+        let player = env.current_player();
+        let action = agents[seats[player.index()]].act(&env, learning[player.index()]);
+        let reward = env.apply_action(&action);
+        total_reward += reward.0;
+        for player in [Player::First, Player::Second] {
+            if learning[player.index()] {
+                agents[seats[player.index()]].observe(player, &env, player.perspective(reward));
+            }
+        }
+    }
+    for player in [Player::First, Player::Second] {
+        if learning[player.index()] {
+            agents[seats[player.index()]].end_episode(player);
+        }
+    }
+    total_reward
+}
+
+// Plays `games` evaluation games without learning or exploration, each one
+// in a fresh environment created by `new_environment`. `agent` alternates
+// between moving first and second. The result is from `agent`'s perspective.
+pub fn play_match<E: TwoPlayerEnvironment>(
+    mut new_environment: impl FnMut() -> E,
+    agent: &mut dyn GameAgent<E>,
+    opponent: &mut dyn GameAgent<E>,
+    games: usize,
+) -> MatchResult {
+    let mut result = MatchResult::default();
+    let mut agents: [&mut dyn GameAgent<E>; 2] = [agent, opponent];
+    for game in 0..games {
+        let agent_player = if game.is_multiple_of(2) {
+            Player::First
+        } else {
+            Player::Second
+        };
+        let seats = match agent_player {
+            Player::First => [0, 1],
+            Player::Second => [1, 0],
+        };
+        let reward = play_game(new_environment(), &mut agents, seats, [false, false]);
+        result.record(agent_player.perspective(RewardT(reward)).0);
+    }
+    result
+}
+
+pub struct SelfPlay<E: TwoPlayerEnvironment, A> {
+    learner: A,
+    snapshots: Vec<A>,
+    opponents: Vec<(String, Box<dyn GameAgent<E>>)>,
+    evaluations: Vec<Evaluation>,
+    config: SelfPlayConfig,
+    rng: StdRng,
+    new_environment: Box<dyn FnMut() -> E>,
+}
+
+impl<E, A> SelfPlay<E, A>
+where
+    E: TwoPlayerEnvironment + Default + 'static,
+    A: GameAgent<E> + Clone,
+{
+    pub fn new(learner: A, config: SelfPlayConfig) -> Self {
+        SelfPlay {
+            learner,
+            snapshots: vec![],
+            opponents: vec![],
+            evaluations: vec![],
+            rng: StdRng::seed_from_u64(config.seed),
+            config,
+            new_environment: Box::new(E::default),
+        }
+    }
+
+    // Replaces `E::default` as the way to set up the environment of every
+    // game, e.g. to pick a board size or enable environment options.
+    pub fn with_environment(mut self, new_environment: impl FnMut() -> E + 'static) -> Self {
+        self.new_environment = Box::new(new_environment);
+        self
+    }
+
+    // Adds a fixed opponent the learner is periodically evaluated against.
+    pub fn add_opponent(&mut self, name: &str, opponent: Box<dyn GameAgent<E>>) {
+        self.opponents.push((name.to_string(), opponent));
+    }
+
+    pub fn learner(&self) -> &A {
+        &self.learner
+    }
+
+    pub fn into_learner(self) -> A {
+        self.learner
+    }
+
+    pub fn snapshots(&self) -> &[A] {
+        &self.snapshots
+    }
+
+    pub fn evaluations(&self) -> &[Evaluation] {
+        &self.evaluations
+    }
+
+    pub fn train(&mut self) -> &[Evaluation] {
+        for episode in 1..=self.config.episodes {
+            self.play_training_episode(episode);
+
+            if let Some(interval) = self.config.snapshot_interval {
+                if episode.is_multiple_of(interval) {
+                    self.snapshots.push(self.learner.clone());
+                    if self.snapshots.len() > self.config.max_snapshots {
+                        self.snapshots.remove(0);
+                    }
+                }
+            }
+
+            if self.config.eval_interval > 0 && episode.is_multiple_of(self.config.eval_interval) {
+                self.evaluate(episode);
+            }
+        }
+        &self.evaluations
+    }
+
+    pub fn evaluate(&mut self, episode: usize) {
+        for (name, opponent) in self.opponents.iter_mut() {
+            let result = play_match(
+                &mut self.new_environment,
+                &mut self.learner,
+                opponent.as_mut(),
+                self.config.eval_games,
+            );
+            self.evaluations.push(Evaluation {
+                episode,
+                opponent: name.clone(),
+                result,
+            });
+        }
+    }
+
+    fn play_training_episode(&mut self, episode: usize) {
+        let use_snapshot =
+            !self.snapshots.is_empty() && self.rng.gen_bool(self.config.snapshot_probability);
+        if !use_snapshot {
+            play_game(
+                (self.new_environment)(),
+                &mut [&mut self.learner],
+                [0, 0],
+                [true, true],
+            );
+            return;
+        }
+
+        let index = self.rng.gen_range(0..self.snapshots.len());
+        let snapshot = &mut self.snapshots[index];
+        // Alternate seats so the learner gets experience as both players.
+        let (seats, learning) = if episode.is_multiple_of(2) {
+            ([0, 1], [true, false])
+        } else {
+            ([1, 0], [false, true])
+        };
+        play_game(
+            (self.new_environment)(),
+            &mut [&mut self.learner, snapshot],
+            seats,
+            learning,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{FirstActionAgent, RandomAgent};
+    use crate::tictactoe::environment::TicTacToeEnvironment;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn match_result_score() {
+        let result = MatchResult {
+            wins: 2,
+            draws: 2,
+            losses: 0,
+        };
+        assert_eq!(result.games(), 4);
+        assert_eq!(result.score(), 0.75);
+        assert_eq!(MatchResult::default().score(), 0.0);
+    }
+
+    #[test]
+    fn play_match_alternates_seats() {
+        // Two deterministic agents: the first mover fills the top row and wins.
+        let mut agent = FirstActionAgent;
+        let mut opponent = FirstActionAgent;
+        let mut created = 0;
+        let new_environment = || {
+            created += 1;
+            TicTacToeEnvironment::new()
+        };
+        let result = play_match(new_environment, &mut agent, &mut opponent, 4);
+        assert_eq!(created, 4);
+        assert_eq!(
+            result,
+            MatchResult {
+                wins: 2,
+                draws: 0,
+                losses: 2
+            }
+        );
+    }
+
+    #[test]
+    fn train_records_evaluations() {
+        let config = SelfPlayConfig {
+            episodes: 20,
+            snapshot_interval: Some(5),
+            max_snapshots: 2,
+            eval_interval: 10,
+            eval_games: 6,
+            ..Default::default()
+        };
+        let mut self_play: SelfPlay<TicTacToeEnvironment, RandomAgent> =
+            SelfPlay::new(RandomAgent::new(1), config);
+        self_play.add_opponent("random", Box::new(RandomAgent::new(2)));
+        self_play.add_opponent("first", Box::new(FirstActionAgent));
+
+        let evaluations = self_play.train();
+        let summary: Vec<(usize, &str, usize)> = evaluations
+            .iter()
+            .map(|e| (e.episode, e.opponent.as_str(), e.result.games()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (10, "random", 6),
+                (10, "first", 6),
+                (20, "random", 6),
+                (20, "first", 6)
+            ]
+        );
+        assert_eq!(self_play.snapshots().len(), 2);
+    }
+}
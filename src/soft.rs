@@ -0,0 +1,341 @@
+// Entropy-regularized (maximum entropy) RL: every step adds `temperature`
+// times the entropy of the policy to the reward. The max over actions of the
+// Bellman equation becomes a log-sum-exp and the optimal policy is the
+// Boltzmann distribution of the soft Q-values, which takes every action with
+// some probability and near-optimal ones almost as often as the best. As the
+// temperature goes to zero, this turns into ordinary value iteration.
+use crate::dp::{
+    action_values, sweep_until_converged, DPConfig, LearningRate, StochasticPolicy,
+    TransitionTable, ValueTable,
+};
+use crate::environment::{ActionId, RewardT, StateId, StateKey};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashMap;
+
+// temperature * ln(sum_i exp(values_i / temperature)), a smooth maximum.
+// Zero for no values, like the value of a terminal state.
+pub fn log_sum_exp(values: &[f64], temperature: f64) -> f64 {
+    check_temperature(temperature);
+    if values.is_empty() {
+        return 0.0;
+    }
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let sum: f64 = values
+        .iter()
+        .map(|value| ((value - max) / temperature).exp())
+        .sum();
+    max + temperature * sum.ln()
+}
+
+// The probabilities proportional to exp(values_i / temperature).
+pub fn boltzmann(values: &[f64], temperature: f64) -> Vec<f64> {
+    check_temperature(temperature);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let weights: Vec<f64> = values
+        .iter()
+        .map(|value| ((value - max) / temperature).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+    weights.into_iter().map(|weight| weight / total).collect()
+}
+
+pub fn soft_value_iteration<K: StateKey>(
+    table: &TransitionTable<K>,
+    config: &DPConfig,
+    temperature: f64,
+) -> ValueTable<K> {
+    check_temperature(temperature);
+    sweep_until_converged(table, config, |_, transitions, values| {
+        let q_values: Vec<f64> = action_values(transitions, values, config.discount)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        log_sum_exp(&q_values, temperature)
+    })
+}
+
+// The Boltzmann policy of the soft Q-values given by `values`, which is the
+// soft-optimal policy for the values of `soft_value_iteration`.
+pub fn boltzmann_policy<K: StateKey>(
+    table: &TransitionTable<K>,
+    values: &ValueTable<K>,
+    discount: f64,
+    temperature: f64,
+) -> StochasticPolicy<K> {
+    table
+        .iter()
+        .filter(|(_, transitions)| !transitions.is_empty())
+        .map(|(state_id, transitions)| {
+            let (action_ids, q_values): (Vec<ActionId>, Vec<f64>) =
+                action_values(transitions, values, discount)
+                    .into_iter()
+                    .unzip();
+            let probs = boltzmann(&q_values, temperature);
+            (
+                state_id.clone(),
+                action_ids.into_iter().zip(probs).collect(),
+            )
+        })
+        .collect()
+}
+
+// Tabular soft Q-learning (Haarnoja et al., 2017): Q-learning with the soft
+// value of the next state as its target. Actions are sampled from the
+// Boltzmann policy, so the temperature also sets the exploration. The
+// available actions are passed in with every state, as they may differ
+// between states.
+#[derive(Debug)]
+pub struct SoftQLearning<K: StateKey = usize> {
+    discount: f64,
+    temperature: f64,
+    learning_rate: LearningRate,
+    q_values: HashMap<(StateId<K>, ActionId), f64>,
+    rng: StdRng,
+}
+
+impl<K: StateKey> SoftQLearning<K> {
+    pub fn new(discount: f64, temperature: f64, seed: u64) -> Self {
+        check_temperature(temperature);
+        SoftQLearning {
+            discount,
+            temperature,
+            learning_rate: LearningRate::new(0.1, 1.0),
+            q_values: HashMap::new(),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn with_learning_rate(mut self, learning_rate: f64, decay: f64) -> Self {
+        self.learning_rate = LearningRate::new(learning_rate, decay);
+        self
+    }
+
+    pub fn q_value(&self, state_id: &StateId<K>, action_id: ActionId) -> f64 {
+        let key = (state_id.clone(), action_id);
+        *self.q_values.get(&key).unwrap_or(&0.0)
+    }
+
+    // The soft value of a state, zero for terminal states without actions.
+    pub fn value(&self, state_id: &StateId<K>, action_ids: &[ActionId]) -> f64 {
+        log_sum_exp(
+            &self.action_q_values(state_id, action_ids),
+            self.temperature,
+        )
+    }
+
+    // The probabilities of `action_ids` under the Boltzmann policy.
+    pub fn policy(&self, state_id: &StateId<K>, action_ids: &[ActionId]) -> Vec<f64> {
+        boltzmann(
+            &self.action_q_values(state_id, action_ids),
+            self.temperature,
+        )
+    }
+
+    pub fn select_action(&mut self, state_id: &StateId<K>, action_ids: &[ActionId]) -> ActionId {
+        if action_ids.is_empty() {
+            panic!("tried acting in a state without actions");
+        }
+        let policy = self.policy(state_id, action_ids);
+        let mut sample: f64 = self.rng.gen();
+        for (action_id, prob) in action_ids.iter().zip(policy) {
+            if sample < prob {
+                return *action_id;
+            }
+            sample -= prob;
+        }
+        action_ids[action_ids.len() - 1]
+    }
+
+    // Learns from a step from `state_id` to `next_state_id`, whose actions are
+    // `next_action_ids`. The episode is over once there are none.
+    pub fn learn(
+        &mut self,
+        state_id: &StateId<K>,
+        action_id: ActionId,
+        reward: RewardT,
+        next_state_id: &StateId<K>,
+        next_action_ids: &[ActionId],
+    ) {
+        let target = reward.0 + self.discount * self.value(next_state_id, next_action_ids);
+        let learning_rate = self.learning_rate.step();
+        let q_value = self
+            .q_values
+            .entry((state_id.clone(), action_id))
+            .or_insert(0.0);
+        *q_value += learning_rate * (target - *q_value);
+    }
+
+    fn action_q_values(&self, state_id: &StateId<K>, action_ids: &[ActionId]) -> Vec<f64> {
+        action_ids
+            .iter()
+            .map(|action_id| self.q_value(state_id, *action_id))
+            .collect()
+    }
+}
+
+fn check_temperature(temperature: f64) {
+    if temperature <= 0.0 {
+        panic!("temperature has to be positive, got {}", temperature);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::benchmarks::chain::{ChainConfig, ChainEnvironment};
+    use crate::constrained::reward_table;
+    use crate::dp::{greedy_policy, rms_error, value_iteration};
+    use crate::environment::{
+        ConstrainedDPEnvironment, DPEnvironment, Environment, ProbabilityT, StateTransition,
+    };
+    use crate::tictactoe::random_opponent::RandomOpponentEnvironment;
+    use pretty_assertions::assert_eq;
+
+    fn config(discount: f64) -> DPConfig {
+        DPConfig {
+            discount,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn smooth_maximum() {
+        assert_eq!(log_sum_exp(&[], 1.0), 0.0);
+        assert!((log_sum_exp(&[0.0, 0.0], 1.0) - 2f64.ln()).abs() < 1e-12);
+        // Large values don't overflow, low temperatures approach the max.
+        assert!((log_sum_exp(&[1000.0, 0.0], 1.0) - 1000.0).abs() < 1e-9);
+        assert!((log_sum_exp(&[1.0, 0.5], 1e-3) - 1.0).abs() < 1e-9);
+
+        let probs = boltzmann(&[1.0, 0.0], 1.0);
+        assert!((probs[0] - 1.0 / (1.0 + (-1f64).exp())).abs() < 1e-12);
+        assert_eq!(boltzmann(&[2.0, 2.0], 0.1), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn soft_values_of_a_single_choice() {
+        // Two actions ending the episode with rewards 1 and 0.
+        let table: TransitionTable = HashMap::from([
+            (
+                StateId(0),
+                [1.0, 0.0]
+                    .iter()
+                    .enumerate()
+                    .map(|(action, reward)| StateTransition {
+                        action_id: ActionId(action),
+                        new_state_id: StateId(1),
+                        reward: RewardT(*reward),
+                        prob: ProbabilityT(1.0),
+                    })
+                    .collect(),
+            ),
+            (StateId(1), vec![]),
+        ]);
+        let values = soft_value_iteration(&table, &config(1.0), 0.5);
+        let expected = 0.5 * ((2.0f64).exp() + 1.0).ln();
+        assert!((values[&StateId(0)] - expected).abs() < 1e-9);
+
+        let policy = boltzmann_policy(&table, &values, 1.0, 0.5);
+        let (action_id, prob) = policy[&StateId(0)][0];
+        assert_eq!(action_id, ActionId(0));
+        assert!((prob - 1.0 / (1.0 + (-2f64).exp())).abs() < 1e-9);
+    }
+
+    #[test]
+    fn low_temperatures_approach_value_iteration() {
+        let env = ChainEnvironment::new(ChainConfig::default(), 0);
+        let table = env.state_transitions();
+        let values = value_iteration(&table, &config(0.9));
+        let soft_values = soft_value_iteration(&table, &config(0.9), 1e-3);
+        assert!(rms_error(&soft_values, &values) < 1e-2);
+
+        let policy = greedy_policy(&table, &values, 0.9);
+        let soft_policy = boltzmann_policy(&table, &soft_values, 0.9, 1e-3);
+        for (state_id, action_id) in policy.iter() {
+            let (_, prob) = soft_policy[state_id]
+                .iter()
+                .find(|(id, _)| id == action_id)
+                .unwrap();
+            assert!(*prob > 0.99);
+        }
+    }
+
+    #[test]
+    fn soft_q_learning_learns_the_soft_values() {
+        let env = ChainEnvironment::new(ChainConfig::default(), 0);
+        let table = env.state_transitions();
+        let temperature = 1.0;
+        let values = soft_value_iteration(&table, &config(0.5), temperature);
+
+        // Learns from transitions sampled from the table, with every action
+        // of every state tried equally often.
+        let mut agent = SoftQLearning::new(0.5, temperature, 0).with_learning_rate(0.5, 0.9998);
+        let mut rng = StdRng::seed_from_u64(1);
+        let action_ids = [ActionId(0), ActionId(1)];
+        for _ in 0..20000 {
+            let state_id = StateId(rng.gen_range(0..5));
+            let action_id = action_ids[rng.gen_range(0..2)];
+            let mut sample: f64 = rng.gen();
+            let transition = table[&state_id]
+                .iter()
+                .filter(|t| t.action_id == action_id)
+                .find(|t| {
+                    sample -= t.prob.0;
+                    sample < 0.0
+                })
+                .unwrap();
+            let next_state_id = transition.new_state_id;
+            agent.learn(
+                &state_id,
+                action_id,
+                transition.reward,
+                &next_state_id,
+                &action_ids,
+            );
+        }
+        for (state_id, value) in values.iter() {
+            assert!((agent.value(state_id, &action_ids) - value).abs() < 0.5);
+        }
+    }
+
+    #[test]
+    fn soft_optimal_tic_tac_toe_openings_are_diverse() {
+        let env = RandomOpponentEnvironment::new(0);
+        let table = reward_table(&env.costed_state_transitions());
+        let start = env.state().canonical_id();
+        // The four corners are the best openings.
+        let values = value_iteration(&table, &config(1.0));
+        let action_values = action_values(&table[&start], &values, 1.0);
+        let best_value = action_values
+            .iter()
+            .map(|(_, v)| *v)
+            .fold(f64::MIN, f64::max);
+        let best: Vec<ActionId> = action_values
+            .iter()
+            .filter(|(_, value)| *value > best_value - 1e-9)
+            .map(|(action_id, _)| *action_id)
+            .collect();
+        assert_eq!(best.len(), 4);
+
+        let opening = |temperature: f64| {
+            let values = soft_value_iteration(&table, &config(1.0), temperature);
+            boltzmann_policy(&table, &values, 1.0, temperature)[&start].clone()
+        };
+        let best_prob = |policy: &[(ActionId, f64)]| -> f64 {
+            policy
+                .iter()
+                .filter(|(action_id, _)| best.contains(action_id))
+                .map(|(_, prob)| prob)
+                .sum()
+        };
+        // A cold policy almost always opens in a corner, a warm one plays
+        // every move now and then, but the corners most often.
+        let cold = opening(1e-3);
+        assert!(best_prob(&cold) > 0.99);
+        let warm = opening(0.1);
+        assert_eq!(warm.len(), 9);
+        assert!(warm.iter().all(|(_, prob)| *prob > 0.01));
+        assert!(best_prob(&warm) < best_prob(&cold));
+        assert!(best_prob(&warm) > 4.0 / 9.0);
+    }
+}
@@ -0,0 +1,198 @@
+// Bounded continuous action spaces, the counterpart of enumerable action
+// types such as `TicTacToeAction`.
+use crate::environment::ContinuousAction;
+use rand::Rng;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActionSpaceError {
+    Dimension { expected: usize, got: usize },
+    NotANumber { index: usize },
+    OutOfBounds { index: usize, value: f64 },
+}
+
+impl fmt::Display for ActionSpaceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ActionSpaceError::Dimension { expected, got } => {
+                write!(
+                    f,
+                    "expected an action of dimension {}, got {}",
+                    expected, got
+                )
+            }
+            ActionSpaceError::NotANumber { index } => {
+                write!(f, "component {} of the action is NaN", index)
+            }
+            ActionSpaceError::OutOfBounds { index, value } => {
+                write!(
+                    f,
+                    "component {} of the action is out of bounds: {}",
+                    index, value
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionSpaceError {}
+
+// An axis-aligned box `[low_0, high_0] x ... x [low_n, high_n]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxSpace {
+    low: Vec<f64>,
+    high: Vec<f64>,
+}
+
+impl BoxSpace {
+    pub fn new(low: Vec<f64>, high: Vec<f64>) -> Self {
+        if low.is_empty() || low.len() != high.len() {
+            panic!(
+                "bounds have to be non-empty and of the same dimension, got {} and {}",
+                low.len(),
+                high.len()
+            );
+        }
+        for (l, h) in low.iter().zip(&high) {
+            if !(l.is_finite() && h.is_finite() && l <= h) {
+                panic!("invalid bounds [{}, {}]", l, h);
+            }
+        }
+        BoxSpace { low, high }
+    }
+
+    // The same interval `[low, high]` in every one of `dim` dimensions.
+    pub fn uniform(dim: usize, low: f64, high: f64) -> Self {
+        BoxSpace::new(vec![low; dim], vec![high; dim])
+    }
+
+    pub fn dim(&self) -> usize {
+        self.low.len()
+    }
+
+    pub fn low(&self) -> &[f64] {
+        &self.low
+    }
+
+    pub fn high(&self) -> &[f64] {
+        &self.high
+    }
+
+    // Checks that `action` has the right dimension, is a number and lies
+    // within the bounds.
+    pub fn validate(&self, action: &ContinuousAction) -> Result<(), ActionSpaceError> {
+        self.validate_shape(action)?;
+        for (index, value) in action.0.iter().enumerate() {
+            if *value < self.low[index] || *value > self.high[index] {
+                return Err(ActionSpaceError::OutOfBounds {
+                    index,
+                    value: *value,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, action: &ContinuousAction) -> bool {
+        self.validate(action).is_ok()
+    }
+
+    fn validate_shape(&self, action: &ContinuousAction) -> Result<(), ActionSpaceError> {
+        if action.0.len() != self.dim() {
+            return Err(ActionSpaceError::Dimension {
+                expected: self.dim(),
+                got: action.0.len(),
+            });
+        }
+        match action.0.iter().position(|value| value.is_nan()) {
+            Some(index) => Err(ActionSpaceError::NotANumber { index }),
+            None => Ok(()),
+        }
+    }
+
+    // Moves every component of `action` into its bounds. Actions of the wrong
+    // dimension or with NaNs can't be clipped and panic.
+    pub fn clip(&self, action: &ContinuousAction) -> ContinuousAction {
+        if let Err(e) = self.validate_shape(action) {
+            panic!("can't clip an invalid action: {}", e);
+        }
+        ContinuousAction(
+            action
+                .0
+                .iter()
+                .enumerate()
+                .map(|(i, value)| value.clamp(self.low[i], self.high[i]))
+                .collect(),
+        )
+    }
+
+    pub fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> ContinuousAction {
+        ContinuousAction(
+            self.low
+                .iter()
+                .zip(&self.high)
+                .map(|(l, h)| if l == h { *l } else { rng.gen_range(*l..*h) })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn validation() {
+        let space = BoxSpace::new(vec![-1.0, 0.0], vec![1.0, 5.0]);
+        assert_eq!(space.validate(&ContinuousAction(vec![0.5, 5.0])), Ok(()));
+        assert_eq!(
+            space.validate(&ContinuousAction(vec![0.5])),
+            Err(ActionSpaceError::Dimension {
+                expected: 2,
+                got: 1
+            })
+        );
+        assert_eq!(
+            space.validate(&ContinuousAction(vec![0.5, f64::NAN])),
+            Err(ActionSpaceError::NotANumber { index: 1 })
+        );
+        assert_eq!(
+            space.validate(&ContinuousAction(vec![-1.5, 1.0])),
+            Err(ActionSpaceError::OutOfBounds {
+                index: 0,
+                value: -1.5
+            })
+        );
+    }
+
+    #[test]
+    fn clipping() {
+        let space = BoxSpace::new(vec![-1.0, 0.0], vec![1.0, 5.0]);
+        assert_eq!(
+            space.clip(&ContinuousAction(vec![-3.0, 7.0])),
+            ContinuousAction(vec![-1.0, 5.0])
+        );
+        assert_eq!(
+            space.clip(&ContinuousAction(vec![0.25, 2.0])),
+            ContinuousAction(vec![0.25, 2.0])
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "expected an action of dimension 2, got 3")]
+    fn clipping_wrong_dimension() {
+        BoxSpace::uniform(2, -1.0, 1.0).clip(&ContinuousAction(vec![0.0; 3]));
+    }
+
+    #[test]
+    fn samples_are_contained() {
+        let space = BoxSpace::new(vec![-2.0, 3.0, 1.0], vec![2.0, 4.0, 1.0]);
+        let mut rng = StdRng::seed_from_u64(0);
+        for _ in 0..100 {
+            assert!(space.contains(&space.sample(&mut rng)));
+        }
+    }
+}
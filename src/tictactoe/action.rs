@@ -1,7 +1,9 @@
 use crate::environment::ActionId;
+use crate::tictactoe::board::BoardShape;
 use crate::tictactoe::cell::CellValue;
+use crate::tictactoe::symmetry::BoardTransform;
 
-#[derive(Debug, Ord, Eq, PartialEq, PartialOrd)]
+#[derive(Debug, Clone, Ord, Eq, PartialEq, PartialOrd)]
 pub struct TicTacToeAction {
     cell_value: CellValue,
     cell_index: usize,
@@ -20,6 +22,14 @@ impl TicTacToeAction {
         self.cell_index
     }
 
+    // Maps the action onto the board obtained by applying `transform`.
+    pub fn transformed(&self, transform: BoardTransform, shape: BoardShape) -> TicTacToeAction {
+        TicTacToeAction::new(
+            self.cell_value,
+            transform.apply_to_index(self.cell_index, shape),
+        )
+    }
+
     pub fn new(value: CellValue, index: usize) -> TicTacToeAction {
         TicTacToeAction {
             cell_value: value,
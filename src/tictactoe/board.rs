@@ -0,0 +1,367 @@
+use crate::environment::StateId;
+use crate::tictactoe::cell::CellValue;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+
+const NUM_WORDS: usize = 4;
+pub const MAX_CELLS: usize = NUM_WORDS * 64;
+
+// Row and column steps of the four line directions: horizontal, vertical,
+// the main diagonal and the other diagonal.
+const DIRECTIONS: [(isize, isize); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+// Dimensions of an m,n,k-game: `rows`x`cols` cells and `win_length` values in
+// a row, column or diagonal to win. Tic-tac-toe is the 3,3,3-game.
+#[derive(Clone, Copy)]
+pub struct BoardShape {
+    rows: usize,
+    cols: usize,
+    win_length: usize,
+    // Shared by all shapes with the same dimensions, so shapes stay `Copy`.
+    tables: &'static BoardTables,
+}
+
+// Precomputed once per shape so that states never have to walk the board.
+// Every cell lies on at most 4 * `win_length` lines, which keeps the tables
+// small even for the largest boards.
+struct BoardTables {
+    // Every run of `win_length` cells in a line, rows first, then columns,
+    // then the main and the other diagonals.
+    win_masks: Vec<Bitboard>,
+    // For every cell the indices into `win_masks` of the runs through it.
+    lines_through_cell: Vec<Vec<usize>>,
+    // Contribution of a cell with value id 1 to the state id, empty when the
+    // shape has no state ids.
+    cell_id_weights: Vec<usize>,
+}
+
+impl BoardTables {
+    fn new(rows: usize, cols: usize, win_length: usize) -> BoardTables {
+        let (rows, cols, length) = (rows as isize, cols as isize, win_length as isize);
+        let mut win_masks = vec![];
+        for (row_step, col_step) in DIRECTIONS {
+            for row in 0..rows {
+                for col in 0..cols {
+                    let end_row = row + (length - 1) * row_step;
+                    let end_col = col + (length - 1) * col_step;
+                    if end_row >= rows || end_col < 0 || end_col >= cols {
+                        continue;
+                    }
+                    let mut mask = Bitboard::default();
+                    for i in 0..length {
+                        mask.set(((row + i * row_step) * cols + col + i * col_step) as usize);
+                    }
+                    win_masks.push(mask);
+                }
+            }
+        }
+
+        let num_cells = (rows * cols) as usize;
+        let mut lines_through_cell = vec![vec![]; num_cells];
+        for (line, mask) in win_masks.iter().enumerate() {
+            for cell in mask.ones() {
+                lines_through_cell[cell].push(line);
+            }
+        }
+
+        let has_state_ids = u32::try_from(num_cells)
+            .ok()
+            .and_then(|num_cells| CellValue::num_values().checked_pow(num_cells))
+            .is_some();
+        let cell_id_weights = if has_state_ids {
+            std::iter::successors(Some(1), |weight| Some(weight * CellValue::num_values()))
+                .take(num_cells)
+                .collect()
+        } else {
+            vec![]
+        };
+
+        BoardTables {
+            win_masks,
+            lines_through_cell,
+            cell_id_weights,
+        }
+    }
+
+    // Builds the tables of every shape only once and keeps them for the rest
+    // of the program.
+    fn of(rows: usize, cols: usize, win_length: usize) -> &'static BoardTables {
+        static TABLES: Mutex<BTreeMap<(usize, usize, usize), &'static BoardTables>> =
+            Mutex::new(BTreeMap::new());
+        let mut tables = TABLES.lock().unwrap();
+        tables
+            .entry((rows, cols, win_length))
+            .or_insert_with(|| Box::leak(Box::new(BoardTables::new(rows, cols, win_length))))
+    }
+}
+
+impl BoardShape {
+    pub fn new(rows: usize, cols: usize, win_length: usize) -> BoardShape {
+        if rows == 0 || cols == 0 || rows * cols > MAX_CELLS {
+            panic!(
+                "boards need between 1 and {} cells, got {}x{}",
+                MAX_CELLS, rows, cols
+            );
+        }
+        if win_length == 0 || win_length > rows.max(cols) {
+            panic!(
+                "win length {} doesn't fit on a {}x{} board",
+                win_length, rows, cols
+            );
+        }
+        BoardShape {
+            rows,
+            cols,
+            win_length,
+            tables: BoardTables::of(rows, cols, win_length),
+        }
+    }
+
+    pub fn tic_tac_toe() -> BoardShape {
+        BoardShape::new(3, 3, 3)
+    }
+
+    pub fn gomoku() -> BoardShape {
+        BoardShape::new(15, 15, 5)
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn win_length(&self) -> usize {
+        self.win_length
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.rows == self.cols
+    }
+
+    // Number of state ids, `None` if 3^(rows*cols) doesn't fit into `usize`.
+    // Larger boards can only be identified by `TicTacToeState::key`.
+    pub fn max_state_id(&self) -> Option<StateId> {
+        let num_cells: u32 = self.num_cells().try_into().ok()?;
+        CellValue::num_values().checked_pow(num_cells).map(StateId)
+    }
+
+    pub fn has_state_ids(&self) -> bool {
+        !self.tables.cell_id_weights.is_empty()
+    }
+
+    // Every run of `win_length` cells in a row, column or diagonal.
+    pub fn win_masks(&self) -> &'static [Bitboard] {
+        &self.tables.win_masks
+    }
+
+    // Indices into `win_masks` of the runs through the cell at `index`.
+    pub fn lines_through_cell(&self, index: usize) -> &'static [usize] {
+        &self.tables.lines_through_cell[index]
+    }
+
+    // The amount a cell with value id 1 at `index` adds to the state id.
+    // Panics if the shape has no state ids.
+    pub fn cell_id_weight(&self, index: usize) -> usize {
+        self.tables.cell_id_weights[index]
+    }
+
+    fn dimensions(&self) -> (usize, usize, usize) {
+        (self.rows, self.cols, self.win_length)
+    }
+}
+
+// Shapes are equal when their dimensions are, the tables follow from those.
+impl PartialEq for BoardShape {
+    fn eq(&self, other: &Self) -> bool {
+        self.dimensions() == other.dimensions()
+    }
+}
+
+impl Eq for BoardShape {}
+
+impl Hash for BoardShape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.dimensions().hash(state);
+    }
+}
+
+impl fmt::Debug for BoardShape {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("BoardShape")
+            .field("rows", &self.rows)
+            .field("cols", &self.cols)
+            .field("win_length", &self.win_length)
+            .finish()
+    }
+}
+
+impl Default for BoardShape {
+    fn default() -> Self {
+        BoardShape::tic_tac_toe()
+    }
+}
+
+// A fixed size bitset with one bit per cell, large enough for 15x15 boards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct Bitboard([u64; NUM_WORDS]);
+
+impl Bitboard {
+    pub fn get(&self, index: usize) -> bool {
+        self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn set(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    // Whether every bit of `other` is also set here.
+    pub fn contains(&self, other: &Bitboard) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(word, other_word)| word & other_word == *other_word)
+    }
+
+    pub fn union(&self, other: &Bitboard) -> Bitboard {
+        let mut words = self.0;
+        for (word, other_word) in words.iter_mut().zip(other.0.iter()) {
+            *word |= other_word;
+        }
+        Bitboard(words)
+    }
+
+    // Indices of the set bits in increasing order.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(word_index, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(word_index * 64 + bit)
+            })
+        })
+    }
+}
+
+// Identifies a board of any size, unlike `StateId` which is limited to boards
+// whose state count fits into `usize`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BoardKey {
+    pub crosses: Bitboard,
+    pub circles: Bitboard,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn bitboard_ones() {
+        let mut bitboard = Bitboard::default();
+        for index in [200, 3, 64, 0] {
+            bitboard.set(index);
+        }
+        assert!(bitboard.get(64));
+        assert!(!bitboard.get(63));
+        assert_eq!(bitboard.count_ones(), 4);
+        assert_eq!(bitboard.ones().collect::<Vec<usize>>(), vec![0, 3, 64, 200]);
+    }
+
+    fn mask(cells: &[usize]) -> Bitboard {
+        let mut mask = Bitboard::default();
+        for &index in cells {
+            mask.set(index);
+        }
+        mask
+    }
+
+    #[test]
+    fn tic_tac_toe_win_masks() {
+        let shape = BoardShape::tic_tac_toe();
+        let expected: Vec<Bitboard> = [
+            [0, 1, 2],
+            [3, 4, 5],
+            [6, 7, 8],
+            [0, 3, 6],
+            [1, 4, 7],
+            [2, 5, 8],
+            [0, 4, 8],
+            [2, 4, 6],
+        ]
+        .iter()
+        .map(|cells| mask(cells))
+        .collect();
+        assert_eq!(shape.win_masks(), expected.as_slice());
+        assert_eq!(shape.lines_through_cell(4), &[1, 4, 6, 7]);
+        assert_eq!(shape.lines_through_cell(0), &[0, 3, 6]);
+        assert_eq!(shape.lines_through_cell(5), &[1, 5]);
+        assert_eq!(shape.cell_id_weight(0), 1);
+        assert_eq!(shape.cell_id_weight(8), 6561);
+    }
+
+    #[test]
+    fn win_masks_shorter_than_the_board() {
+        // Windows of 3 on a 3x4 board: 2 per row, 1 per column and 2 along
+        // each diagonal direction.
+        let shape = BoardShape::new(3, 4, 3);
+        assert_eq!(shape.win_masks().len(), 6 + 4 + 2 + 2);
+        assert!(shape.win_masks().contains(&mask(&[1, 6, 11])));
+        assert!(shape.win_masks().contains(&mask(&[3, 6, 9])));
+        assert!(!shape.win_masks().contains(&mask(&[2, 7, 12])));
+
+        let shape = BoardShape::gomoku();
+        assert_eq!(shape.win_masks().len(), 2 * 15 * 11 + 2 * 11 * 11);
+        let center = 7 * 15 + 7;
+        assert_eq!(shape.lines_through_cell(center).len(), 4 * 5);
+        for &line in shape.lines_through_cell(center) {
+            assert!(shape.win_masks()[line].get(center));
+        }
+    }
+
+    #[test]
+    fn bitboard_contains() {
+        let board = mask(&[0, 4, 8, 100]);
+        assert!(board.contains(&mask(&[0, 4, 8])));
+        assert!(board.contains(&mask(&[100])));
+        assert!(!board.contains(&mask(&[0, 4, 7])));
+        assert!(board.contains(&Bitboard::default()));
+    }
+
+    #[test]
+    fn max_state_id() {
+        assert_eq!(
+            BoardShape::tic_tac_toe().max_state_id(),
+            Some(StateId(19683))
+        );
+        assert_eq!(
+            BoardShape::new(4, 4, 3).max_state_id(),
+            Some(StateId(43046721))
+        );
+        assert_eq!(BoardShape::gomoku().max_state_id(), None);
+        assert!(BoardShape::new(4, 4, 3).has_state_ids());
+        assert!(!BoardShape::gomoku().has_state_ids());
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't fit")]
+    fn win_length_too_long() {
+        BoardShape::new(3, 4, 5);
+    }
+}
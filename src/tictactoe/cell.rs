@@ -1,6 +1,6 @@
 use std::char;
 
-#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Ord, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Ord, Eq, Hash)]
 pub enum CellValue {
     None,
     Cross,
@@ -26,15 +26,12 @@ impl CellValue {
     }
 
     pub fn is_set(&self) -> bool {
-        match self {
-            CellValue::None => false,
-            _ => true,
-        }
+        !matches!(self, CellValue::None)
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct CellValueConversionError(char);
+pub struct CellValueConversionError(pub char);
 
 impl TryFrom<char> for CellValue {
     type Error = CellValueConversionError;
@@ -72,7 +69,7 @@ mod tests {
     #[test]
     fn value_id() {
         let values: Vec<CellValue> = (0..CellValue::num_values())
-            .map(|id| CellValue::value_with_id(CellValueId(id as usize)))
+            .map(|id| CellValue::value_with_id(CellValueId(id)))
             .collect();
 
         assert_eq!(
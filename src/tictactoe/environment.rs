@@ -1,7 +1,9 @@
 use crate::environment::{
-    DPEnvironment, Environment, ProbabilityT, RewardT, StateId, StateTransition,
+    AfterstateEnvironment, DPEnvironment, Environment, Player, ProbabilityT, RewardT, StateId,
+    StateTransition, TwoPlayerEnvironment,
 };
 use crate::tictactoe::action::TicTacToeAction;
+use crate::tictactoe::board::{BoardKey, BoardShape};
 use crate::tictactoe::cell::CellValue;
 use crate::tictactoe::state::TicTacToeState;
 use std::collections::HashMap;
@@ -9,12 +11,44 @@ use std::collections::HashMap;
 #[derive(Debug)]
 pub struct TicTacToeEnvironment {
     state: TicTacToeState,
+    // Whether state ids handed out by the environment are canonical under the
+    // board symmetries, see `TicTacToeState::canonical`.
+    reduce_symmetries: bool,
 }
 
 impl TicTacToeEnvironment {
     pub fn new() -> Self {
+        TicTacToeEnvironment::with_shape(BoardShape::tic_tac_toe())
+    }
+
+    pub fn with_shape(shape: BoardShape) -> Self {
         TicTacToeEnvironment {
-            state: TicTacToeState::create_state_with_id(StateId(0)).unwrap(),
+            state: TicTacToeState::empty(shape),
+            reduce_symmetries: false,
+        }
+    }
+
+    // Makes `state_transitions` and afterstate ids work on the symmetry
+    // reduced state space, symmetric states share one id.
+    pub fn with_symmetry_reduction(mut self) -> Self {
+        self.reduce_symmetries = true;
+        self
+    }
+
+    pub fn state_id(&self, state: &TicTacToeState) -> StateId {
+        if self.reduce_symmetries {
+            state.canonical_id()
+        } else {
+            state.id()
+        }
+    }
+
+    // Like `state_id`, but for boards of any size.
+    pub fn state_key(&self, state: &TicTacToeState) -> BoardKey {
+        if self.reduce_symmetries {
+            state.canonical_key()
+        } else {
+            state.key()
         }
     }
 
@@ -27,6 +61,12 @@ impl TicTacToeEnvironment {
     }
 }
 
+impl Default for TicTacToeEnvironment {
+    fn default() -> Self {
+        TicTacToeEnvironment::new()
+    }
+}
+
 impl Environment for TicTacToeEnvironment {
     type Action = TicTacToeAction;
     type State = TicTacToeState;
@@ -43,13 +83,37 @@ impl Environment for TicTacToeEnvironment {
     }
 }
 
+impl AfterstateEnvironment for TicTacToeEnvironment {
+    fn afterstate_id(&self, action: &TicTacToeAction) -> StateId {
+        self.state_id(&self.state.apply_action(action))
+    }
+}
+
+impl AfterstateEnvironment<BoardKey> for TicTacToeEnvironment {
+    fn afterstate_id(&self, action: &TicTacToeAction) -> StateId<BoardKey> {
+        StateId(self.state_key(&self.state.apply_action(action)))
+    }
+}
+
+impl TwoPlayerEnvironment for TicTacToeEnvironment {
+    fn current_player(&self) -> Player {
+        self.state.current_player()
+    }
+}
+
 impl DPEnvironment for TicTacToeEnvironment {
     fn state_transitions(&self) -> HashMap<StateId, Vec<StateTransition>> {
         let mut transition_table = HashMap::new();
-        let num_states = TicTacToeState::max_state_id();
+        let shape = self.state.shape();
+        let num_states = shape
+            .max_state_id()
+            .expect("the board has too many states to enumerate");
         for i in 0..num_states.0 {
             let state_id = StateId(i);
-            let state = TicTacToeState::create_state_with_id(state_id).unwrap();
+            let state = TicTacToeState::create_state_with_id(shape, state_id).unwrap();
+            if self.state_id(&state) != state_id {
+                continue;
+            }
             let actions = state.actions();
             let transitions = actions
                 .iter()
@@ -57,8 +121,8 @@ impl DPEnvironment for TicTacToeEnvironment {
                     let new_state = state.apply_action(a);
                     StateTransition {
                         action_id: a.id(),
-                        prob: ProbabilityT((actions.len() as f64).recip()),
-                        new_state_id: new_state.id(),
+                        prob: ProbabilityT(1.0),
+                        new_state_id: self.state_id(&new_state),
                         reward: TicTacToeEnvironment::reward_for_state(&new_state),
                     }
                 })
@@ -69,3 +133,75 @@ impl DPEnvironment for TicTacToeEnvironment {
         transition_table
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::environment::State;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashSet;
+
+    fn reachable_states(transitions: &HashMap<StateId, Vec<StateTransition>>) -> usize {
+        let mut visited = HashSet::from([StateId(0)]);
+        let mut stack = vec![StateId(0)];
+        while let Some(state_id) = stack.pop() {
+            for transition in transitions[&state_id].iter() {
+                if visited.insert(transition.new_state_id) {
+                    stack.push(transition.new_state_id);
+                }
+            }
+        }
+        visited.len()
+    }
+
+    #[test]
+    fn state_transitions_with_symmetry_reduction() {
+        let transitions = TicTacToeEnvironment::new().state_transitions();
+        assert_eq!(transitions.len(), 19683);
+        assert_eq!(reachable_states(&transitions), 5478);
+
+        let env = TicTacToeEnvironment::new().with_symmetry_reduction();
+        let transitions = env.state_transitions();
+        assert_eq!(transitions.len(), 2862);
+        assert_eq!(reachable_states(&transitions), 765);
+        for (state_id, state_transitions) in transitions.iter() {
+            let state =
+                TicTacToeState::create_state_with_id(BoardShape::tic_tac_toe(), *state_id).unwrap();
+            assert_eq!(state.canonical_id(), *state_id);
+            assert_eq!(state.is_terminal(), state_transitions.is_empty());
+        }
+    }
+
+    #[test]
+    fn transition_probabilities_sum_to_one_per_action() {
+        let transitions = TicTacToeEnvironment::new().state_transitions();
+        for state_transitions in transitions.values() {
+            let mut totals: HashMap<usize, f64> = HashMap::new();
+            for transition in state_transitions.iter() {
+                *totals.entry(transition.action_id.0).or_default() += transition.prob.0;
+            }
+            for total in totals.values() {
+                assert!((total - 1.0).abs() < 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn symmetric_afterstates_share_ids() {
+        let env = TicTacToeEnvironment::new().with_symmetry_reduction();
+        let corners = [0, 2, 6, 8].map(|index| TicTacToeAction::new(CellValue::Cross, index));
+        let corner_ids: HashSet<StateId> = corners.iter().map(|a| env.afterstate_id(a)).collect();
+        assert_eq!(corner_ids.len(), 1);
+        let corner_keys: HashSet<StateId<BoardKey>> =
+            corners.iter().map(|a| env.afterstate_id(a)).collect();
+        assert_eq!(corner_keys.len(), 1);
+    }
+
+    #[test]
+    fn afterstate_keys_on_large_boards() {
+        let env = TicTacToeEnvironment::with_shape(BoardShape::gomoku());
+        let keys: HashSet<StateId<BoardKey>> =
+            env.actions().iter().map(|a| env.afterstate_id(a)).collect();
+        assert_eq!(keys.len(), 225);
+    }
+}
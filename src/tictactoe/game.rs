@@ -0,0 +1,71 @@
+use crate::environment::{Player, ProbabilityT, RewardT, State};
+use crate::extensive_form::{ExtensiveFormGame, NodeKind};
+use crate::tictactoe::action::TicTacToeAction;
+use crate::tictactoe::board::BoardShape;
+use crate::tictactoe::cell::CellValue;
+use crate::tictactoe::state::TicTacToeState;
+
+// TicTacToe as an extensive-form game, e.g. to check game-theoretic solvers
+// against the minimax value. The game has perfect information and no chance,
+// so every board is its own information set.
+#[derive(Debug, Clone, Copy)]
+pub struct TicTacToeGame {
+    start: TicTacToeState,
+}
+
+impl TicTacToeGame {
+    pub fn new() -> Self {
+        TicTacToeGame::with_state(TicTacToeState::empty(BoardShape::tic_tac_toe()))
+    }
+
+    // Starts the game from an arbitrary position, the full game tree is large
+    // for tree-walking solvers.
+    pub fn with_state(start: TicTacToeState) -> Self {
+        TicTacToeGame { start }
+    }
+}
+
+impl Default for TicTacToeGame {
+    fn default() -> Self {
+        TicTacToeGame::new()
+    }
+}
+
+impl ExtensiveFormGame for TicTacToeGame {
+    type History = TicTacToeState;
+    type Action = TicTacToeAction;
+    type InfoSet = TicTacToeState;
+
+    fn root(&self) -> TicTacToeState {
+        self.start
+    }
+    fn node_kind(&self, history: &TicTacToeState) -> NodeKind {
+        if history.is_terminal() {
+            NodeKind::Terminal
+        } else {
+            NodeKind::Decision(history.current_player())
+        }
+    }
+    fn chance_outcomes(&self, _history: &TicTacToeState) -> Vec<(TicTacToeAction, ProbabilityT)> {
+        vec![]
+    }
+    fn actions(&self, history: &TicTacToeState) -> Vec<TicTacToeAction> {
+        history.actions()
+    }
+    fn info_set(&self, history: &TicTacToeState) -> TicTacToeState {
+        *history
+    }
+    fn apply(&self, history: &TicTacToeState, action: &TicTacToeAction) -> TicTacToeState {
+        history.apply_action(action)
+    }
+    // Like `TicTacToeEnvironment`, a win of crosses is +1 for the first
+    // player.
+    fn rewards(&self, history: &TicTacToeState) -> [RewardT; 2] {
+        let first = match history.has_winning_value() {
+            CellValue::Cross => RewardT(1.0),
+            CellValue::Circle => RewardT(-1.0),
+            CellValue::None => RewardT(0.0),
+        };
+        [first, Player::Second.perspective(first)]
+    }
+}
@@ -1,4 +1,8 @@
 pub mod action;
+pub mod board;
 pub mod cell;
 pub mod environment;
+pub mod game;
+pub mod random_opponent;
 pub mod state;
+pub mod symmetry;
@@ -0,0 +1,188 @@
+use crate::environment::{
+    ConstrainedDPEnvironment, CostT, CostedStateTransition, Environment, ProbabilityT, RewardT,
+    State, StateId, StateTransition,
+};
+use crate::tictactoe::action::TicTacToeAction;
+use crate::tictactoe::board::BoardShape;
+use crate::tictactoe::cell::CellValue;
+use crate::tictactoe::state::TicTacToeState;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::collections::{HashMap, HashSet};
+
+// The crosses play against circles that move uniformly at random, which
+// makes the game a single-agent MDP over the states with crosses to move.
+// Its cost channel marks unsafe moves: moves after which the circles could
+// win right away, whether or not they happen to find the winning move.
+#[derive(Debug)]
+pub struct RandomOpponentEnvironment {
+    state: TicTacToeState,
+    rng: StdRng,
+}
+
+impl RandomOpponentEnvironment {
+    pub fn new(seed: u64) -> Self {
+        RandomOpponentEnvironment {
+            state: TicTacToeState::empty(BoardShape::tic_tac_toe()),
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.state = TicTacToeState::empty(self.state.shape());
+    }
+
+    // Whether playing `action` lets the circles win with their next move.
+    pub fn is_unsafe(state: &TicTacToeState, action: &TicTacToeAction) -> bool {
+        let next = state.apply_action(action);
+        !next.is_terminal()
+            && next
+                .actions()
+                .iter()
+                .any(|reply| next.apply_action(reply).has_winning_value() == CellValue::Circle)
+    }
+
+    fn reward_for_state(state: &TicTacToeState) -> RewardT {
+        match state.has_winning_value() {
+            CellValue::Circle => RewardT(-1.0),
+            CellValue::Cross => RewardT(1.0),
+            CellValue::None => RewardT(0.0),
+        }
+    }
+
+    // The transitions of a state with crosses to move. States are identified
+    // by their canonical id under the board symmetries.
+    fn costed_transitions(state: &TicTacToeState) -> Vec<CostedStateTransition> {
+        let mut transitions = vec![];
+        for action in state.actions() {
+            let costs = vec![CostT(if Self::is_unsafe(state, &action) {
+                1.0
+            } else {
+                0.0
+            })];
+            let after_move = state.apply_action(&action);
+            let replies = after_move.actions();
+            let outcomes: Vec<(TicTacToeState, f64)> = if replies.is_empty() {
+                vec![(after_move, 1.0)]
+            } else {
+                let prob = 1.0 / replies.len() as f64;
+                replies
+                    .iter()
+                    .map(|reply| (after_move.apply_action(reply), prob))
+                    .collect()
+            };
+            for (next, prob) in outcomes {
+                transitions.push(CostedStateTransition {
+                    transition: StateTransition {
+                        action_id: action.id(),
+                        new_state_id: next.canonical_id(),
+                        reward: Self::reward_for_state(&next),
+                        prob: ProbabilityT(prob),
+                    },
+                    costs: costs.clone(),
+                });
+            }
+        }
+        transitions
+    }
+}
+
+impl Environment for RandomOpponentEnvironment {
+    type Action = TicTacToeAction;
+    type State = TicTacToeState;
+
+    fn state(&self) -> &TicTacToeState {
+        &self.state
+    }
+    fn actions(&self) -> Vec<TicTacToeAction> {
+        self.state.actions()
+    }
+    fn apply_action(&mut self, action: &TicTacToeAction) -> RewardT {
+        self.state = self.state.apply_action(action);
+        if let Some(reply) = self.state.actions().choose(&mut self.rng) {
+            self.state = self.state.apply_action(reply);
+        }
+        Self::reward_for_state(&self.state)
+    }
+}
+
+// Covers the canonical states reachable from the empty board. Action ids
+// refer to the canonical state, like with
+// `TicTacToeEnvironment::with_symmetry_reduction`.
+impl ConstrainedDPEnvironment for RandomOpponentEnvironment {
+    fn num_cost_channels(&self) -> usize {
+        1
+    }
+    fn costed_state_transitions(&self) -> HashMap<StateId, Vec<CostedStateTransition>> {
+        let shape = self.state.shape();
+        let start = TicTacToeState::empty(shape).canonical_id();
+        let mut table = HashMap::new();
+        let mut visited = HashSet::from([start]);
+        let mut stack = vec![start];
+        while let Some(state_id) = stack.pop() {
+            let state = TicTacToeState::create_state_with_id(shape, state_id).unwrap();
+            let transitions = Self::costed_transitions(&state);
+            for t in transitions.iter() {
+                if visited.insert(t.transition.new_state_id) {
+                    stack.push(t.transition.new_state_id);
+                }
+            }
+            table.insert(state_id, transitions);
+        }
+        table
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn unsafe_moves() {
+        // O O .
+        // X X .
+        // . . .
+        let state = [4, 0, 3, 1].iter().fold(
+            TicTacToeState::empty(BoardShape::tic_tac_toe()),
+            |state, &index| {
+                let value = state.actions()[0].value();
+                state.apply_action(&TicTacToeAction::new(value, index))
+            },
+        );
+        let blocking = TicTacToeAction::new(CellValue::Cross, 2);
+        let winning = TicTacToeAction::new(CellValue::Cross, 5);
+        let other = TicTacToeAction::new(CellValue::Cross, 8);
+        assert!(!RandomOpponentEnvironment::is_unsafe(&state, &blocking));
+        assert!(!RandomOpponentEnvironment::is_unsafe(&state, &winning));
+        assert!(RandomOpponentEnvironment::is_unsafe(&state, &other));
+    }
+
+    #[test]
+    fn transitions_of_the_random_opponent() {
+        let env = RandomOpponentEnvironment::new(0);
+        let table = env.costed_state_transitions();
+        for (state_id, transitions) in table.iter() {
+            let state =
+                TicTacToeState::create_state_with_id(BoardShape::tic_tac_toe(), *state_id).unwrap();
+            assert_eq!(state.is_terminal(), transitions.is_empty());
+            let total: f64 = transitions.iter().map(|t| t.transition.prob.0).sum();
+            assert!((total - state.actions().len() as f64).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn episodes_end_with_the_final_reward() {
+        let mut env = RandomOpponentEnvironment::new(3);
+        let mut reward = RewardT(0.0);
+        while !env.state().is_terminal() {
+            let action = env.actions()[0].clone();
+            reward = env.apply_action(&action);
+        }
+        let expected = RandomOpponentEnvironment::reward_for_state(env.state());
+        assert_eq!(reward, expected);
+        env.reset();
+        assert_eq!(env.actions().len(), 9);
+    }
+}
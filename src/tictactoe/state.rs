@@ -1,26 +1,34 @@
-use crate::environment::{State, StateId};
+use crate::environment::{Player, State, StateId};
 use crate::tictactoe::action::TicTacToeAction;
+use crate::tictactoe::board::{Bitboard, BoardKey, BoardShape};
 use crate::tictactoe::cell::{CellValue, CellValueId};
+use crate::tictactoe::symmetry::BoardTransform;
 use std::fmt;
 
-pub const GRID_SIZE: usize = 3;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TicTacToeState {
-    // The cells are aranged from left to right, from top to bottom.
-    cells: [CellValue; GRID_SIZE * GRID_SIZE],
+    shape: BoardShape,
+    // Bitboards of the crosses and circles, bit `i` stands for cell `i`. The
+    // cells are aranged from left to right, from top to bottom.
+    crosses: Bitboard,
+    circles: Bitboard,
+    // The id is updated incrementally with every action, it is only
+    // meaningful when the shape `has_state_ids`.
+    id: StateId,
+    // Cached result of the win detection, actions only have to check the
+    // lines through the cell they set.
+    winning_value: CellValue,
 }
 
 impl fmt::Display for TicTacToeState {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let num_dashes = GRID_SIZE * 2 + 1;
+        let num_dashes = self.shape.cols() * 2 + 1;
         let separator_line = "-".repeat(num_dashes) + "\n";
         f.write_str(&separator_line)?;
-        for i in 0..GRID_SIZE {
+        for i in 0..self.shape.rows() {
             f.write_str("|")?;
-            let row: Vec<String> = self.cells[i * GRID_SIZE..(i + 1) * GRID_SIZE]
-                .iter()
-                .map(|&cell| cell.into())
+            let row: Vec<String> = (i * self.shape.cols()..(i + 1) * self.shape.cols())
+                .map(|index| self.cell(index).into())
                 .collect();
             f.write_str(&row.join("|"))?;
             f.write_str("|\n")?;
@@ -31,70 +39,112 @@ impl fmt::Display for TicTacToeState {
 }
 
 impl TicTacToeState {
-    fn next_cell_value(&self) -> CellValue {
-        if self.is_terminal() {
-            return CellValue::None;
+    pub fn empty(shape: BoardShape) -> TicTacToeState {
+        TicTacToeState {
+            shape,
+            crosses: Bitboard::default(),
+            circles: Bitboard::default(),
+            id: StateId(0),
+            winning_value: CellValue::None,
         }
-        let num_set_cells = self
-            .cells
-            .iter()
-            .filter(|cell_value| cell_value.is_set())
-            .count();
-        if num_set_cells % 2 == 0 {
-            CellValue::Cross
-        } else {
-            CellValue::Circle
+    }
+
+    fn from_cells(shape: BoardShape, cells: &[CellValue]) -> TicTacToeState {
+        let mut state = TicTacToeState::empty(shape);
+        for (index, cell_value) in cells.iter().enumerate() {
+            state.set_cell(index, *cell_value);
         }
+        state.winning_value = state.find_winning_value();
+        state
     }
 
-    fn all_cells_set(&self) -> bool {
-        self.cells.iter().all(|c| c.is_set())
+    fn set_cell(&mut self, index: usize, value: CellValue) {
+        match value {
+            CellValue::Cross => self.crosses.set(index),
+            CellValue::Circle => self.circles.set(index),
+            CellValue::None => return,
+        }
+        if self.shape.has_state_ids() {
+            self.id.0 += value.value_id().0 * self.shape.cell_id_weight(index);
+        }
     }
 
-    pub fn has_winning_value(&self) -> CellValue {
-        // Check rows.
-        for i in 0..GRID_SIZE {
-            let first_cell = self.cells[i * GRID_SIZE];
-            if first_cell.is_set()
-                && self.cells[i * GRID_SIZE..i * GRID_SIZE + GRID_SIZE]
-                    .iter()
-                    .all(|c| *c == first_cell)
-            {
-                return first_cell;
+    // Checks every run on the board, for states that weren't built action by
+    // action.
+    fn find_winning_value(&self) -> CellValue {
+        for mask in self.shape.win_masks() {
+            if self.crosses.contains(mask) {
+                return CellValue::Cross;
+            }
+            if self.circles.contains(mask) {
+                return CellValue::Circle;
             }
         }
+        CellValue::None
+    }
 
-        // Check columns.
-        for i in 0..GRID_SIZE {
-            let first_cell = self.cells[i];
-            if first_cell.is_set()
-                && self.cells[i..]
-                    .iter()
-                    .step_by(GRID_SIZE)
-                    .all(|c| *c == self.cells[i])
-            {
-                return first_cell;
-            }
+    // Checks the runs of `win_length` cells through `index` for the value set
+    // there.
+    fn winning_value_through(&self, index: usize) -> CellValue {
+        let cells = match self.cell(index) {
+            CellValue::Cross => &self.crosses,
+            CellValue::Circle => &self.circles,
+            CellValue::None => return CellValue::None,
+        };
+        let win_masks = self.shape.win_masks();
+        if self
+            .shape
+            .lines_through_cell(index)
+            .iter()
+            .any(|&line| cells.contains(&win_masks[line]))
+        {
+            self.cell(index)
+        } else {
+            CellValue::None
         }
+    }
 
-        // Check the main diagonal.
-        let top_left_cell = self.cells[0];
-        if top_left_cell.is_set() {
-            if (1..GRID_SIZE).all(|i| self.cells[i * GRID_SIZE + i] == top_left_cell) {
-                return top_left_cell;
-            }
+    pub fn shape(&self) -> BoardShape {
+        self.shape
+    }
+
+    pub fn cell(&self, index: usize) -> CellValue {
+        if self.crosses.get(index) {
+            CellValue::Cross
+        } else if self.circles.get(index) {
+            CellValue::Circle
+        } else {
+            CellValue::None
         }
+    }
 
-        // Check the other diagonal.
-        let top_right_cell = self.cells[GRID_SIZE - 1];
-        if top_right_cell.is_set() {
-            if (1..GRID_SIZE)
-                .all(|i| self.cells[i * GRID_SIZE + GRID_SIZE - i - 1] == top_right_cell)
-            {
-                return top_right_cell;
-            }
+    fn next_cell_value(&self) -> CellValue {
+        if self.is_terminal() {
+            return CellValue::None;
+        }
+        match self.current_player() {
+            Player::First => CellValue::Cross,
+            Player::Second => CellValue::Circle,
+        }
+    }
+
+    // Crosses always move first, so the player to move only depends on the
+    // number of cells set so far.
+    pub fn current_player(&self) -> Player {
+        let num_set_cells = self.crosses.union(&self.circles).count_ones();
+        if num_set_cells.is_multiple_of(2) {
+            Player::First
+        } else {
+            Player::Second
         }
-        CellValue::None
+    }
+
+    fn all_cells_set(&self) -> bool {
+        self.crosses.union(&self.circles).count_ones() == self.shape.num_cells()
+    }
+
+    pub fn has_winning_value(&self) -> CellValue {
+        self.winning_value
     }
 
     pub fn apply_action(&self, action: &TicTacToeAction) -> TicTacToeState {
@@ -102,68 +152,102 @@ impl TicTacToeState {
             panic!("tried applying an action to a terminal state");
         }
 
-        if self.cells[action.index()].is_set() {
+        if action.index() >= self.shape.num_cells() {
+            panic!(
+                "action tried setting cell {} of a board with {} cells",
+                action.index(),
+                self.shape.num_cells()
+            );
+        }
+        if self.cell(action.index()).is_set() {
             panic!(
                 "action tried setting a cell that was already set. state={:?} action={:?}",
                 self, action
             );
         }
 
-        let mut new_cells = self.cells.clone();
-        new_cells[action.index()] = action.value();
-        TicTacToeState { cells: new_cells }
+        let mut new_state = *self;
+        new_state.set_cell(action.index(), action.value());
+        new_state.winning_value = new_state.winning_value_through(action.index());
+        new_state
     }
 
-    pub fn create_state_with_id(state_id: StateId) -> Option<TicTacToeState> {
-        if state_id.0 > TicTacToeState::max_state_id().0 {
+    pub fn create_state_with_id(shape: BoardShape, state_id: StateId) -> Option<TicTacToeState> {
+        if state_id.0 >= shape.max_state_id()?.0 {
             return None;
         }
 
-        const NUM_CELLS: usize = GRID_SIZE * GRID_SIZE;
-        let mut cells = [CellValue::None; NUM_CELLS];
+        let mut cells = vec![CellValue::None; shape.num_cells()];
         let mut state_id: usize = state_id.0;
-        for cell_id in 0..NUM_CELLS {
+        for cell in cells.iter_mut() {
             let value_id = state_id % CellValue::num_values();
-            cells[cell_id] = CellValue::value_with_id(CellValueId(value_id));
+            *cell = CellValue::value_with_id(CellValueId(value_id));
             state_id /= CellValue::num_values();
         }
-        Some(TicTacToeState { cells })
+        Some(TicTacToeState::from_cells(shape, &cells))
     }
 
+    // Panics for boards too large to be enumerated by a `StateId`, these have
+    // to be identified by `key` instead.
     pub fn id(&self) -> StateId {
-        let mut mult: usize = 1;
-        let mut state_id: usize = 0;
-        for cell_value in self.cells.iter() {
-            state_id += cell_value.value_id().0 * mult;
-            mult *= CellValue::num_values();
+        if !self.shape.has_state_ids() {
+            panic!("{:?} has too many states for a StateId", self.shape);
         }
-        StateId(state_id)
+        self.id
     }
 
-    pub fn actions(&self) -> Vec<TicTacToeAction> {
-        let mut actions = vec![];
-        if self.is_terminal() {
-            return actions;
+    pub fn key(&self) -> BoardKey {
+        BoardKey {
+            crosses: self.crosses,
+            circles: self.circles,
         }
+    }
 
-        let next_cell_value = self.next_cell_value();
-        for (index, cell_value) in self.cells.iter().enumerate() {
-            if !cell_value.is_set() {
-                actions.push(TicTacToeAction::new(next_cell_value, index))
-            }
+    pub fn transformed(&self, transform: BoardTransform) -> TicTacToeState {
+        let mut cells = vec![CellValue::None; self.shape.num_cells()];
+        for index in self.crosses.union(&self.circles).ones() {
+            cells[transform.apply_to_index(index, self.shape)] = self.cell(index);
         }
-        actions
+        TicTacToeState::from_cells(self.shape, &cells)
+    }
+
+    // Returns the representative of the state under the board symmetries,
+    // which is the variant with the smallest key, together with the transform
+    // mapping this state onto it. Actions of this state are mapped onto the
+    // canonical state with `TicTacToeAction::transformed`.
+    pub fn canonical(&self) -> (TicTacToeState, BoardTransform) {
+        BoardTransform::symmetries_of(self.shape)
+            .into_iter()
+            .map(|transform| (self.transformed(transform), transform))
+            .min_by_key(|(state, _)| state.key())
+            .unwrap()
     }
 
-    pub fn max_state_id() -> StateId {
-        let n = usize::pow(3, (GRID_SIZE * GRID_SIZE).try_into().unwrap());
-        StateId(n)
+    pub fn canonical_id(&self) -> StateId {
+        self.canonical().0.id()
+    }
+
+    pub fn canonical_key(&self) -> BoardKey {
+        self.canonical().0.key()
+    }
+
+    pub fn actions(&self) -> Vec<TicTacToeAction> {
+        if self.is_terminal() {
+            return vec![];
+        }
+
+        let next_cell_value = self.next_cell_value();
+        let set_cells = self.crosses.union(&self.circles);
+        (0..self.shape.num_cells())
+            .filter(|&index| !set_cells.get(index))
+            .map(|index| TicTacToeAction::new(next_cell_value, index))
+            .collect()
     }
 }
 
 impl State for TicTacToeState {
     fn is_terminal(&self) -> bool {
-        self.has_winning_value() != CellValue::None || self.all_cells_set()
+        self.winning_value != CellValue::None || self.all_cells_set()
     }
 }
 
@@ -175,15 +259,30 @@ mod tests {
     use crate::tictactoe::action::TicTacToeAction;
     use itertools::sorted;
     use pretty_assertions::assert_eq;
+    use std::collections::HashSet;
+
+    const GRID_SIZE: usize = 3;
 
     fn make_state(cell_chars: [[char; GRID_SIZE]; GRID_SIZE]) -> TicTacToeState {
-        let cells: Vec<CellValue> = cell_chars
+        let rows: Vec<String> = cell_chars.iter().map(|row| row.iter().collect()).collect();
+        let rows: Vec<&str> = rows.iter().map(|row| row.as_str()).collect();
+        make_board(&rows, GRID_SIZE)
+    }
+
+    fn make_board(rows: &[&str], win_length: usize) -> TicTacToeState {
+        let shape = BoardShape::new(rows.len(), rows[0].len(), win_length);
+        let cells: Vec<CellValue> = rows
             .iter()
-            .flat_map(|row| row.iter())
-            .map(|c| CellValue::try_from(*c).unwrap())
+            .flat_map(|row| row.chars())
+            .map(|c| CellValue::try_from(c).unwrap())
             .collect();
-        let cells: [CellValue; GRID_SIZE * GRID_SIZE] = cells.try_into().unwrap();
-        TicTacToeState { cells }
+        TicTacToeState::from_cells(shape, &cells)
+    }
+
+    fn tic_tac_toe_states() -> impl Iterator<Item = TicTacToeState> {
+        let shape = BoardShape::tic_tac_toe();
+        (0..shape.max_state_id().unwrap().0)
+            .map(move |id| TicTacToeState::create_state_with_id(shape, StateId(id)).unwrap())
     }
 
     #[test]
@@ -579,12 +678,134 @@ mod tests {
         assert_eq!(actions, vec![]);
     }
 
+    #[test]
+    fn canonical_is_shared_by_symmetric_states() {
+        let state = make_state([['x', 'o', ' '], [' ', ' ', ' '], [' ', ' ', ' ']]);
+        let rotated = make_state([[' ', ' ', 'x'], [' ', ' ', 'o'], [' ', ' ', ' ']]);
+        let mirrored = make_state([['x', ' ', ' '], ['o', ' ', ' '], [' ', ' ', ' ']]);
+        assert_eq!(state.canonical_id(), rotated.canonical_id());
+        assert_eq!(state.canonical_id(), mirrored.canonical_id());
+
+        let other = make_state([['x', ' ', ' '], [' ', 'o', ' '], [' ', ' ', ' ']]);
+        assert!(state.canonical_id() != other.canonical_id());
+    }
+
+    #[test]
+    fn canonical_transform_maps_actions() {
+        let state = make_state([[' ', ' ', 'x'], [' ', ' ', 'o'], [' ', ' ', ' ']]);
+        let (canonical, transform) = state.canonical();
+        assert_eq!(state.transformed(transform).id(), canonical.id());
+
+        for action in state.actions() {
+            let mapped = action.transformed(transform, state.shape());
+            assert_eq!(
+                state.apply_action(&action).transformed(transform).id(),
+                canonical.apply_action(&mapped).id()
+            );
+        }
+    }
+
+    #[test]
+    fn number_of_canonical_states() {
+        let canonical_ids: HashSet<StateId> = tic_tac_toe_states()
+            .map(|state| state.canonical_id())
+            .collect();
+        // Burnside's lemma: (3^9 + 2 * 3^3 + 3^5 + 4 * 3^6) / 8.
+        assert_eq!(canonical_ids.len(), 2862);
+    }
+
+    #[test]
+    fn incremental_updates_match_full_scan() {
+        for state in tic_tac_toe_states() {
+            for action in state.actions() {
+                let new_state = state.apply_action(&action);
+                let rescanned =
+                    TicTacToeState::create_state_with_id(state.shape(), new_state.id()).unwrap();
+                assert_eq!(new_state, rescanned, "state={:#}", new_state);
+            }
+        }
+    }
+
     #[test]
     fn ids_are_bijective() {
-        for id in 0..TicTacToeState::max_state_id().0 {
-            let id = StateId(id);
-            let state = TicTacToeState::create_state_with_id(id).unwrap();
-            assert_eq!(id, state.id(), "state={:#}", state);
+        for (id, state) in tic_tac_toe_states().enumerate() {
+            assert_eq!(StateId(id), state.id(), "state={:#}", state);
+        }
+    }
+
+    #[test]
+    fn fmt_non_square() {
+        let state = make_board(&["x o ", " ox "], 2);
+        assert_eq!(
+            state.to_string(),
+            "\
+---------
+|x| |o| |
+---------
+| |o|x| |
+---------
+"
+        );
+    }
+
+    #[test]
+    fn has_winning_value_win_length_shorter_than_board() {
+        // Diagonals of length `win_length` away from the main diagonals count.
+        let state = make_board(&["    ", "x   ", " x  ", "  x "], 3);
+        assert_eq!(state.has_winning_value(), CellValue::Cross);
+
+        let state = make_board(&["   o", "  o ", " o  ", "    "], 3);
+        assert_eq!(state.has_winning_value(), CellValue::Circle);
+
+        let state = make_board(&["xx x", "oo  ", "    ", "    "], 3);
+        assert_eq!(state.has_winning_value(), CellValue::None);
+        assert!(!state.is_terminal());
+    }
+
+    #[test]
+    fn has_winning_value_non_square() {
+        let state = make_board(&["     ", " oooo", "xxx x"], 4);
+        assert_eq!(state.has_winning_value(), CellValue::Circle);
+
+        // Columns are shorter than the win length.
+        let state = make_board(&["x    ", "x    ", "x    "], 4);
+        assert_eq!(state.has_winning_value(), CellValue::None);
+    }
+
+    #[test]
+    fn gomoku_uses_keys() {
+        let shape = BoardShape::gomoku();
+        let mut state = TicTacToeState::empty(shape);
+        assert_eq!(state.actions().len(), 225);
+
+        // Crosses play down the diagonal ending in the bottom-right corner,
+        // circles play along the top row.
+        let mut keys = HashSet::from([state.key()]);
+        for i in 0..5 {
+            let cross = TicTacToeAction::new(CellValue::Cross, (10 + i) * 15 + 10 + i);
+            state = state.apply_action(&cross);
+            assert!(keys.insert(state.key()));
+            if state.is_terminal() {
+                break;
+            }
+            let circle = TicTacToeAction::new(CellValue::Circle, i);
+            state = state.apply_action(&circle);
+            assert!(keys.insert(state.key()));
         }
+        assert_eq!(state.has_winning_value(), CellValue::Cross);
+        assert_eq!(state.cell(224), CellValue::Cross);
+    }
+
+    #[test]
+    #[should_panic(expected = "too many states")]
+    fn gomoku_has_no_state_ids() {
+        TicTacToeState::empty(BoardShape::gomoku()).id();
+    }
+
+    #[test]
+    #[should_panic(expected = "tried setting cell 9 of a board with 9 cells")]
+    fn actions_outside_the_board() {
+        TicTacToeState::empty(BoardShape::tic_tac_toe())
+            .apply_action(&TicTacToeAction::new(CellValue::Cross, 9));
     }
 }
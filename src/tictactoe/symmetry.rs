@@ -0,0 +1,141 @@
+use crate::tictactoe::board::BoardShape;
+
+// The eight symmetries of a square board. Rotations are clockwise. Non-square
+// boards only have the symmetries that keep their dimensions, see
+// `BoardTransform::symmetries_of`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BoardTransform {
+    Identity,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    // Mirrors the columns.
+    FlipHorizontal,
+    // Mirrors the rows.
+    FlipVertical,
+    // Transposes the board along the top-left to bottom-right diagonal.
+    FlipMainDiagonal,
+    // Transposes the board along the top-right to bottom-left diagonal.
+    FlipAntiDiagonal,
+}
+
+impl BoardTransform {
+    pub fn all() -> [BoardTransform; 8] {
+        [
+            BoardTransform::Identity,
+            BoardTransform::Rotate90,
+            BoardTransform::Rotate180,
+            BoardTransform::Rotate270,
+            BoardTransform::FlipHorizontal,
+            BoardTransform::FlipVertical,
+            BoardTransform::FlipMainDiagonal,
+            BoardTransform::FlipAntiDiagonal,
+        ]
+    }
+
+    pub fn symmetries_of(shape: BoardShape) -> Vec<BoardTransform> {
+        BoardTransform::all()
+            .into_iter()
+            .filter(|transform| shape.is_square() || transform.keeps_dimensions())
+            .collect()
+    }
+
+    fn keeps_dimensions(&self) -> bool {
+        matches!(
+            self,
+            BoardTransform::Identity
+                | BoardTransform::Rotate180
+                | BoardTransform::FlipHorizontal
+                | BoardTransform::FlipVertical
+        )
+    }
+
+    // Returns the index the cell at `index` of a board with the given shape is
+    // moved to.
+    pub fn apply_to_index(&self, index: usize, shape: BoardShape) -> usize {
+        if !shape.is_square() && !self.keeps_dimensions() {
+            panic!("{:?} is not a symmetry of {:?}", self, shape);
+        }
+        let (last_row, last_col) = (shape.rows() - 1, shape.cols() - 1);
+        let (row, col) = (index / shape.cols(), index % shape.cols());
+        let (new_row, new_col) = match self {
+            BoardTransform::Identity => (row, col),
+            BoardTransform::Rotate90 => (col, last_row - row),
+            BoardTransform::Rotate180 => (last_row - row, last_col - col),
+            BoardTransform::Rotate270 => (last_col - col, row),
+            BoardTransform::FlipHorizontal => (row, last_col - col),
+            BoardTransform::FlipVertical => (last_row - row, col),
+            BoardTransform::FlipMainDiagonal => (col, row),
+            BoardTransform::FlipAntiDiagonal => (last_col - col, last_row - row),
+        };
+        new_row * shape.cols() + new_col
+    }
+
+    pub fn inverse(&self) -> BoardTransform {
+        match self {
+            BoardTransform::Rotate90 => BoardTransform::Rotate270,
+            BoardTransform::Rotate270 => BoardTransform::Rotate90,
+            other => *other,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    const NUM_CELLS: usize = 9;
+
+    #[test]
+    fn transforms_are_permutations() {
+        for shape in [BoardShape::tic_tac_toe(), BoardShape::new(3, 5, 3)] {
+            for transform in BoardTransform::symmetries_of(shape) {
+                let mut indices: Vec<usize> = (0..shape.num_cells())
+                    .map(|i| transform.apply_to_index(i, shape))
+                    .collect();
+                indices.sort();
+                assert_eq!(indices, (0..shape.num_cells()).collect::<Vec<usize>>());
+            }
+        }
+    }
+
+    #[test]
+    fn symmetries_of_non_square_board() {
+        assert_eq!(
+            BoardTransform::symmetries_of(BoardShape::new(2, 3, 2)),
+            vec![
+                BoardTransform::Identity,
+                BoardTransform::Rotate180,
+                BoardTransform::FlipHorizontal,
+                BoardTransform::FlipVertical
+            ]
+        );
+    }
+
+    #[test]
+    fn inverse_undoes_transform() {
+        let shape = BoardShape::tic_tac_toe();
+        for transform in BoardTransform::all() {
+            for i in 0..NUM_CELLS {
+                assert_eq!(
+                    transform
+                        .inverse()
+                        .apply_to_index(transform.apply_to_index(i, shape), shape),
+                    i,
+                    "transform={:?}",
+                    transform
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_90() {
+        let indices: Vec<usize> = (0..NUM_CELLS)
+            .map(|i| BoardTransform::Rotate90.apply_to_index(i, BoardShape::tic_tac_toe()))
+            .collect();
+        // The top-left corner moves to the top-right corner.
+        assert_eq!(indices, vec![2, 5, 8, 1, 4, 7, 0, 3, 6]);
+    }
+}